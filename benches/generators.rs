@@ -2,7 +2,7 @@ use bulletproofs::{BulletproofGens, PedersenGens};
 
 #[macro_use]
 extern crate criterion;
-use criterion::Criterion;
+use criterion::{BatchSize, Criterion};
 
 fn pc_gens(c: &mut Criterion) {
     c.bench_function("PedersenGens::new", |b| b.iter(|| PedersenGens::default()));
@@ -16,9 +16,63 @@ fn bp_gens(c: &mut Criterion) {
     );
 }
 
+fn bp_gens_extend(c: &mut Criterion) {
+    c.bench_function("BulletproofGens::new(64, 16)", |b| {
+        b.iter(|| BulletproofGens::new(64, 16))
+    });
+    let gens = BulletproofGens::new(64, 8);
+    c.bench_function("BulletproofGens::extend (64, 8) to (64, 16)", move |b| {
+        b.iter_batched(
+            || gens.clone(),
+            |mut gens| {
+                gens.extend(64, 16).unwrap();
+                gens
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// Compares computing the generators on one thread and on the
+/// default thread pool.
+#[cfg(feature = "rayon")]
+fn bp_gens_parallel(c: &mut Criterion) {
+    let sequential = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    for party_capacity in [16, 64] {
+        c.bench_function(
+            &format!("BulletproofGens::new(64, {}) sequential", party_capacity),
+            |b| b.iter(|| sequential.install(|| BulletproofGens::new(64, party_capacity))),
+        );
+        c.bench_function(
+            &format!("BulletproofGens::new(64, {}) parallel", party_capacity),
+            |b| b.iter(|| BulletproofGens::new(64, party_capacity)),
+        );
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+fn bp_gens_parallel(_: &mut Criterion) {}
+
+fn bp_gens_from_bytes(c: &mut Criterion) {
+    let bytes = BulletproofGens::new(64, 16).to_bytes();
+    let validated_bytes = bytes.clone();
+    c.bench_function("BulletproofGens::from_bytes (64, 16)", move |b| {
+        b.iter(|| BulletproofGens::from_bytes(&bytes).unwrap())
+    });
+    c.bench_function("BulletproofGens::from_bytes_validated (64, 16)", move |b| {
+        b.iter(|| BulletproofGens::from_bytes_validated(&validated_bytes).unwrap())
+    });
+}
+
 criterion_group! {
     bp,
     bp_gens,
+    bp_gens_extend,
+    bp_gens_parallel,
+    bp_gens_from_bytes,
     pc_gens,
 }
 
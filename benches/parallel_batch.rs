@@ -0,0 +1,102 @@
+#![allow(non_snake_case)]
+#[macro_use]
+extern crate criterion;
+use criterion::Criterion;
+
+use rand;
+use rand::Rng;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use bulletproofs::RangeProof;
+use bulletproofs::{BulletproofGens, PedersenGens};
+
+fn prove_batch(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    n: usize,
+    m: usize,
+    count: usize,
+) -> Vec<(RangeProof, Vec<CompressedRistretto>)> {
+    let mut rng = rand::thread_rng();
+    (0..count)
+        .map(|_| {
+            let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
+            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
+            let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+            let mut transcript = Transcript::new(b"ParallelBatchBenchmark");
+            RangeProof::prove_multiple(bp_gens, pc_gens, &mut transcript, &values, &blindings, n)
+                .unwrap()
+        })
+        .collect()
+}
+
+/// Compares serial and parallel verification of `count` proofs with
+/// bitsize `n` and aggregation size `m`.
+fn verify_batch_helper(n: usize, m: usize, count: usize, c: &mut Criterion) {
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(n, m);
+    let proofs = prove_batch(&bp_gens, &pc_gens, n, m, count);
+    let name = format!("{}_{}_x{}", n, m, count);
+
+    let serial_proofs = proofs.clone();
+    let serial_gens = bp_gens.clone();
+    c.bench_function(&format!("Batch rangeproof verify {}", name), move |b| {
+        b.iter(|| {
+            let mut transcripts =
+                vec![Transcript::new(b"ParallelBatchBenchmark"); serial_proofs.len()];
+            assert!(RangeProof::verify_batch(
+                serial_proofs.iter().zip(&mut transcripts).map(
+                    |((proof, commitments), transcript)| {
+                        proof.verification_view(transcript, commitments, n)
+                    }
+                ),
+                &serial_gens,
+                &pc_gens,
+            )
+            .is_ok());
+        })
+    });
+
+    c.bench_function(
+        &format!("Parallel batch rangeproof verify {}", name),
+        move |b| {
+            b.iter(|| {
+                let mut transcripts =
+                    vec![Transcript::new(b"ParallelBatchBenchmark"); proofs.len()];
+                assert!(RangeProof::verify_batch_par(
+                    proofs.iter().zip(&mut transcripts).map(
+                        |((proof, commitments), transcript)| {
+                            proof.verification_view(transcript, commitments, n)
+                        }
+                    ),
+                    &bp_gens,
+                    &pc_gens,
+                )
+                .is_ok());
+            })
+        },
+    );
+}
+
+fn verify_batch_64_2_x32(c: &mut Criterion) {
+    verify_batch_helper(64, 2, 32, c);
+}
+
+fn verify_batch_64_2_x64(c: &mut Criterion) {
+    verify_batch_helper(64, 2, 64, c);
+}
+
+criterion_group! {
+    name = parallel_batch_verify;
+    config = Criterion::default();
+    targets =
+    verify_batch_64_2_x32,
+    verify_batch_64_2_x64,
+}
+
+criterion_main!(parallel_batch_verify);
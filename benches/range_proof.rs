@@ -6,14 +6,16 @@ use criterion::Criterion;
 use rand;
 use rand::Rng;
 
+use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::scalar::Scalar;
 
 use merlin::Transcript;
 
-use bulletproofs::RangeProof;
 use bulletproofs::{BulletproofGens, PedersenGens};
+use bulletproofs::{RangeProof, RangeProofVerificationKey};
 
 static AGGREGATION_SIZES: [usize; 6] = [1, 2, 4, 8, 16, 32];
+static LARGE_AGGREGATION_SIZES: [usize; 3] = [32, 64, 128];
 
 fn create_aggregated_rangeproof_helper(n: usize, c: &mut Criterion) {
     let label = format!("Aggregated {}-bit rangeproof creation", n);
@@ -63,6 +65,38 @@ fn create_aggregated_rangeproof_n_64(c: &mut Criterion) {
     create_aggregated_rangeproof_helper(64, c);
 }
 
+/// Proof creation for large aggregations, where the parties' work
+/// dominates.  Compare runs with and without the `rayon` feature to
+/// see the scaling of parallel proving.
+fn create_large_aggregated_rangeproof_n_64(c: &mut Criterion) {
+    let n = 64;
+    c.bench_function_over_inputs(
+        "Large aggregated 64-bit rangeproof creation",
+        move |b, &&m| {
+            let pc_gens = PedersenGens::default();
+            let bp_gens = BulletproofGens::new(n, m);
+            let mut rng = rand::thread_rng();
+
+            let values: Vec<u64> = (0..m).map(|_| rng.gen()).collect();
+            let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+            b.iter(|| {
+                let mut transcript = Transcript::new(b"AggregateRangeProofBenchmark");
+
+                RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+            })
+        },
+        &LARGE_AGGREGATION_SIZES,
+    );
+}
+
 criterion_group! {
     name = create_rp;
     config = Criterion::default().sample_size(10);
@@ -71,6 +105,7 @@ criterion_group! {
     create_aggregated_rangeproof_n_16,
     create_aggregated_rangeproof_n_32,
     create_aggregated_rangeproof_n_64,
+    create_large_aggregated_rangeproof_n_64,
 }
 
 fn verify_aggregated_rangeproof_helper(n: usize, c: &mut Criterion) {
@@ -125,6 +160,46 @@ fn verify_aggregated_rangeproof_n_64(c: &mut Criterion) {
     verify_aggregated_rangeproof_helper(64, c);
 }
 
+fn verify_compressed_rangeproof_n_64(c: &mut Criterion) {
+    let (n, m) = (64, 8);
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(n, m);
+    let mut rng = rand::thread_rng();
+
+    let values: Vec<u64> = (0..m).map(|_| rng.gen()).collect();
+    let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+    let mut transcript = Transcript::new(b"AggregateRangeProofBenchmark");
+    let (proof, value_commitments) =
+        RangeProof::prove_multiple(&bp_gens, &pc_gens, &mut transcript, &values, &blindings, n)
+            .unwrap();
+
+    c.bench_function(
+        "Aggregated 64-bit rangeproof verification (m = 8, generic)",
+        |b| {
+            b.iter(|| {
+                let mut transcript = Transcript::new(b"AggregateRangeProofBenchmark");
+                proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+            })
+        },
+    );
+    c.bench_function(
+        "Aggregated 64-bit rangeproof verification (m = 8, compressed)",
+        |b| {
+            b.iter(|| {
+                let mut transcript = Transcript::new(b"AggregateRangeProofBenchmark");
+                proof.verify_multiple_compressed(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    n,
+                )
+            })
+        },
+    );
+}
+
 criterion_group! {
     name = verify_rp;
     config = Criterion::default();
@@ -133,6 +208,38 @@ criterion_group! {
     verify_aggregated_rangeproof_n_16,
     verify_aggregated_rangeproof_n_32,
     verify_aggregated_rangeproof_n_64,
+    verify_compressed_rangeproof_n_64,
+}
+
+/// Creates a proof for each `(n, m)` in `nm`, returning the proofs
+/// along with their value commitments and bitsizes.
+fn prove_batch(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    nm: &[(usize, usize)],
+) -> Vec<(RangeProof, Vec<CompressedRistretto>, usize)> {
+    nm.iter()
+        .map(|&(n, m)| {
+            let mut rng = rand::thread_rng();
+
+            let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
+            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
+            let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+            let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+            let (proof, value_commitments) = RangeProof::prove_multiple(
+                bp_gens,
+                pc_gens,
+                &mut transcript,
+                &values,
+                &blindings,
+                n,
+            )
+            .unwrap();
+
+            (proof, value_commitments, n)
+        })
+        .collect()
 }
 
 fn verify_aggregated_rangeproof_batch_helper(
@@ -149,29 +256,7 @@ fn verify_aggregated_rangeproof_batch_helper(
         let bp_gens = BulletproofGens::new(max_bitsize, max_parties);
 
         // Provers
-        let proofs: Vec<_> = nm
-            .iter()
-            .map(|&(n, m)| {
-                let mut rng = rand::thread_rng();
-
-                let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
-                let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
-                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
-
-                let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
-                let (proof, value_commitments) = RangeProof::prove_multiple(
-                    &bp_gens,
-                    &pc_gens,
-                    &mut transcript,
-                    &values,
-                    &blindings,
-                    n,
-                )
-                .unwrap();
-
-                (proof, value_commitments, n)
-            })
-            .collect();
+        let proofs = prove_batch(&bp_gens, &pc_gens, nm);
 
         let mut rng = rand::thread_rng();
         b.iter(|| {
@@ -196,6 +281,47 @@ fn verify_aggregated_rangeproof_batch_helper(
     });
 }
 
+/// Like `verify_aggregated_rangeproof_batch_helper`, but verifies with
+/// a verification key built once for proofs of up to `m` values of
+/// bitsize `n`.
+fn verify_precomputed_batch_helper(
+    name: &'static str,
+    nm: &[(usize, usize)],
+    (n, m): (usize, usize),
+    c: &mut Criterion,
+) {
+    let label = format!("Batch rangeproof verify precomputed {}", name);
+
+    c.bench_function(&label, move |b| {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 8);
+        let key = RangeProofVerificationKey::new(&bp_gens, &pc_gens, n, m).unwrap();
+
+        // Provers
+        let proofs = prove_batch(&bp_gens, &pc_gens, nm);
+
+        let mut rng = rand::thread_rng();
+        b.iter(|| {
+            let mut transcripts = proofs
+                .iter()
+                .map(|_| Transcript::new(b"AggregatedRangeProofTest"))
+                .collect::<Vec<_>>();
+
+            assert!(RangeProof::verify_batch_precomputed_with_rng(
+                proofs
+                    .iter()
+                    .zip(&mut transcripts)
+                    .map(|((proof, commitments, n), transcript)| {
+                        proof.verification_view(transcript, commitments, *n)
+                    }),
+                &key,
+                &mut rng
+            )
+            .is_ok());
+        });
+    });
+}
+
 fn verify_batch_32_1_64_4_64_2_64_1(c: &mut Criterion) {
     verify_aggregated_rangeproof_batch_helper(
         "32_1_64_4_64_2_64_1",
@@ -208,12 +334,81 @@ fn verify_batch_64_2_x32(c: &mut Criterion) {
     verify_aggregated_rangeproof_batch_helper("64_2_x32", &vec![(64, 2); 32], c);
 }
 
+fn verify_batch_precomputed_64_2_x32(c: &mut Criterion) {
+    verify_precomputed_batch_helper("64_2_x32", &vec![(64, 2); 32], (64, 2), c);
+}
+
+/// Compares the nested and flat layouts of the batch verifier's
+/// generator scalars, accumulating the scalars of `count` proofs with
+/// bitsize `n` and aggregation size `m`.
+fn batch_collector_layout_helper(n: usize, m: usize, count: usize, c: &mut Criterion) {
+    let name = format!("{}_{}_x{}", n, m, count);
+    let mut rng = rand::thread_rng();
+    let scalars: Vec<Scalar> = (0..n * m).map(|_| Scalar::random(&mut rng)).collect();
+
+    let nested_scalars = scalars.clone();
+    c.bench_function(
+        &format!("Batch collector nested layout {}", name),
+        move |b| {
+            b.iter(|| {
+                let mut acc: Vec<Vec<Scalar>> = vec![];
+                for _ in 0..count {
+                    acc.resize_with(m, || vec![]);
+                    for v in &mut acc {
+                        v.resize(n, Scalar::ZERO);
+                    }
+                    let mut s = nested_scalars.iter();
+                    for cur_m in 0..m {
+                        for cur_n in 0..n {
+                            acc[cur_m][cur_n] += *s.next().unwrap();
+                        }
+                    }
+                }
+                acc.into_iter().flatten().collect::<Vec<_>>()
+            })
+        },
+    );
+
+    c.bench_function(&format!("Batch collector flat layout {}", name), move |b| {
+        b.iter(|| {
+            let mut acc: Vec<Scalar> = vec![];
+            for _ in 0..count {
+                acc.resize(n * m, Scalar::ZERO);
+                let mut s = scalars.iter();
+                for cur_m in 0..m {
+                    let offset = cur_m * n;
+                    for idx in offset..offset + n {
+                        acc[idx] += *s.next().unwrap();
+                    }
+                }
+            }
+            acc
+        })
+    });
+}
+
+fn batch_collector_layout_64_2_x32(c: &mut Criterion) {
+    batch_collector_layout_helper(64, 2, 32, c);
+}
+
+fn batch_collector_layout_64_8_x256(c: &mut Criterion) {
+    batch_collector_layout_helper(64, 8, 256, c);
+}
+
+fn verify_batch_64_2_x256(c: &mut Criterion) {
+    verify_aggregated_rangeproof_batch_helper("64_2_x256", &vec![(64, 2); 256], c);
+}
+
 criterion_group! {
     name = batch_verify;
     config = Criterion::default();
     targets =
     verify_batch_64_2_x32,
+    verify_batch_precomputed_64_2_x32,
     verify_batch_32_1_64_4_64_2_64_1,
+    verify_batch_64_2_x256,
+    batch_collector_layout_64_2_x32,
+    batch_collector_layout_64_8_x256,
 }
 
 criterion_main!(create_rp, verify_rp, batch_verify);
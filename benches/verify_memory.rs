@@ -0,0 +1,205 @@
+#![allow(non_snake_case)]
+#[macro_use]
+extern crate criterion;
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{Criterion, Throughput};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use rand;
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use bulletproofs::RangeProof;
+use bulletproofs::{BulletproofGens, PedersenGens};
+
+static LARGE_AGGREGATION_SIZES: [usize; 3] = [32, 128, 512];
+
+/// Forwards to the system allocator, tracking the bytes held by the
+/// current thread while measuring is enabled, like the allocator of
+/// the `allocations` test.
+struct TrackingAllocator;
+
+thread_local! {
+    // The current and peak bytes allocated since measuring was
+    // enabled, or `None` while it is disabled.
+    static MEMORY: Cell<Option<(isize, isize)>> = const { Cell::new(None) };
+}
+
+fn record_bytes(bytes: isize) {
+    let _ = MEMORY.try_with(|memory| {
+        if let Some((current, peak)) = memory.get() {
+            let current = current + bytes;
+            memory.set(Some((current, peak.max(current))));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_bytes(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_bytes(layout.size() as isize);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // The old and new buffers may both be live while copying.
+        record_bytes(new_size as isize);
+        record_bytes(-(layout.size() as isize));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record_bytes(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+/// Runs `f`, returning its result and the peak number of bytes it held
+/// allocated on the current thread.
+fn peak_memory<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    MEMORY.with(|memory| memory.set(Some((0, 0))));
+    let result = f();
+    let (_, peak) = MEMORY.with(|memory| memory.replace(None)).unwrap();
+    (result, peak as usize)
+}
+
+/// A criterion measurement of bytes, reported by the benchmarks with
+/// `iter_custom` instead of being timed.
+struct PeakBytes;
+
+impl Measurement for PeakBytes {
+    type Intermediate = ();
+    type Value = usize;
+
+    fn start(&self) -> Self::Intermediate {}
+
+    fn end(&self, _: Self::Intermediate) -> Self::Value {
+        0
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &BytesFormatter
+    }
+}
+
+struct BytesFormatter;
+
+impl ValueFormatter for BytesFormatter {
+    fn scale_values(&self, typical_value: f64, values: &mut [f64]) -> &'static str {
+        let (factor, unit) = if typical_value < 1024.0 {
+            (1.0, "B")
+        } else if typical_value < 1024.0 * 1024.0 {
+            (1024.0, "KiB")
+        } else {
+            (1024.0 * 1024.0, "MiB")
+        };
+        for value in values {
+            *value /= factor;
+        }
+        unit
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        _throughput: &Throughput,
+        _values: &mut [f64],
+    ) -> &'static str {
+        "B"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "B"
+    }
+}
+
+/// Measures the peak heap memory of verifying an aggregated proof of
+/// `m` values of bitsize `n`.  Only the current thread is tracked, so
+/// run this without the `rayon` feature.
+fn verify_peak_memory_helper(n: usize, c: &mut Criterion<PeakBytes>) {
+    let label = format!("Aggregated {}-bit rangeproof verification peak memory", n);
+
+    c.bench_function_over_inputs(
+        &label,
+        move |b, &&m| {
+            let pc_gens = PedersenGens::default();
+            let bp_gens = BulletproofGens::new(n, m);
+            let mut rng = rand::thread_rng();
+
+            let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
+            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
+            let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+            let mut transcript = Transcript::new(b"AggregateRangeProofBenchmark");
+            let (proof, value_commitments) = RangeProof::prove_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &values,
+                &blindings,
+                n,
+            )
+            .unwrap();
+
+            // Criterion divides the result by the number of iterations,
+            // so sum the peaks to report their mean.
+            b.iter_custom(|iters| {
+                (0..iters)
+                    .map(|_| {
+                        // Each proof verification requires a clean transcript.
+                        let mut transcript = Transcript::new(b"AggregateRangeProofBenchmark");
+                        let (result, peak) = peak_memory(|| {
+                            proof.verify_multiple(
+                                &bp_gens,
+                                &pc_gens,
+                                &mut transcript,
+                                &value_commitments,
+                                n,
+                            )
+                        });
+                        assert!(result.is_ok());
+                        peak
+                    })
+                    .sum()
+            })
+        },
+        &LARGE_AGGREGATION_SIZES,
+    );
+}
+
+fn verify_peak_memory_n_64(c: &mut Criterion<PeakBytes>) {
+    verify_peak_memory_helper(64, c);
+}
+
+criterion_group! {
+    name = verify_memory;
+    config = Criterion::default().with_measurement(PeakBytes).sample_size(10);
+    targets =
+    verify_peak_memory_n_64,
+}
+
+criterion_main!(verify_memory);
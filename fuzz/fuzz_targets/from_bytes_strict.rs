@@ -0,0 +1,13 @@
+#![no_main]
+
+use bulletproofs::{ProofError, RangeProof};
+use libfuzzer_sys::fuzz_target;
+
+// Decoding arbitrary bytes must never panic, and must either yield a
+// proof whose points all decompress or fail with a format error.
+fuzz_target!(|data: &[u8]| {
+    match RangeProof::from_bytes_strict(data) {
+        Ok(proof) => assert_eq!(proof.to_bytes(), data),
+        Err(e) => assert_eq!(e, ProofError::FormatError),
+    }
+});
@@ -7,8 +7,12 @@ use alloc::vec::Vec;
 use thiserror::Error;
 
 /// Represents an error in proof creation, verification, or parsing.
+///
+/// New variants may be added in minor releases, so matches on this
+/// type need a wildcard arm.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "std", derive(Error))]
+#[non_exhaustive]
 pub enum ProofError {
     /// This error occurs when a proof failed to verify.
     #[cfg_attr(feature = "std", error("Proof verification failed."))]
@@ -21,14 +25,15 @@ pub enum ProofError {
     #[cfg_attr(feature = "std", error("Wrong number of blinding factors supplied."))]
     WrongNumBlindingFactors,
     /// This error occurs when attempting to create a proof with
-    /// bitsize other than \\(8\\), \\(16\\), \\(32\\), or \\(64\\).
-    #[cfg_attr(feature = "std", error("Invalid bitsize, must have n = 8,16,32,64."))]
+    /// bitsize outside \\(1 \leq n \leq 128\\).
+    #[cfg_attr(feature = "std", error("Invalid bitsize, must have 1 <= n <= 128."))]
     InvalidBitsize,
-    /// This error occurs when attempting to create an aggregated
-    /// proof with non-power-of-two aggregation size.
+    /// This error occurs when attempting to create or verify an
+    /// aggregated proof of no values, or of more values than the
+    /// generators' party capacity once padded to a power of two.
     #[cfg_attr(
         feature = "std",
-        error("Invalid aggregation size, m must be a power of 2.")
+        error("Invalid aggregation size, must have 1 <= m <= party capacity.")
     )]
     InvalidAggregation,
     /// This error occurs when there are insufficient generators for the proof.
@@ -43,6 +48,98 @@ pub enum ProofError {
         error("Invalid input size, incorrect input length for proof")
     )]
     InvalidInputLength,
+    /// This error occurs when the bounds of a range are invalid, for
+    /// instance when the range is empty or too wide for the bitsize.
+    #[cfg_attr(feature = "std", error("Invalid range bounds."))]
+    InvalidRange,
+    /// This error occurs when a value to be proven lies outside of the
+    /// range supported by the proof.
+    #[cfg_attr(feature = "std", error("Value is out of the provable range."))]
+    ValueOutOfRange,
+    /// This error occurs when the seed supplied for seeded proving is
+    /// shorter than 32 bytes.
+    #[cfg_attr(feature = "std", error("Seed must be at least 32 bytes."))]
+    InsufficientSeed,
+    /// This error occurs when a proof cannot be rewound with the given
+    /// rewind key.
+    #[cfg_attr(feature = "std", error("Proof cannot be rewound with this key."))]
+    RewindError,
+    /// This error occurs when a value commitment is the identity or
+    /// cannot be decompressed, and the verifier was asked to reject it.
+    #[cfg_attr(feature = "std", error("Invalid value commitment at index {index}."))]
+    InvalidCommitment {
+        /// The index of the commitment among the proof's commitments.
+        index: usize,
+    },
+    /// This error occurs when a point of a proof being verified, or
+    /// one of its value commitments, is not a valid encoding.
+    #[cfg_attr(
+        feature = "std",
+        error("Point {component} of proof {proof_index} could not be decompressed.")
+    )]
+    PointDecompressionError {
+        /// The index of the proof among the proofs being verified.
+        proof_index: usize,
+        /// The point that could not be decompressed: `"A"`, `"S"`,
+        /// `"T_1"`, `"T_2"`, `"L"` or `"R"` for the points of the
+        /// proof, or `"V"` for a value commitment.  The verifiers
+        /// taking two commitments `V1` and `V2` report `"V1"` or
+        /// `"V2"`.
+        component: &'static str,
+    },
+    /// This error occurs when a point of a proof being verified is
+    /// rejected as it is appended to the transcript, because it is
+    /// the identity.
+    #[cfg_attr(
+        feature = "std",
+        error("Point {label} of proof {proof_index} failed transcript validation.")
+    )]
+    TranscriptPointValidation {
+        /// The index of the proof among the proofs being verified.
+        proof_index: usize,
+        /// The transcript label of the point.
+        label: &'static str,
+    },
+    /// This error occurs when the same value commitment appears in two
+    /// different proofs that were required to have disjoint commitments.
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "Commitment {commitment_index} of proof {proof_b} already appears in proof {proof_a}."
+        )
+    )]
+    DuplicateAcrossProofs {
+        /// The index of the first proof with the commitment.
+        proof_a: usize,
+        /// The index of the second proof with the commitment.
+        proof_b: usize,
+        /// The index of the commitment among the commitments of `proof_b`.
+        commitment_index: usize,
+    },
+    /// This error occurs when a batch of proofs failed to verify, and
+    /// identifies the offending proofs.
+    #[cfg_attr(
+        feature = "std",
+        error("Batch verification failed for proofs {bad_indices:?}")
+    )]
+    BatchVerificationError {
+        /// A vector with the indexes of the proofs that failed to verify.
+        bad_indices: Vec<usize>,
+    },
+    /// This error occurs when one or more parties submit malformed
+    /// proof shares to the dealer.
+    #[cfg_attr(
+        feature = "std",
+        error("Malformed proof shares from parties {bad_shares:?}")
+    )]
+    MalformedShares {
+        /// A vector with the indexes of the parties whose shares were malformed.
+        bad_shares: Vec<usize>,
+    },
+    /// This error occurs when the dealer gives a zero challenge,
+    /// which would annihilate the blinding factors.
+    #[cfg_attr(feature = "std", error("Dealer gave a malicious challenge value."))]
+    MaliciousDealer,
     /// This error results from an internal error during proving.
     ///
     /// The single-party prover is implemented by performing
@@ -50,7 +147,7 @@ pub enum ProofError {
     /// MPC protocol is not exposed by the single-party API, we
     /// consider its errors to be internal errors.
     #[cfg_attr(feature = "std", error("Internal error during proof creation: {0}"))]
-    ProvingError(MPCError),
+    ProvingError(#[cfg_attr(feature = "std", source)] MPCError),
 }
 
 impl From<MPCError> for ProofError {
@@ -59,6 +156,11 @@ impl From<MPCError> for ProofError {
             MPCError::InvalidBitsize => ProofError::InvalidBitsize,
             MPCError::InvalidAggregation => ProofError::InvalidAggregation,
             MPCError::InvalidGeneratorsLength => ProofError::InvalidGeneratorsLength,
+            MPCError::ValueOutOfRange => ProofError::ValueOutOfRange,
+            MPCError::MalformedProofShares { bad_shares } => {
+                ProofError::MalformedShares { bad_shares }
+            }
+            MPCError::MaliciousDealer => ProofError::MaliciousDealer,
             _ => ProofError::ProvingError(e),
         }
     }
@@ -79,8 +181,8 @@ pub enum MPCError {
     #[cfg_attr(feature = "std", error("Dealer gave a malicious challenge value."))]
     MaliciousDealer,
     /// This error occurs when attempting to create a proof with
-    /// bitsize other than \\(8\\), \\(16\\), \\(32\\), or \\(64\\).
-    #[cfg_attr(feature = "std", error("Invalid bitsize, must have n = 8,16,32,64"))]
+    /// bitsize outside \\(1 \leq n \leq 128\\).
+    #[cfg_attr(feature = "std", error("Invalid bitsize, must have 1 <= n <= 128"))]
     InvalidBitsize,
     /// This error occurs when attempting to create an aggregated
     /// proof with non-power-of-two aggregation size.
@@ -95,6 +197,19 @@ pub enum MPCError {
         error("Invalid generators size, too few generators for proof")
     )]
     InvalidGeneratorsLength,
+    /// This error occurs when a party's value does not fit in the
+    /// bitsize of the proof.
+    #[cfg_attr(feature = "std", error("Value is out of the provable range."))]
+    ValueOutOfRange,
+    /// This error occurs when the dealer is given a participant list
+    /// whose length does not match the aggregation size.
+    #[cfg_attr(feature = "std", error("Wrong number of participants"))]
+    WrongNumParticipants,
+    /// This error occurs when a party's expected participant list does
+    /// not match the dealer's, or when the party does not appear at
+    /// its claimed position.
+    #[cfg_attr(feature = "std", error("Participant list mismatch"))]
+    ParticipantMismatch,
     /// This error occurs when the dealer is given the wrong number of
     /// value commitments.
     #[cfg_attr(feature = "std", error("Wrong number of value commitments"))]
@@ -119,6 +234,50 @@ pub enum MPCError {
     },
 }
 
+/// Represents an error in the construction of generators.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Error))]
+pub enum GensError {
+    /// This error occurs when the total number of generators overflows.
+    #[cfg_attr(feature = "std", error("Generators capacity overflows."))]
+    CapacityOverflow,
+    /// This error occurs when a capacity exceeds its documented maximum.
+    #[cfg_attr(
+        feature = "std",
+        error("Invalid capacity, must have gens_capacity <= {max_gens} and party_capacity <= {max_parties}.")
+    )]
+    InvalidCapacity {
+        /// The maximum number of generators per party.
+        max_gens: usize,
+        /// The maximum number of parties.
+        max_parties: usize,
+    },
+    /// This error occurs when the generators would take more memory
+    /// than the allowed budget.
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "Generators require {requested_bytes} bytes, more than the {max_bytes} bytes allowed."
+        )
+    )]
+    TooLarge {
+        /// The memory required by the generators, in bytes.
+        requested_bytes: usize,
+        /// The memory budget, in bytes.
+        max_bytes: usize,
+    },
+    /// This error occurs when growing generators which were decoded
+    /// without being validated, so that the chains they follow are
+    /// unknown.
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "Cannot grow generators of unknown derivation, decode them with a validated decoder."
+        )
+    )]
+    UnknownDerivation,
+}
+
 /// Represents an error during the proving or verifying of a constraint system.
 ///
 /// XXX: should this be separate from a `ProofError`?
@@ -9,12 +9,16 @@ extern crate alloc;
 use alloc::vec::Vec;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
-use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::MultiscalarMul;
 use digest::{ExtendableOutput, Update, XofReader};
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 use sha3::{Sha3_512, Shake256, Shake256Reader};
 
+use crate::errors::{GensError, ProofError};
+use crate::util;
+
 /// Represents a pair of base points for Pedersen commitments.
 ///
 /// The Bulletproofs implementation and API is designed to support
@@ -26,7 +30,16 @@ use sha3::{Sha3_512, Shake256, Shake256Reader};
 /// * `B`: the `ristretto255` basepoint;
 /// * `B_blinding`: the result of `ristretto255` SHA3-512
 /// hash-to-group on input `B_bytes`.
-#[derive(Copy, Clone)]
+///
+/// All the value commitments of a proof share the same `B`: the
+/// verifier checks a single polynomial evaluation \\(t(x)\\), which
+/// combines every party's value, against `B`.  Values committed to
+/// different bases, such as the amounts of different assets, must
+/// therefore be proven in separate proofs, each with the
+/// `PedersenGens` of its own base.  Those proofs are verified
+/// separately too, since a batch is verified against one
+/// `PedersenGens`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct PedersenGens {
     /// Base for the committed value
     pub B: RistrettoPoint,
@@ -35,20 +48,34 @@ pub struct PedersenGens {
 }
 
 impl PedersenGens {
+    /// Creates Pedersen generators from the given base points, for
+    /// compatibility with commitments made under other generators.
+    ///
+    /// The points must be independent: nobody may know the discrete
+    /// log of `B_blinding` with respect to `B`, or commitments are not
+    /// binding.
+    pub fn new(B: RistrettoPoint, B_blinding: RistrettoPoint) -> PedersenGens {
+        PedersenGens { B, B_blinding }
+    }
+
     /// Creates a Pedersen commitment using the value scalar and a blinding factor.
     pub fn commit(&self, value: Scalar, blinding: Scalar) -> RistrettoPoint {
         RistrettoPoint::multiscalar_mul(&[value, blinding], &[self.B, self.B_blinding])
     }
+
+    /// Creates a Pedersen commitment to a `u64` value, as returned by
+    /// the range proof provers for the same value and blinding factor.
+    pub fn commit_u64(&self, value: u64, blinding: Scalar) -> RistrettoPoint {
+        self.commit(Scalar::from(value), blinding)
+    }
 }
 
 impl Default for PedersenGens {
     fn default() -> Self {
-        PedersenGens {
-            B: RISTRETTO_BASEPOINT_POINT,
-            B_blinding: RistrettoPoint::hash_from_bytes::<Sha3_512>(
-                RISTRETTO_BASEPOINT_COMPRESSED.as_bytes(),
-            ),
-        }
+        PedersenGens::new(
+            RISTRETTO_BASEPOINT_POINT,
+            RistrettoPoint::hash_from_bytes::<Sha3_512>(RISTRETTO_BASEPOINT_COMPRESSED.as_bytes()),
+        )
     }
 }
 
@@ -82,6 +109,34 @@ impl GeneratorsChain {
     }
 }
 
+/// Returns the label of the chain of `kind` generators, `b'G'` or
+/// `b'H'`, of the party at index `party`.
+fn party_label(kind: u8, party: usize) -> [u8; 5] {
+    use byteorder::{ByteOrder, LittleEndian};
+
+    let mut label = [kind, 0, 0, 0, 0];
+    LittleEndian::write_u32(&mut label[1..5], party as u32);
+    label
+}
+
+/// Returns the label of the chain of `kind` generators of the party at
+/// index `party`, for generators bound to the application label `app`.
+///
+/// The length of `app` is appended, so that the labels of different
+/// applications never collide with each other nor with the 5-byte
+/// labels of [`party_label`].
+fn app_party_label(kind: u8, party: usize, app: &[u8]) -> Vec<u8> {
+    use byteorder::{ByteOrder, LittleEndian};
+
+    let mut label = Vec::with_capacity(5 + app.len() + 8);
+    label.extend_from_slice(&party_label(kind, party));
+    label.extend_from_slice(app);
+    let mut len = [0u8; 8];
+    LittleEndian::write_u64(&mut len, app.len() as u64);
+    label.extend_from_slice(&len);
+    label
+}
+
 impl Default for GeneratorsChain {
     fn default() -> Self {
         Self::new(&[])
@@ -129,12 +184,22 @@ impl Iterator for GeneratorsChain {
 /// chain, and even forward-compatible to multiparty aggregation of
 /// constraint system proofs, since the generators are namespaced by
 /// their party index.
+///
+/// Generators created with [`BulletproofGens::new_with_label`] are
+/// additionally namespaced by an application label, so that proofs
+/// for one application do not verify for another.
 #[derive(Clone)]
 pub struct BulletproofGens {
     /// The maximum number of usable generators for each party.
     pub gens_capacity: usize,
     /// Number of values or parties
     pub party_capacity: usize,
+    /// The application label, or `None` for the default generators.
+    label: Option<Vec<u8>>,
+    /// Whether the generators are known to be the chains derived from
+    /// `label`, so that they can be grown.  Generators decoded without
+    /// validation could be any points.
+    derived: bool,
     /// Precomputed \\(\mathbf G\\) generators for each party.
     G_vec: Vec<Vec<RistrettoPoint>>,
     /// Precomputed \\(\mathbf H\\) generators for each party.
@@ -142,27 +207,180 @@ pub struct BulletproofGens {
 }
 
 impl BulletproofGens {
+    /// The maximum number of generators per party accepted by
+    /// [`BulletproofGens::try_new`].
+    pub const MAX_GENS_CAPACITY: usize = 1 << 24;
+
+    /// The maximum number of parties accepted by
+    /// [`BulletproofGens::try_new`].
+    pub const MAX_PARTY_CAPACITY: usize = 1 << 16;
+
+    /// The default memory budget of [`BulletproofGens::try_new`], in bytes.
+    pub const DEFAULT_MAX_BYTES: usize = 1 << 30;
+
     /// Create a new `BulletproofGens` object.
     ///
     /// # Inputs
     ///
     /// * `gens_capacity` is the number of generators to precompute
     ///    for each party.  For rangeproofs, it is sufficient to pass
-    ///    `64`, the maximum bitsize of the rangeproofs.  For circuit
+    ///    the largest bitsize to be proven, at most `128`.  For circuit
     ///    proofs, the capacity must be greater than the number of
     ///    multipliers, rounded up to the next power of two.
     ///
     /// * `party_capacity` is the maximum number of parties that can
     ///    produce an aggregated proof.
+    ///
+    /// With the `rayon` feature, the generators of different parties
+    /// are computed on multiple threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`BulletproofGens::try_new`] fails.  Use it instead
+    /// when the capacities come from untrusted configuration.
     pub fn new(gens_capacity: usize, party_capacity: usize) -> Self {
+        BulletproofGens::try_new(gens_capacity, party_capacity)
+            .expect("invalid BulletproofGens capacities")
+    }
+
+    /// Create a new `BulletproofGens` object whose generators are
+    /// derived from the application label `label`.
+    ///
+    /// Proofs created with these generators only verify with
+    /// generators of the same label, and not with the default
+    /// generators of [`BulletproofGens::new`].  Any label, including
+    /// the empty one, gives generators different from the default ones.
+    ///
+    /// See [`BulletproofGens::new`] for the other inputs.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`BulletproofGens::new`].
+    pub fn new_with_label(label: &[u8], gens_capacity: usize, party_capacity: usize) -> Self {
+        BulletproofGens::build(
+            Some(label),
+            gens_capacity,
+            party_capacity,
+            BulletproofGens::DEFAULT_MAX_BYTES,
+        )
+        .expect("invalid BulletproofGens capacities")
+    }
+
+    /// Create a new `BulletproofGens` object, checking that the
+    /// capacities are at most [`BulletproofGens::MAX_GENS_CAPACITY`]
+    /// and [`BulletproofGens::MAX_PARTY_CAPACITY`], and that the
+    /// generators fit in [`BulletproofGens::DEFAULT_MAX_BYTES`].
+    ///
+    /// See [`BulletproofGens::new`] for the inputs.
+    pub fn try_new(gens_capacity: usize, party_capacity: usize) -> Result<Self, GensError> {
+        BulletproofGens::try_new_with_budget(
+            gens_capacity,
+            party_capacity,
+            BulletproofGens::DEFAULT_MAX_BYTES,
+        )
+    }
+
+    /// Create a new `BulletproofGens` object, checking that the
+    /// capacities are at most [`BulletproofGens::MAX_GENS_CAPACITY`]
+    /// and [`BulletproofGens::MAX_PARTY_CAPACITY`], and that the
+    /// generators fit in `max_bytes` bytes of memory.
+    ///
+    /// See [`BulletproofGens::new`] for the inputs.
+    pub fn try_new_with_budget(
+        gens_capacity: usize,
+        party_capacity: usize,
+        max_bytes: usize,
+    ) -> Result<Self, GensError> {
+        BulletproofGens::build(None, gens_capacity, party_capacity, max_bytes)
+    }
+
+    /// Checks the capacities and creates the generators for the
+    /// application label `label`, if any.
+    fn build(
+        label: Option<&[u8]>,
+        gens_capacity: usize,
+        party_capacity: usize,
+        max_bytes: usize,
+    ) -> Result<Self, GensError> {
+        let requested_bytes = BulletproofGens::required_bytes(gens_capacity, party_capacity)
+            .ok_or(GensError::CapacityOverflow)?;
+        if gens_capacity > BulletproofGens::MAX_GENS_CAPACITY
+            || party_capacity > BulletproofGens::MAX_PARTY_CAPACITY
+        {
+            return Err(GensError::InvalidCapacity {
+                max_gens: BulletproofGens::MAX_GENS_CAPACITY,
+                max_parties: BulletproofGens::MAX_PARTY_CAPACITY,
+            });
+        }
+        if requested_bytes > max_bytes {
+            return Err(GensError::TooLarge {
+                requested_bytes,
+                max_bytes,
+            });
+        }
+
         let mut gens = BulletproofGens {
             gens_capacity: 0,
             party_capacity,
+            label: label.map(|label| label.to_vec()),
+            derived: true,
             G_vec: (0..party_capacity).map(|_| Vec::new()).collect(),
             H_vec: (0..party_capacity).map(|_| Vec::new()).collect(),
         };
-        gens.increase_capacity(gens_capacity);
-        gens
+        gens.grow_capacity(gens_capacity);
+        Ok(gens)
+    }
+
+    /// Returns the memory taken by the \\(\mathbf G\\) and
+    /// \\(\mathbf H\\) generators for the given capacities, in bytes,
+    /// or `None` on overflow.
+    pub fn required_bytes(gens_capacity: usize, party_capacity: usize) -> Option<usize> {
+        gens_capacity
+            .checked_mul(party_capacity)?
+            .checked_mul(2)?
+            .checked_mul(core::mem::size_of::<RistrettoPoint>())
+    }
+
+    /// Create the smallest `BulletproofGens` object able to prove and
+    /// verify proofs of every given `(n, m)` dimension, where `n` is
+    /// the bitsize and `m` the aggregation size.
+    ///
+    /// The capacities are the maximum `n` and the maximum `m` across
+    /// `dims`.
+    pub fn for_dimensions(dims: &[(usize, usize)]) -> Self {
+        let gens_capacity = dims.iter().map(|&(n, _)| n).max().unwrap_or(0);
+        let party_capacity = dims.iter().map(|&(_, m)| m).max().unwrap_or(0);
+        BulletproofGens::new(gens_capacity, party_capacity)
+    }
+
+    /// Returns whether the generators are large enough to prove and
+    /// verify aggregated proofs of `m` values of bitsize `n`.
+    ///
+    /// The bitsize is padded to 8, 16, 32, 64 or 128 and the
+    /// aggregation size to the next power of two, see
+    /// [`required_capacities`].
+    pub fn supports(&self, n: usize, m: usize) -> bool {
+        match required_capacities(core::iter::once((n, m))) {
+            Some((gens_capacity, party_capacity)) => {
+                gens_capacity <= self.gens_capacity && party_capacity <= self.party_capacity
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the application label of the generators, or `None` for
+    /// the default generators of [`BulletproofGens::new`].
+    pub fn label(&self) -> Option<&[u8]> {
+        self.label.as_deref()
+    }
+
+    /// Returns the chain of `kind` generators of the party at index
+    /// `party`.
+    fn chain(&self, kind: u8, party: usize) -> GeneratorsChain {
+        match &self.label {
+            None => GeneratorsChain::new(&party_label(kind, party)),
+            Some(app) => GeneratorsChain::new(&app_party_label(kind, party, app)),
+        }
     }
 
     /// Returns j-th share of generators, with an appropriate
@@ -176,33 +394,256 @@ impl BulletproofGens {
 
     /// Increases the generators' capacity to the amount specified.
     /// If less than or equal to the current capacity, does nothing.
+    ///
+    /// Generators decoded without validation cannot be grown, so this
+    /// also does nothing for them.  Use
+    /// [`BulletproofGens::try_increase_capacity`] to get an error
+    /// instead.
     pub fn increase_capacity(&mut self, new_capacity: usize) {
-        use byteorder::{ByteOrder, LittleEndian};
+        let _ = self.try_increase_capacity(new_capacity);
+    }
+
+    /// Increases the generators' capacity to the amount specified, like
+    /// [`BulletproofGens::increase_capacity`].
+    ///
+    /// Returns [`GensError::UnknownDerivation`] if the capacity must
+    /// grow but the generators were decoded without validation.
+    pub fn try_increase_capacity(&mut self, new_capacity: usize) -> Result<(), GensError> {
+        self.extend_to(new_capacity, self.party_capacity)
+    }
 
+    /// Grows the chain of every party to `new_capacity` generators.
+    /// If less than or equal to the current capacity, does nothing.
+    fn grow_capacity(&mut self, new_capacity: usize) {
         if self.gens_capacity >= new_capacity {
             return;
         }
 
-        for i in 0..self.party_capacity {
-            let party_index = i as u32;
-            let mut label = [b'G', 0, 0, 0, 0];
-            LittleEndian::write_u32(&mut label[1..5], party_index);
-            self.G_vec[i].extend(
-                &mut GeneratorsChain::new(&label)
-                    .fast_forward(self.gens_capacity)
-                    .take(new_capacity - self.gens_capacity),
-            );
-
-            label[0] = b'H';
-            self.H_vec[i].extend(
-                &mut GeneratorsChain::new(&label)
-                    .fast_forward(self.gens_capacity)
-                    .take(new_capacity - self.gens_capacity),
-            );
+        let new_gens = self.parties_gens(0..self.party_capacity, self.gens_capacity, new_capacity);
+        for (i, (G, H)) in new_gens.into_iter().enumerate() {
+            self.G_vec[i].extend(G);
+            self.H_vec[i].extend(H);
         }
         self.gens_capacity = new_capacity;
     }
 
+    /// Increases the number of parties to the amount specified,
+    /// computing only the generator chains of the new parties.
+    /// If less than or equal to the current party capacity, does nothing.
+    ///
+    /// The generators of the existing parties are kept as is, and the
+    /// result has the same generators as
+    /// `BulletproofGens::new(self.gens_capacity, new_party_capacity)`.
+    ///
+    /// Generators decoded without validation cannot be grown, so this
+    /// also does nothing for them.  Use [`BulletproofGens::extend_to`]
+    /// to get an error instead.
+    pub fn increase_party_capacity(&mut self, new_party_capacity: usize) {
+        let _ = self.extend_to(self.gens_capacity, new_party_capacity);
+    }
+
+    /// Grows the generators in place to `new_gens_capacity` generators
+    /// for each of `new_party_capacity` parties, computing only the
+    /// generators that are missing.
+    ///
+    /// The result has the same generators as
+    /// `BulletproofGens::new(new_gens_capacity, new_party_capacity)`.
+    /// If either new capacity is less than the current one, does nothing.
+    ///
+    /// Returns [`GensError::UnknownDerivation`] if the generators must
+    /// grow but were decoded without validation, since the missing
+    /// generators would not follow the decoded ones.
+    pub fn extend(
+        &mut self,
+        new_gens_capacity: usize,
+        new_party_capacity: usize,
+    ) -> Result<(), GensError> {
+        if new_gens_capacity < self.gens_capacity || new_party_capacity < self.party_capacity {
+            return Ok(());
+        }
+        if new_gens_capacity == self.gens_capacity && new_party_capacity == self.party_capacity {
+            return Ok(());
+        }
+        if !self.derived {
+            return Err(GensError::UnknownDerivation);
+        }
+
+        self.grow_capacity(new_gens_capacity);
+
+        let new_gens = self.parties_gens(
+            self.party_capacity..new_party_capacity,
+            0,
+            self.gens_capacity,
+        );
+        for (G, H) in new_gens {
+            self.G_vec.push(G);
+            self.H_vec.push(H);
+        }
+        self.party_capacity = new_party_capacity;
+        Ok(())
+    }
+
+    /// Computes the \\(\mathbf G\\) and \\(\mathbf H\\) generators
+    /// from index `from` to `to` of each party in `parties`.
+    ///
+    /// The chains of different parties are independent, so with the
+    /// `rayon` feature they are computed on multiple threads.  The
+    /// generators are the same either way.
+    fn parties_gens(
+        &self,
+        parties: core::ops::Range<usize>,
+        from: usize,
+        to: usize,
+    ) -> Vec<(Vec<RistrettoPoint>, Vec<RistrettoPoint>)> {
+        let party_gens = |i: usize| {
+            let G = self.chain(b'G', i).fast_forward(from).take(to - from);
+            let H = self.chain(b'H', i).fast_forward(from).take(to - from);
+            (G.collect(), H.collect())
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            parties.into_par_iter().map(party_gens).collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        parties.map(party_gens).collect()
+    }
+
+    /// Grows the generators in place to at least `gens_capacity`
+    /// generators for each of at least `party_capacity` parties,
+    /// computing only the generators that are missing.
+    ///
+    /// Unlike [`BulletproofGens::extend`], each capacity is grown
+    /// independently, and a capacity which is already large enough is
+    /// kept as is.
+    ///
+    /// Returns [`GensError::UnknownDerivation`] if the generators must
+    /// grow but were decoded without validation.
+    pub fn extend_to(
+        &mut self,
+        gens_capacity: usize,
+        party_capacity: usize,
+    ) -> Result<(), GensError> {
+        self.extend(
+            gens_capacity.max(self.gens_capacity),
+            party_capacity.max(self.party_capacity),
+        )
+    }
+
+    /// Serializes the generators into a byte array, so that they can
+    /// be loaded with [`BulletproofGens::from_bytes`] instead of being
+    /// recomputed.
+    ///
+    /// # Layout
+    ///
+    /// The layout of the generators encoding is:
+    ///
+    /// * `gens_capacity` and `party_capacity` as 32-bit little-endian integers,
+    /// * for each party, its `gens_capacity` \\(\mathbf G\\) generators as compressed Ristretto points,
+    /// * for each party, its `gens_capacity` \\(\mathbf H\\) generators as compressed Ristretto points.
+    ///
+    /// The application label is not encoded: decode generators created
+    /// with [`BulletproofGens::new_with_label`] with
+    /// [`BulletproofGens::from_bytes_with_label`] or
+    /// [`BulletproofGens::from_bytes_validated_with_label`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        use byteorder::{ByteOrder, LittleEndian};
+
+        let num_points = self.gens_capacity * self.party_capacity * 2;
+        let mut buf = Vec::with_capacity(8 + num_points * 32);
+        let mut header = [0u8; 8];
+        LittleEndian::write_u32(&mut header[0..4], self.gens_capacity as u32);
+        LittleEndian::write_u32(&mut header[4..8], self.party_capacity as u32);
+        buf.extend_from_slice(&header);
+        for gens in self.G_vec.iter().chain(self.H_vec.iter()) {
+            for point in gens.iter() {
+                buf.extend_from_slice(point.compress().as_bytes());
+            }
+        }
+        buf
+    }
+
+    /// Deserializes the generators from a byte slice, see
+    /// [`BulletproofGens::to_bytes`] for the layout.
+    ///
+    /// Returns [`ProofError::InvalidGeneratorsLength`] if the number of
+    /// points does not match the capacities, and
+    /// [`ProofError::FormatError`] if the slice is otherwise malformed.
+    ///
+    /// The points are not checked to be the generators derived by
+    /// [`BulletproofGens::new`], so they must come from a trusted source.
+    /// Use [`BulletproofGens::from_bytes_validated`] otherwise.
+    ///
+    /// The decoded generators have no application label, and since
+    /// their derivation is unknown, they cannot be grown: growing them
+    /// returns [`GensError::UnknownDerivation`].  They still prove and
+    /// verify like the encoded generators.
+    pub fn from_bytes(slice: &[u8]) -> Result<BulletproofGens, ProofError> {
+        let (gens_capacity, party_capacity) = read_capacities(slice)?;
+        decode_gens(slice, gens_capacity, gens_capacity, party_capacity)
+    }
+
+    /// Deserializes the generators from a byte slice like
+    /// [`BulletproofGens::from_bytes`], recording that they were
+    /// encoded from generators created with
+    /// [`BulletproofGens::new_with_label`] and `label`.
+    ///
+    /// Like [`BulletproofGens::from_bytes`], the points are not checked
+    /// and the decoded generators cannot be grown.
+    pub fn from_bytes_with_label(
+        slice: &[u8],
+        label: &[u8],
+    ) -> Result<BulletproofGens, ProofError> {
+        let mut gens = BulletproofGens::from_bytes(slice)?;
+        gens.label = Some(label.to_vec());
+        Ok(gens)
+    }
+
+    /// Deserializes the generators from a byte slice like
+    /// [`BulletproofGens::from_bytes`], and checks that they are the
+    /// generators derived by [`BulletproofGens::new`] for the encoded
+    /// capacities.
+    ///
+    /// Returns [`ProofError::FormatError`] if any point differs, so
+    /// that a tampered cache cannot substitute rogue generators.
+    /// The check derives the generators again, so it is as expensive
+    /// as [`BulletproofGens::new`]; it is meant for loading a cache
+    /// which is not trusted, not for the fast path.  The validated
+    /// generators can be grown like fresh ones.
+    pub fn from_bytes_validated(slice: &[u8]) -> Result<BulletproofGens, ProofError> {
+        BulletproofGens::decode_validated(slice, None)
+    }
+
+    /// Deserializes the generators from a byte slice like
+    /// [`BulletproofGens::from_bytes_validated`], and checks that they
+    /// are the generators derived by
+    /// [`BulletproofGens::new_with_label`] with `label` for the
+    /// encoded capacities.
+    pub fn from_bytes_validated_with_label(
+        slice: &[u8],
+        label: &[u8],
+    ) -> Result<BulletproofGens, ProofError> {
+        BulletproofGens::decode_validated(slice, Some(label))
+    }
+
+    /// Decodes the generators and checks that they are the ones
+    /// derived from the application label `label`, if any.
+    fn decode_validated(slice: &[u8], label: Option<&[u8]>) -> Result<BulletproofGens, ProofError> {
+        let gens = BulletproofGens::from_bytes(slice)?;
+        let derived = BulletproofGens::build(
+            label,
+            gens.gens_capacity,
+            gens.party_capacity,
+            BulletproofGens::DEFAULT_MAX_BYTES,
+        )
+        .map_err(|_| ProofError::InvalidGeneratorsLength)?;
+        if gens.G_vec != derived.G_vec || gens.H_vec != derived.H_vec {
+            return Err(ProofError::FormatError);
+        }
+        Ok(derived)
+    }
+
     /// Return an iterator over the aggregation of the parties' G generators with given size `n`.
     pub(crate) fn G(&self, n: usize, m: usize) -> impl Iterator<Item = &RistrettoPoint> {
         AggregatedGensIter {
@@ -226,6 +667,186 @@ impl BulletproofGens {
     }
 }
 
+/// Returns the smallest `gens_capacity` and `party_capacity` of
+/// [`BulletproofGens`] able to prove and verify proofs of every given
+/// `(n, m)` dimension, where `n` is the bitsize and `m` the aggregation
+/// size.
+///
+/// Proofs are padded to a bitsize of 8, 16, 32, 64 or 128 and to a
+/// power of two aggregation size, so the capacities are the maximum
+/// padded `n` and `m`.  Returns `None` if a bitsize is outside
+/// \\(1 \leq n \leq 128\\) or an aggregation size is 0, since no
+/// generators support it.
+pub fn required_capacities(
+    dims: impl IntoIterator<Item = (usize, usize)>,
+) -> Option<(usize, usize)> {
+    let mut capacities = (0, 0);
+    for (n, m) in dims {
+        let padded_n = util::padded_bitsize(n)?;
+        let padded_m = match m {
+            0 => return None,
+            _ => m.checked_next_power_of_two()?,
+        };
+        capacities = (capacities.0.max(padded_n), capacities.1.max(padded_m));
+    }
+    Some(capacities)
+}
+
+/// Reads the capacities of an encoding of [`BulletproofGens::to_bytes`],
+/// checking that the length of `slice` matches them.
+fn read_capacities(slice: &[u8]) -> Result<(usize, usize), ProofError> {
+    use byteorder::{ByteOrder, LittleEndian};
+
+    if slice.len() < 8 || (slice.len() - 8) % 32 != 0 {
+        return Err(ProofError::FormatError);
+    }
+    let gens_capacity = LittleEndian::read_u32(&slice[0..4]) as usize;
+    let party_capacity = LittleEndian::read_u32(&slice[4..8]) as usize;
+    let num_points = gens_capacity
+        .checked_mul(party_capacity)
+        .and_then(|n| n.checked_mul(2))
+        .ok_or(ProofError::InvalidGeneratorsLength)?;
+    if (slice.len() - 8) / 32 != num_points {
+        return Err(ProofError::InvalidGeneratorsLength);
+    }
+    Ok((gens_capacity, party_capacity))
+}
+
+/// Decompresses the first `gens_capacity` generators of each of the
+/// first `party_capacity` parties from `slice`, an encoding of
+/// [`BulletproofGens::to_bytes`] with `encoded_gens_capacity`
+/// generators per party, whose length was checked.
+fn decode_gens(
+    slice: &[u8],
+    encoded_gens_capacity: usize,
+    gens_capacity: usize,
+    party_capacity: usize,
+) -> Result<BulletproofGens, ProofError> {
+    let encoded_points = (slice.len() - 8) / 32;
+    let decode = |first: usize| -> Result<Vec<Vec<RistrettoPoint>>, ProofError> {
+        (0..party_capacity)
+            .map(|j| {
+                let start = 8 + (first + j * encoded_gens_capacity) * 32;
+                slice[start..start + gens_capacity * 32]
+                    .chunks_exact(32)
+                    .map(|bytes| {
+                        CompressedRistretto(util::read32(bytes))
+                            .decompress()
+                            .ok_or(ProofError::FormatError)
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+
+    Ok(BulletproofGens {
+        gens_capacity,
+        party_capacity,
+        label: None,
+        derived: false,
+        G_vec: decode(0)?,
+        H_vec: decode(encoded_points / 2)?,
+    })
+}
+
+/// Generators encoded by [`BulletproofGens::to_bytes`], read in place
+/// from a byte slice such as a memory-mapped file.
+///
+/// The verifier needs the generators as decompressed points, so
+/// [`MappedGens::load`] decompresses the generators of the given
+/// capacities into a [`BulletproofGens`].  With the capacities of
+/// [`required_capacities`] for the proofs at hand, only the generators
+/// they use take memory, however large the encoded generators are.
+/// Decompressing is slower than reading decompressed points, so the
+/// loaded generators should be kept for as long as they are needed.
+///
+/// Like [`BulletproofGens::from_bytes`], the points are not checked to
+/// be the generators derived by [`BulletproofGens::new`], so they must
+/// come from a trusted source.
+#[cfg(feature = "mmap-gens")]
+pub struct MappedGens<'a> {
+    bytes: &'a [u8],
+    gens_capacity: usize,
+    party_capacity: usize,
+}
+
+#[cfg(feature = "mmap-gens")]
+impl<'a> MappedGens<'a> {
+    /// Reads the capacities of the encoded generators in `bytes`.
+    ///
+    /// Returns [`ProofError::InvalidGeneratorsLength`] if the number of
+    /// points does not match the capacities, and
+    /// [`ProofError::FormatError`] if `bytes` is otherwise malformed.
+    /// The points are only decompressed when loaded.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, ProofError> {
+        let (gens_capacity, party_capacity) = read_capacities(bytes)?;
+        Ok(MappedGens {
+            bytes,
+            gens_capacity,
+            party_capacity,
+        })
+    }
+
+    /// Returns the number of encoded generators of each party.
+    pub fn gens_capacity(&self) -> usize {
+        self.gens_capacity
+    }
+
+    /// Returns the number of parties of the encoded generators.
+    pub fn party_capacity(&self) -> usize {
+        self.party_capacity
+    }
+
+    /// Decompresses the first `gens_capacity` generators of each of the
+    /// first `party_capacity` parties, which are the generators of
+    /// [`BulletproofGens::new`] with these capacities.
+    ///
+    /// Returns [`ProofError::InvalidGeneratorsLength`] if a capacity
+    /// exceeds the encoded one, and [`ProofError::FormatError`] if a
+    /// point does not decompress.
+    pub fn load(
+        &self,
+        gens_capacity: usize,
+        party_capacity: usize,
+    ) -> Result<BulletproofGens, ProofError> {
+        if gens_capacity > self.gens_capacity || party_capacity > self.party_capacity {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        decode_gens(
+            self.bytes,
+            self.gens_capacity,
+            gens_capacity,
+            party_capacity,
+        )
+    }
+}
+
+impl Serialize for BulletproofGens {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for BulletproofGens {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = Vec::deserialize(deserializer)?;
+        // Using Error::custom requires T: Display, which our error
+        // type only implements when it implements std::error::Error.
+        #[cfg(feature = "std")]
+        return BulletproofGens::from_bytes(&bytes).map_err(serde::de::Error::custom);
+        // In no-std contexts, drop the error message.
+        #[cfg(not(feature = "std"))]
+        return BulletproofGens::from_bytes(&bytes)
+            .map_err(|_| serde::de::Error::custom("deserialization error"));
+    }
+}
+
 struct AggregatedGensIter<'a> {
     array: &'a Vec<Vec<RistrettoPoint>>,
     n: usize,
@@ -290,6 +911,162 @@ impl<'a> BulletproofGensShare<'a> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn proofs_are_bound_to_custom_pedersen_gens() {
+        use crate::RangeProof;
+        use merlin::Transcript;
+
+        let custom = PedersenGens::new(
+            RistrettoPoint::hash_from_bytes::<Sha3_512>(b"custom B"),
+            RistrettoPoint::hash_from_bytes::<Sha3_512>(b"custom B_blinding"),
+        );
+        assert_ne!(custom, PedersenGens::default());
+
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::from(1234u64);
+        let mut transcript = Transcript::new(b"CustomPedersenGensTest");
+        let (proof, V) =
+            RangeProof::prove_single(&bp_gens, &custom, &mut transcript, 42, &blinding, 32)
+                .unwrap();
+        assert_eq!(V, custom.commit(Scalar::from(42u64), blinding).compress());
+
+        let mut transcript = Transcript::new(b"CustomPedersenGensTest");
+        assert!(proof
+            .verify_single(&bp_gens, &custom, &mut transcript, &V, 32)
+            .is_ok());
+
+        let mut transcript = Transcript::new(b"CustomPedersenGensTest");
+        assert!(proof
+            .verify_single(&bp_gens, &PedersenGens::default(), &mut transcript, &V, 32)
+            .is_err());
+    }
+
+    #[test]
+    fn commit_u64_matches_prover_commitment() {
+        use crate::RangeProof;
+        use merlin::Transcript;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let blinding = Scalar::from(987654321u64);
+        for &v in &[0u64, 1, 42, u64::MAX] {
+            let mut transcript = Transcript::new(b"CommitU64Test");
+            let (_, V) =
+                RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, v, &blinding, 64)
+                    .unwrap();
+            assert_eq!(pc_gens.commit_u64(v, blinding).compress(), V);
+            assert_eq!(
+                pc_gens.commit_u64(v, blinding),
+                pc_gens.commit(Scalar::from(v), blinding)
+            );
+        }
+    }
+
+    #[test]
+    fn required_capacities_are_padded() {
+        assert_eq!(required_capacities(Vec::new()), Some((0, 0)));
+        assert_eq!(
+            required_capacities(vec![(64, 1), (20, 3), (8, 16)]),
+            Some((64, 16))
+        );
+        assert_eq!(required_capacities(vec![(1, 1)]), Some((8, 1)));
+        assert_eq!(required_capacities(vec![(32, 2), (0, 1)]), None);
+        assert_eq!(required_capacities(vec![(129, 1)]), None);
+        assert_eq!(required_capacities(vec![(32, 0)]), None);
+
+        let gens = BulletproofGens::new(32, 4);
+        assert!(gens.supports(32, 4));
+        assert!(gens.supports(20, 3));
+        assert!(gens.supports(1, 1));
+        assert!(!gens.supports(33, 1));
+        assert!(!gens.supports(32, 5));
+        assert!(!gens.supports(0, 1));
+        assert!(!gens.supports(32, 0));
+    }
+
+    #[test]
+    fn supports_matches_verification() {
+        use crate::{ProofError, RangeProof};
+        use merlin::Transcript;
+
+        let big_gens = BulletproofGens::new(64, 4);
+        let pc_gens = PedersenGens::default();
+        let mut transcript = Transcript::new(b"SupportsTest");
+        let (proof, Vs) = RangeProof::prove_multiple(
+            &big_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2, 3],
+            &[Scalar::ONE, Scalar::ONE, Scalar::ONE],
+            20,
+        )
+        .unwrap();
+
+        for (gens_capacity, party_capacity) in [(32, 4), (16, 4), (32, 2), (64, 8)] {
+            let gens = BulletproofGens::new(gens_capacity, party_capacity);
+            let mut transcript = Transcript::new(b"SupportsTest");
+            let result = proof.verify_multiple(&gens, &pc_gens, &mut transcript, &Vs, 20);
+            assert_eq!(gens.supports(20, 3), result.is_ok());
+            if !gens.supports(20, 3) {
+                assert!(matches!(
+                    result,
+                    Err(ProofError::InvalidGeneratorsLength) | Err(ProofError::InvalidAggregation)
+                ));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "mmap-gens")]
+    fn mapped_gens_load_the_generators_of_smaller_capacities() {
+        use crate::RangeProof;
+        use merlin::Transcript;
+
+        let bytes = BulletproofGens::new(64, 8).to_bytes();
+        let mapped = MappedGens::new(&bytes).unwrap();
+        assert_eq!((mapped.gens_capacity(), mapped.party_capacity()), (64, 8));
+
+        let loaded = mapped.load(32, 2).unwrap();
+        let expected = BulletproofGens::new(32, 2);
+        assert_eq!(loaded.G_vec, expected.G_vec);
+        assert_eq!(loaded.H_vec, expected.H_vec);
+        assert_eq!(
+            mapped.load(64, 8).unwrap().G_vec,
+            BulletproofGens::from_bytes(&bytes).unwrap().G_vec
+        );
+        assert_eq!(
+            mapped.load(128, 1).map(|_| ()),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+        assert_eq!(
+            mapped.load(8, 16).map(|_| ()),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+
+        // Proofs verify under the loaded generators.
+        let pc_gens = PedersenGens::default();
+        let mut transcript = Transcript::new(b"MappedGensTest");
+        let (proof, Vs) = RangeProof::prove_multiple(
+            &expected,
+            &pc_gens,
+            &mut transcript,
+            &[7, 1 << 20],
+            &[Scalar::ONE, Scalar::ONE],
+            32,
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"MappedGensTest");
+        assert_eq!(
+            proof.verify_multiple(&loaded, &pc_gens, &mut transcript, &Vs, 32),
+            Ok(())
+        );
+
+        assert_eq!(
+            MappedGens::new(&bytes[..bytes.len() - 32]).map(|_| ()),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+    }
+
     #[test]
     fn aggregated_gens_iter_matches_flat_map() {
         let gens = BulletproofGens::new(64, 8);
@@ -353,4 +1130,315 @@ mod tests {
         helper(32, 8);
         helper(16, 8);
     }
+
+    #[test]
+    fn try_new_rejects_huge_parameters() {
+        assert_eq!(
+            BulletproofGens::try_new(usize::MAX, usize::MAX).unwrap_err(),
+            GensError::CapacityOverflow
+        );
+        assert_eq!(
+            BulletproofGens::try_new(1 << 30, 1 << 10).unwrap_err(),
+            GensError::InvalidCapacity {
+                max_gens: BulletproofGens::MAX_GENS_CAPACITY,
+                max_parties: BulletproofGens::MAX_PARTY_CAPACITY,
+            }
+        );
+        assert!(BulletproofGens::try_new(64, 8).is_ok());
+    }
+
+    #[test]
+    fn try_new_respects_budget() {
+        let requested_bytes = BulletproofGens::required_bytes(64, 8).unwrap();
+
+        assert_eq!(
+            BulletproofGens::try_new_with_budget(64, 8, requested_bytes - 1).unwrap_err(),
+            GensError::TooLarge {
+                requested_bytes,
+                max_bytes: requested_bytes - 1,
+            }
+        );
+        assert!(BulletproofGens::try_new_with_budget(64, 8, requested_bytes).is_ok());
+    }
+
+    #[test]
+    fn gens_follow_each_party_chain() {
+        let mut gens = BulletproofGens::new(16, 8);
+        gens.extend(32, 12).unwrap();
+        for i in 0..12 {
+            let G: Vec<_> = gens.chain(b'G', i).take(32).collect();
+            let H: Vec<_> = gens.chain(b'H', i).take(32).collect();
+            assert_eq!(gens.G_vec[i], G);
+            assert_eq!(gens.H_vec[i], H);
+        }
+    }
+
+    #[test]
+    fn extend_matches_creating_bigger_gens() {
+        let bigger = BulletproofGens::new(64, 16);
+
+        let mut gens = BulletproofGens::new(32, 8);
+        gens.extend(64, 16).unwrap();
+        assert_eq!(gens.gens_capacity, 64);
+        assert_eq!(gens.party_capacity, 16);
+        assert!(gens.G(64, 16).eq(bigger.G(64, 16)));
+        assert!(gens.H(64, 16).eq(bigger.H(64, 16)));
+
+        // Shrinking either capacity does nothing.
+        gens.extend(32, 32).unwrap();
+        gens.extend(128, 8).unwrap();
+        assert_eq!(gens.gens_capacity, 64);
+        assert_eq!(gens.party_capacity, 16);
+    }
+
+    #[test]
+    fn increase_party_capacity_matches_new() {
+        let bigger = BulletproofGens::new(64, 8);
+
+        let mut gens = BulletproofGens::new(64, 4);
+        let first_G = gens.G_vec[3].clone();
+        gens.increase_party_capacity(8);
+        assert_eq!(gens.gens_capacity, 64);
+        assert_eq!(gens.party_capacity, 8);
+        assert_eq!(gens.G_vec, bigger.G_vec);
+        assert_eq!(gens.H_vec, bigger.H_vec);
+        assert_eq!(gens.G_vec[3], first_G);
+
+        // A smaller party capacity does nothing.
+        gens.increase_party_capacity(2);
+        assert_eq!(gens.party_capacity, 8);
+        assert_eq!(gens.G_vec, bigger.G_vec);
+    }
+
+    #[test]
+    fn labeled_gens_are_bound_to_their_label() {
+        use crate::RangeProof;
+        use merlin::Transcript;
+
+        let default = BulletproofGens::new(32, 2);
+        let myapp = BulletproofGens::new_with_label(b"myapp", 32, 2);
+        let other = BulletproofGens::new_with_label(b"otherapp", 32, 2);
+        let empty = BulletproofGens::new_with_label(b"", 32, 2);
+        assert_eq!(default.label(), None);
+        assert_eq!(myapp.label(), Some(&b"myapp"[..]));
+        assert_eq!(empty.label(), Some(&b""[..]));
+        for gens in [&myapp, &other, &empty] {
+            assert_ne!(gens.G_vec, default.G_vec);
+            assert_ne!(gens.H_vec, default.H_vec);
+        }
+
+        // Growing labeled generators keeps the label.
+        let mut grown = BulletproofGens::new_with_label(b"myapp", 8, 1);
+        grown.extend_to(32, 2).unwrap();
+        assert_eq!(grown.G_vec, myapp.G_vec);
+        assert_eq!(grown.H_vec, myapp.H_vec);
+
+        let pc_gens = PedersenGens::default();
+        let blindings = [Scalar::from(3u64), Scalar::from(4u64)];
+        let mut transcript = Transcript::new(b"LabelTest");
+        let (proof, commitments) =
+            RangeProof::prove_multiple(&myapp, &pc_gens, &mut transcript, &[5, 6], &blindings, 32)
+                .unwrap();
+
+        let verify = |gens: &BulletproofGens| {
+            let mut transcript = Transcript::new(b"LabelTest");
+            proof.verify_multiple(gens, &pc_gens, &mut transcript, &commitments, 32)
+        };
+        assert_eq!(verify(&myapp), Ok(()));
+        assert_eq!(verify(&grown), Ok(()));
+        assert_eq!(verify(&default), Err(ProofError::VerificationError));
+        assert_eq!(verify(&other), Err(ProofError::VerificationError));
+        assert_eq!(verify(&empty), Err(ProofError::VerificationError));
+    }
+
+    #[test]
+    fn extend_to_proves_like_fresh_gens() {
+        use crate::RangeProof;
+        use merlin::Transcript;
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let pc_gens = PedersenGens::default();
+        let fresh = BulletproofGens::new(64, 4);
+
+        let mut gens = BulletproofGens::new(32, 8);
+        gens.extend_to(64, 4).unwrap();
+        assert_eq!(gens.gens_capacity, 64);
+        assert_eq!(gens.party_capacity, 8);
+
+        let prove = |bp_gens: &BulletproofGens| {
+            let mut rng = ChaChaRng::from_seed([5u8; 32]);
+            let blindings: Vec<_> = (0..4).map(|_| Scalar::random(&mut rng)).collect();
+            let mut transcript = Transcript::new(b"ExtendToTest");
+            RangeProof::prove_multiple_with_rng(
+                bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &[1, 2, 3, u64::MAX],
+                &blindings,
+                64,
+                &mut rng,
+            )
+            .unwrap()
+        };
+        let (proof, Vs) = prove(&gens);
+        let (fresh_proof, fresh_Vs) = prove(&fresh);
+        assert_eq!(proof.to_bytes(), fresh_proof.to_bytes());
+        assert_eq!(Vs, fresh_Vs);
+    }
+
+    #[test]
+    fn gens_bytes_round_trip() {
+        let gens = BulletproofGens::new(64, 4);
+        let bytes = gens.to_bytes();
+        assert_eq!(bytes.len(), 8 + 64 * 4 * 2 * 32);
+
+        let decoded = BulletproofGens::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.gens_capacity, 64);
+        assert_eq!(decoded.party_capacity, 4);
+        assert!(gens.G(64, 4).eq(decoded.G(64, 4)));
+        assert!(gens.H(64, 4).eq(decoded.H(64, 4)));
+
+        let serialized = bincode::serialize(&gens).unwrap();
+        let deserialized: BulletproofGens = bincode::deserialize(&serialized).unwrap();
+        assert!(gens.G(64, 4).eq(deserialized.G(64, 4)));
+        assert!(gens.H(64, 4).eq(deserialized.H(64, 4)));
+    }
+
+    #[test]
+    fn decoded_gens_verify_proofs_from_fresh_gens() {
+        use crate::RangeProof;
+        use merlin::Transcript;
+
+        let pc_gens = PedersenGens::default();
+        let fresh = BulletproofGens::new(32, 2);
+        let decoded = BulletproofGens::from_bytes(&fresh.to_bytes()).unwrap();
+
+        let blindings = [Scalar::from(11u64), Scalar::from(13u64)];
+        let mut transcript = Transcript::new(b"DecodedGensTest");
+        let (proof, Vs) = RangeProof::prove_multiple(
+            &fresh,
+            &pc_gens,
+            &mut transcript,
+            &[7, 1 << 31],
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"DecodedGensTest");
+        assert!(proof
+            .verify_multiple(&decoded, &pc_gens, &mut transcript, &Vs, 32)
+            .is_ok());
+    }
+
+    #[test]
+    fn gens_from_bytes_rejects_malformed_input() {
+        let bytes = BulletproofGens::new(8, 2).to_bytes();
+
+        assert_eq!(
+            BulletproofGens::from_bytes(&bytes[..bytes.len() - 32]).unwrap_err(),
+            ProofError::InvalidGeneratorsLength
+        );
+        assert_eq!(
+            BulletproofGens::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            ProofError::FormatError
+        );
+
+        // An encoding which does not decompress to a Ristretto point.
+        let mut bad_point = bytes.clone();
+        bad_point[8..40].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            BulletproofGens::from_bytes(&bad_point).unwrap_err(),
+            ProofError::FormatError
+        );
+    }
+
+    #[test]
+    fn gens_from_bytes_validated_rejects_rogue_points() {
+        let bytes = BulletproofGens::new(16, 2).to_bytes();
+        let validated = BulletproofGens::from_bytes_validated(&bytes).unwrap();
+        assert_eq!(validated.to_bytes(), bytes);
+
+        // Swapping two valid generators, or replacing one by another
+        // valid point, is only caught by the validated decoder.
+        let mut swapped = bytes.clone();
+        let (first, second) = (swapped[8..40].to_vec(), swapped[40..72].to_vec());
+        swapped[8..40].copy_from_slice(&second);
+        swapped[40..72].copy_from_slice(&first);
+
+        let mut rogue = bytes.clone();
+        let point = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"rogue generator");
+        let last = rogue.len() - 32;
+        rogue[last..].copy_from_slice(point.compress().as_bytes());
+
+        for tampered in [swapped, rogue] {
+            assert!(BulletproofGens::from_bytes(&tampered).is_ok());
+            assert_eq!(
+                BulletproofGens::from_bytes_validated(&tampered).unwrap_err(),
+                ProofError::FormatError
+            );
+        }
+    }
+
+    #[test]
+    fn decoded_labeled_gens_keep_their_label() {
+        let myapp = BulletproofGens::new_with_label(b"myapp", 16, 2);
+        let bytes = myapp.to_bytes();
+
+        // The label is not encoded, so validating against the default
+        // generators or another label fails.
+        assert_eq!(
+            BulletproofGens::from_bytes_validated(&bytes).unwrap_err(),
+            ProofError::FormatError
+        );
+        assert_eq!(
+            BulletproofGens::from_bytes_validated_with_label(&bytes, b"otherapp").unwrap_err(),
+            ProofError::FormatError
+        );
+
+        let decoded = BulletproofGens::from_bytes_with_label(&bytes, b"myapp").unwrap();
+        assert_eq!(decoded.label(), Some(&b"myapp"[..]));
+
+        // Validated generators grow along their label's chains.
+        let mut validated =
+            BulletproofGens::from_bytes_validated_with_label(&bytes, b"myapp").unwrap();
+        assert_eq!(validated.label(), Some(&b"myapp"[..]));
+        validated.extend_to(32, 4).unwrap();
+        let bigger = BulletproofGens::new_with_label(b"myapp", 32, 4);
+        assert_eq!(validated.G_vec, bigger.G_vec);
+        assert_eq!(validated.H_vec, bigger.H_vec);
+    }
+
+    #[test]
+    fn unvalidated_gens_cannot_grow() {
+        let bytes = BulletproofGens::new(16, 2).to_bytes();
+        let mut decoded = BulletproofGens::from_bytes(&bytes).unwrap();
+
+        // Growing to the current capacities, or less, is a no-op.
+        assert_eq!(decoded.extend_to(16, 2), Ok(()));
+        assert_eq!(decoded.extend(8, 1), Ok(()));
+
+        assert_eq!(decoded.extend_to(32, 2), Err(GensError::UnknownDerivation));
+        assert_eq!(decoded.extend(16, 4), Err(GensError::UnknownDerivation));
+        assert_eq!(
+            decoded.try_increase_capacity(32),
+            Err(GensError::UnknownDerivation)
+        );
+        assert_eq!(decoded.try_increase_capacity(16), Ok(()));
+        decoded.increase_capacity(32);
+        decoded.increase_party_capacity(4);
+        assert_eq!((decoded.gens_capacity, decoded.party_capacity), (16, 2));
+
+        let mut labeled = BulletproofGens::from_bytes_with_label(
+            &BulletproofGens::new_with_label(b"myapp", 16, 2).to_bytes(),
+            b"myapp",
+        )
+        .unwrap();
+        assert_eq!(labeled.extend_to(32, 2), Err(GensError::UnknownDerivation));
+
+        let mut validated = BulletproofGens::from_bytes_validated(&bytes).unwrap();
+        assert_eq!(validated.extend_to(32, 4), Ok(()));
+        assert_eq!(validated.G_vec, BulletproofGens::new(32, 4).G_vec);
+    }
 }
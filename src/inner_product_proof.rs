@@ -11,10 +11,20 @@ use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::VartimeMultiscalarMul;
 use merlin::Transcript;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
 
 use crate::errors::ProofError;
 use crate::transcript::TranscriptProtocol;
-
+use crate::util;
+
+/// An inner-product argument, proving knowledge of vectors
+/// \\(\mathbf{a}, \mathbf{b}\\) such that
+/// \\(P = \langle \mathbf{a}, \mathbf{G} \rangle + \langle \mathbf{b}, \mathbf{H'} \rangle + \langle \mathbf{a}, \mathbf{b} \rangle Q\\),
+/// as in §3 of the Bulletproofs paper.
+///
+/// The proof has size logarithmic in the length of the vectors.
 #[derive(Clone, Debug)]
 pub struct InnerProductProof {
     pub(crate) L_vec: Vec<CompressedRistretto>,
@@ -24,6 +34,44 @@ pub struct InnerProductProof {
 }
 
 impl InnerProductProof {
+    /// Create an inner-product proof for the vectors `a_vec` and
+    /// `b_vec`, with respect to the generators `G` and `H`.
+    ///
+    /// This is the same as [`InnerProductProof::create`], but borrows
+    /// the generators.  See it for the requirements on the inputs: in
+    /// particular, all lengths must be the same power of two `n`.
+    ///
+    /// The proof shows that the prover knows `a_vec` and `b_vec` such
+    /// that \\(P = \langle \mathbf{a}, \mathbf{G}' \rangle + \langle \mathbf{b}, \mathbf{H}' \rangle + c \cdot Q\\)
+    /// for \\(c = \langle \mathbf{a}, \mathbf{b} \rangle\\), where
+    /// \\(G'\_i = G\_i \cdot \texttt{G\\_factors}\_i\\) and
+    /// \\(H'\_i = H\_i \cdot \texttt{H\\_factors}\_i\\).  It can be
+    /// checked with [`InnerProductProof::verify`].
+    ///
+    /// Returns [`ProofError::InvalidInputLength`] if the lengths differ
+    /// or are not a power of two.
+    pub fn prove(
+        transcript: &mut Transcript,
+        Q: &RistrettoPoint,
+        G_factors: &[Scalar],
+        H_factors: &[Scalar],
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+        a_vec: Vec<Scalar>,
+        b_vec: Vec<Scalar>,
+    ) -> Result<InnerProductProof, ProofError> {
+        InnerProductProof::create(
+            transcript,
+            Q,
+            G_factors,
+            H_factors,
+            G.to_vec(),
+            H.to_vec(),
+            a_vec,
+            b_vec,
+        )
+    }
+
     /// Create an inner-product proof.
     ///
     /// The proof is created with respect to the bases \\(G\\), \\(H'\\),
@@ -33,8 +81,8 @@ impl InnerProductProof {
     /// challenges depend on the *entire* transcript (including parent
     /// protocols).
     ///
-    /// The lengths of the vectors must all be the same, and must all be
-    /// either 0 or a power of 2.
+    /// The lengths of the vectors must all be the same power of 2,
+    /// otherwise [`ProofError::InvalidInputLength`] is returned.
     pub fn create(
         transcript: &mut Transcript,
         Q: &RistrettoPoint,
@@ -44,7 +92,22 @@ impl InnerProductProof {
         mut H_vec: Vec<RistrettoPoint>,
         mut a_vec: Vec<Scalar>,
         mut b_vec: Vec<Scalar>,
-    ) -> InnerProductProof {
+    ) -> Result<InnerProductProof, ProofError> {
+        // All of the input vectors must have the same length, which
+        // must be a power of two.
+        let mut n = G_vec.len();
+        if H_vec.len() != n
+            || a_vec.len() != n
+            || b_vec.len() != n
+            || G_factors.len() != n
+            || H_factors.len() != n
+            || !n.is_power_of_two()
+        {
+            a_vec.zeroize();
+            b_vec.zeroize();
+            return Err(ProofError::InvalidInputLength);
+        }
+
         // Create slices G, H, a, b backed by their respective
         // vectors.  This lets us reslice as we compress the lengths
         // of the vectors in the main loop below.
@@ -53,19 +116,6 @@ impl InnerProductProof {
         let mut a = &mut a_vec[..];
         let mut b = &mut b_vec[..];
 
-        let mut n = G.len();
-
-        // All of the input vectors must have the same length.
-        assert_eq!(G.len(), n);
-        assert_eq!(H.len(), n);
-        assert_eq!(a.len(), n);
-        assert_eq!(b.len(), n);
-        assert_eq!(G_factors.len(), n);
-        assert_eq!(H_factors.len(), n);
-
-        // All of the input vectors must have a length that is a power of two.
-        assert!(n.is_power_of_two());
-
         transcript.innerproduct_domain_sep(n as u64);
 
         let lg_n = n.next_power_of_two().trailing_zeros() as usize;
@@ -184,18 +234,28 @@ impl InnerProductProof {
             H = H_L;
         }
 
-        InnerProductProof {
+        let (a, b) = (a[0], b[0]);
+
+        // The folded vectors are derived from the prover's witness.
+        a_vec.zeroize();
+        b_vec.zeroize();
+
+        Ok(InnerProductProof {
             L_vec: L_vec,
             R_vec: R_vec,
-            a: a[0],
-            b: b[0],
-        }
+            a,
+            b,
+        })
     }
 
     /// Computes three vectors of verification scalars \\([u\_{i}^{2}]\\), \\([u\_{i}^{-2}]\\) and \\([s\_{i}]\\) for combined multiscalar multiplication
     /// in a parent protocol. See [inner product protocol notes](index.html#verification-equation) for details.
     /// The verifier must provide the input length \\(n\\) explicitly to avoid unbounded allocation within the inner product proof.
-    pub(crate) fn verification_scalars(
+    ///
+    /// This appends the proof to `transcript`, so it replaces
+    /// [`InnerProductProof::verify`] for protocols which fold the
+    /// inner-product check into their own multiscalar multiplication.
+    pub fn verification_scalars(
         &self,
         n: usize,
         transcript: &mut Transcript,
@@ -252,11 +312,17 @@ impl InnerProductProof {
         Ok((challenges_sq, challenges_inv_sq, s))
     }
 
-    /// This method is for testing that proof generation work,
-    /// but for efficiency the actual protocols would use `verification_scalars`
-    /// method to combine inner product verification with other checks
+    /// Verifies the proof for the commitment `P` to vectors of length
+    /// `n`, which must be a power of two, with respect to the generators
+    /// `G` and `H` scaled by `G_factors` and `H_factors`.
+    ///
+    /// The `transcript` must be in the same state as the prover's was.
+    /// Returns [`ProofError::InvalidInputLength`] if `G` and `H` do not
+    /// have length `n`, or if there are fewer than `n` factors.
+    ///
+    /// Protocols embedding an inner-product argument can be more
+    /// efficient by combining its verification with their other checks
     /// in a single multiscalar multiplication.
-    #[allow(dead_code)]
     pub fn verify<IG, IH>(
         &self,
         n: usize,
@@ -274,21 +340,36 @@ impl InnerProductProof {
         IH: IntoIterator,
         IH::Item: Borrow<Scalar>,
     {
+        // Only the first n factors are used, so the factors may be
+        // given as unbounded iterators.
+        let G_factors: Vec<Scalar> = G_factors
+            .into_iter()
+            .take(n)
+            .map(|g_i| *g_i.borrow())
+            .collect();
+        let H_factors: Vec<Scalar> = H_factors
+            .into_iter()
+            .take(n)
+            .map(|h_i| *h_i.borrow())
+            .collect();
+        if G.len() != n || H.len() != n || G_factors.len() != n || H_factors.len() != n {
+            return Err(ProofError::InvalidInputLength);
+        }
+
         let (u_sq, u_inv_sq, s) = self.verification_scalars(n, transcript)?;
 
         let g_times_a_times_s = G_factors
-            .into_iter()
+            .iter()
             .zip(s.iter())
-            .map(|(g_i, s_i)| (self.a * s_i) * g_i.borrow())
-            .take(G.len());
+            .map(|(g_i, s_i)| (self.a * s_i) * g_i);
 
         // 1/s[i] is s[!i], and !i runs from n-1 to 0 as i runs from 0 to n-1
         let inv_s = s.iter().rev();
 
         let h_times_b_div_s = H_factors
-            .into_iter()
+            .iter()
             .zip(inv_s)
-            .map(|(h_i, s_i_inv)| (self.b * s_i_inv) * h_i.borrow());
+            .map(|(h_i, s_i_inv)| (self.b * s_i_inv) * h_i);
 
         let neg_u_sq = u_sq.iter().map(|ui| -ui);
         let neg_u_inv_sq = u_inv_sq.iter().map(|ui| -ui);
@@ -325,13 +406,57 @@ impl InnerProductProof {
         }
     }
 
-    /// Returns the size in bytes required to serialize the inner
-    /// product proof.
+    /// Returns the size in bytes required to serialize an inner
+    /// product proof for vectors of length `nm`, a power of two.
     ///
     /// For vectors of length `n` the proof size is
     /// \\(32 \cdot (2\lg n+2)\\) bytes.
-    pub fn serialized_size(&self) -> usize {
-        (self.L_vec.len() * 2 + 2) * 32
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nm` is not a power of two.
+    pub const fn serialized_size(nm: usize) -> usize {
+        assert!(nm.is_power_of_two(), "nm must be a power of two");
+        InnerProductProof::serialized_size_lg(nm.trailing_zeros() as usize)
+    }
+
+    /// Returns the size in bytes required to serialize an inner
+    /// product proof for vectors of length \\(2\^{\mathtt{lg\_nm}}\\),
+    /// without computing the length itself.
+    pub(crate) const fn serialized_size_lg(lg_nm: usize) -> usize {
+        (lg_nm * 2 + 2) * 32
+    }
+
+    /// Returns the size in bytes of this proof's encoding, as returned
+    /// by [`InnerProductProof::to_bytes`].
+    pub fn len_bytes(&self) -> usize {
+        Self::serialized_size(1 << self.L_vec.len())
+    }
+
+    /// Returns the \\(L_j\\) points of the proof, one per round.
+    pub fn L_vec(&self) -> &[CompressedRistretto] {
+        &self.L_vec
+    }
+
+    /// Returns the \\(R_j\\) points of the proof, one per round.
+    pub fn R_vec(&self) -> &[CompressedRistretto] {
+        &self.R_vec
+    }
+
+    /// Returns the number of rounds of the proof, \\(\lg n\\) for
+    /// vectors of length \\(n\\).
+    pub fn n_rounds(&self) -> usize {
+        self.L_vec.len()
+    }
+
+    /// Returns the final scalar \\(a\\) of the proof.
+    pub fn a(&self) -> Scalar {
+        self.a
+    }
+
+    /// Returns the final scalar \\(b\\) of the proof.
+    pub fn b(&self) -> Scalar {
+        self.b
     }
 
     /// Serializes the proof into a byte array of \\(2n+2\\) 32-byte elements.
@@ -339,7 +464,7 @@ impl InnerProductProof {
     /// * \\(n\\) pairs of compressed Ristretto points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
     /// * two scalars \\(a, b\\).
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(self.serialized_size());
+        let mut buf = Vec::with_capacity(self.len_bytes());
         for (l, r) in self.L_vec.iter().zip(self.R_vec.iter()) {
             buf.extend_from_slice(l.as_bytes());
             buf.extend_from_slice(r.as_bytes());
@@ -364,12 +489,25 @@ impl InnerProductProof {
             .copied()
     }
 
+    /// Returns the 32-byte elements of the encoding, in the order of
+    /// [`InnerProductProof::to_bytes`].
+    pub(crate) fn elements(&self) -> impl Iterator<Item = &[u8; 32]> + '_ {
+        self.L_vec
+            .iter()
+            .zip(self.R_vec.iter())
+            .flat_map(|(l, r)| iter::once(l.as_bytes()).chain(iter::once(r.as_bytes())))
+            .chain(iter::once(self.a.as_bytes()))
+            .chain(iter::once(self.b.as_bytes()))
+    }
+
     /// Deserializes the proof from a byte slice.
     /// Returns an error in the following cases:
     /// * the slice does not have \\(2n+2\\) 32-byte elements,
     /// * \\(n\\) is larger or equal to 32 (proof is too big),
-    /// * any of \\(2n\\) points are not valid compressed Ristretto points,
     /// * any of 2 scalars are not canonical scalars modulo Ristretto group order.
+    ///
+    /// The \\(2n\\) points are not decompressed, so invalid points are
+    /// only rejected at verification.
     pub fn from_bytes(slice: &[u8]) -> Result<InnerProductProof, ProofError> {
         let b = slice.len();
         if b % 32 != 0 {
@@ -407,6 +545,75 @@ impl InnerProductProof {
     }
 }
 
+/// Serializes the proof as its [`InnerProductProof::to_bytes`]
+/// encoding, like a [`RangeProof`](crate::RangeProof): a lowercase hex
+/// string in human-readable formats, and raw bytes otherwise.
+impl Serialize for InnerProductProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&util::to_hex(&self.to_bytes()))
+        } else {
+            serializer.serialize_bytes(&self.to_bytes())
+        }
+    }
+}
+
+/// Deserializes the proof with [`InnerProductProof::from_bytes`], from
+/// a hex string in human-readable formats and from raw bytes otherwise.
+impl<'de> Deserialize<'de> for InnerProductProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = if deserializer.is_human_readable() {
+            let hex = alloc::string::String::deserialize(deserializer)?;
+            util::from_hex(&hex).ok_or_else(|| serde::de::Error::custom("invalid hex encoding"))?
+        } else {
+            Vec::deserialize(deserializer)?
+        };
+        // Using Error::custom requires T: Display, which our error
+        // type only implements when it implements std::error::Error.
+        #[cfg(feature = "std")]
+        return InnerProductProof::from_bytes(&bytes).map_err(serde::de::Error::custom);
+        // In no-std contexts, drop the error message.
+        #[cfg(not(feature = "std"))]
+        return InnerProductProof::from_bytes(&bytes)
+            .map_err(|_| serde::de::Error::custom("deserialization error"));
+    }
+}
+
+impl ConstantTimeEq for InnerProductProof {
+    /// Compares the proofs in constant time, except for the number of
+    /// rounds, which is public.
+    fn ct_eq(&self, other: &Self) -> Choice {
+        if self.L_vec.len() != other.L_vec.len() || self.R_vec.len() != other.R_vec.len() {
+            return Choice::from(0);
+        }
+
+        let points = self
+            .L_vec
+            .iter()
+            .zip(other.L_vec.iter())
+            .chain(self.R_vec.iter().zip(other.R_vec.iter()))
+            .fold(Choice::from(1), |acc, (x, y)| {
+                acc & x.as_bytes().ct_eq(y.as_bytes())
+            });
+
+        points & self.a.ct_eq(&other.a) & self.b.ct_eq(&other.b)
+    }
+}
+
+impl PartialEq for InnerProductProof {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for InnerProductProof {}
+
 /// Computes an inner product of two vectors
 /// \\[
 ///    {\langle {\mathbf{a}}, {\mathbf{b}} \rangle} = \sum\_{i=0}^{n-1} a\_i \cdot b\_i.
@@ -427,10 +634,9 @@ pub fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
 mod tests {
     use super::*;
 
-    use crate::util;
     use sha3::Sha3_512;
 
-    fn test_helper_create(n: usize) {
+    fn test_helper_create(n: usize) -> InnerProductProof {
         let mut rng = rand::thread_rng();
 
         use crate::generators::BulletproofGens;
@@ -476,7 +682,8 @@ mod tests {
             H.clone(),
             a.clone(),
             b.clone(),
-        );
+        )
+        .unwrap();
 
         let mut verifier = Transcript::new(b"innerproducttest");
         assert!(proof
@@ -506,6 +713,87 @@ mod tests {
                 &H
             )
             .is_ok());
+        proof
+    }
+
+    #[test]
+    fn serialized_size_is_exact() {
+        for nm in [2, 4, 8, 16, 32, 64, 128] {
+            let proof = test_helper_create(nm);
+            let size = InnerProductProof::serialized_size(nm);
+            assert_eq!(size, 32 * (2 * nm.trailing_zeros() as usize + 2));
+            assert_eq!(proof.to_bytes().len(), size);
+            assert_eq!(proof.len_bytes(), size);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn serialized_size_rejects_non_powers_of_two() {
+        InnerProductProof::serialized_size(12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn serialized_size_rejects_zero() {
+        InnerProductProof::serialized_size(0);
+    }
+
+    #[test]
+    fn prove_and_verify_inner_product() {
+        let mut rng = rand::thread_rng();
+        let n = 16;
+
+        use crate::generators::BulletproofGens;
+        let bp_gens = BulletproofGens::new(n, 1);
+        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(n).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(n).cloned().collect();
+        let Q = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point");
+
+        let a: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let b: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let c = inner_product(&a, &b);
+
+        let ones = vec![Scalar::ONE; n];
+        let commit = |c: Scalar| {
+            RistrettoPoint::vartime_multiscalar_mul(
+                a.iter().chain(b.iter()).chain(iter::once(&c)),
+                G.iter().chain(H.iter()).chain(iter::once(&Q)),
+            )
+        };
+
+        let mut transcript = Transcript::new(b"innerproducttest");
+        let proof = InnerProductProof::prove(
+            &mut transcript,
+            &Q,
+            &ones,
+            &ones,
+            &G,
+            &H,
+            a.clone(),
+            b.clone(),
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"innerproducttest");
+        assert!(proof
+            .verify(n, &mut transcript, &ones, &ones, &commit(c), &Q, &G, &H)
+            .is_ok());
+
+        let mut transcript = Transcript::new(b"innerproducttest");
+        assert_eq!(
+            proof.verify(
+                n,
+                &mut transcript,
+                &ones,
+                &ones,
+                &commit(c + Scalar::ONE),
+                &Q,
+                &G,
+                &H
+            ),
+            Err(ProofError::VerificationError)
+        );
     }
 
     #[test]
@@ -523,6 +811,11 @@ mod tests {
         test_helper_create(4);
     }
 
+    #[test]
+    fn make_ipp_16() {
+        test_helper_create(16);
+    }
+
     #[test]
     fn make_ipp_32() {
         test_helper_create(32);
@@ -533,6 +826,111 @@ mod tests {
         test_helper_create(64);
     }
 
+    #[test]
+    fn mismatched_lengths_are_rejected() {
+        let n = 4;
+        use crate::generators::BulletproofGens;
+        let bp_gens = BulletproofGens::new(8, 1);
+        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(n).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(n).cloned().collect();
+        let Q = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point");
+        let ones = vec![Scalar::ONE; n];
+
+        let prove = |G: &[RistrettoPoint], H: &[RistrettoPoint], a_len: usize, b_len: usize| {
+            let mut transcript = Transcript::new(b"innerproducttest");
+            InnerProductProof::prove(
+                &mut transcript,
+                &Q,
+                &ones[..G.len().min(n)],
+                &ones[..H.len().min(n)],
+                G,
+                H,
+                vec![Scalar::ONE; a_len],
+                vec![Scalar::ONE; b_len],
+            )
+        };
+        assert!(prove(&G, &H, n, n).is_ok());
+        assert_eq!(
+            prove(&G, &H, n, n - 1).unwrap_err(),
+            ProofError::InvalidInputLength
+        );
+        assert_eq!(
+            prove(&G, &H[..2], n, n).unwrap_err(),
+            ProofError::InvalidInputLength
+        );
+        // Lengths which are not a power of two, or zero.
+        assert_eq!(
+            prove(&G[..3], &H[..3], 3, 3).unwrap_err(),
+            ProofError::InvalidInputLength
+        );
+        assert_eq!(
+            prove(&[], &[], 0, 0).unwrap_err(),
+            ProofError::InvalidInputLength
+        );
+
+        let proof = prove(&G, &H, n, n).unwrap();
+        let P = RistrettoPoint::vartime_multiscalar_mul(
+            ones.iter()
+                .chain(ones.iter())
+                .chain(iter::once(&Scalar::from(4u64))),
+            G.iter().chain(H.iter()).chain(iter::once(&Q)),
+        );
+        let verify = |G_factors: &[Scalar], G: &[RistrettoPoint]| {
+            let mut transcript = Transcript::new(b"innerproducttest");
+            proof.verify(n, &mut transcript, G_factors, &ones, &P, &Q, G, &H)
+        };
+        assert_eq!(verify(&ones, &G), Ok(()));
+        assert_eq!(verify(&ones, &G[..2]), Err(ProofError::InvalidInputLength));
+        assert_eq!(verify(&ones[..2], &G), Err(ProofError::InvalidInputLength));
+    }
+
+    #[test]
+    fn ipp_serde_round_trip() {
+        let n = 16;
+        let mut rng = rand::thread_rng();
+        use crate::generators::BulletproofGens;
+        let bp_gens = BulletproofGens::new(n, 1);
+        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(n).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(n).cloned().collect();
+        let Q = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point");
+        let ones = vec![Scalar::ONE; n];
+
+        let mut transcript = Transcript::new(b"innerproducttest");
+        let proof = InnerProductProof::prove(
+            &mut transcript,
+            &Q,
+            &ones,
+            &ones,
+            &G,
+            &H,
+            (0..n).map(|_| Scalar::random(&mut rng)).collect(),
+            (0..n).map(|_| Scalar::random(&mut rng)).collect(),
+        )
+        .unwrap();
+
+        let json = serde_json::to_value(&proof).unwrap();
+        assert_eq!(json, serde_json::json!(util::to_hex(&proof.to_bytes())));
+        assert_eq!(
+            serde_json::from_value::<InnerProductProof>(json).unwrap(),
+            proof
+        );
+
+        let bytes = bincode::serialize(&proof).unwrap();
+        assert_eq!(
+            bincode::deserialize::<InnerProductProof>(&bytes).unwrap(),
+            proof
+        );
+
+        // A non-canonical scalar is rejected by from_bytes.
+        let mut bytes = proof.to_bytes();
+        let len = bytes.len();
+        bytes[len - 32..].copy_from_slice(&[0xff; 32]);
+        assert!(
+            serde_json::from_value::<InnerProductProof>(serde_json::json!(util::to_hex(&bytes)))
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_inner_product() {
         let a = vec![
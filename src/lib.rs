@@ -31,10 +31,27 @@ mod linear_proof;
 mod range_proof;
 mod transcript;
 
-pub use crate::errors::ProofError;
-pub use crate::generators::{BulletproofGens, BulletproofGensShare, PedersenGens};
+pub use crate::errors::{GensError, ProofError};
+#[cfg(feature = "mmap-gens")]
+pub use crate::generators::MappedGens;
+pub use crate::generators::{
+    required_capacities, BulletproofGens, BulletproofGensShare, PedersenGens,
+};
+pub use crate::inner_product_proof::InnerProductProof;
 pub use crate::linear_proof::LinearProof;
-pub use crate::range_proof::{RangeProof, RangeProofView};
+pub use crate::range_proof::backend::{DalekBackend, MultiscalarBackend};
+pub use crate::range_proof::bitsize::Bitsize;
+pub use crate::range_proof::bundle::{BatchBundle, BundleEntry};
+pub use crate::range_proof::commitments::Commitments;
+pub use crate::range_proof::signature::{Signature, SignatureOrder};
+pub use crate::range_proof::verification_key::RangeProofVerificationKey;
+pub use crate::range_proof::{
+    BatchCollector, BatchVerifier, DecompressedCommitment, RangeProof, RangeProofView,
+    ValueCommitment,
+};
+
+#[cfg(feature = "prover-introspection")]
+pub use crate::range_proof::trace::{BitTrace, PartyTrace, PolyTrace, ProverTrace};
 
 #[cfg_attr(feature = "docs", doc(include = "../docs/aggregation-api.md"))]
 pub mod range_proof_mpc {
@@ -42,8 +59,12 @@ pub mod range_proof_mpc {
     pub use crate::range_proof::dealer;
     pub use crate::range_proof::messages;
     pub use crate::range_proof::party;
+    pub use crate::range_proof::streaming;
 }
 
 #[cfg(feature = "yoloproofs")]
 #[cfg(feature = "std")]
 pub mod r1cs;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -116,7 +116,7 @@ impl R1CSProof {
         } else {
             14
         };
-        1 + elements * 32 + self.ipp_proof.serialized_size()
+        1 + elements * 32 + self.ipp_proof.len_bytes()
     }
 
     fn missing_phase2_commitments(&self) -> bool {
@@ -695,7 +695,8 @@ impl<'g, T: BorrowMut<Transcript>> Prover<'g, T> {
             gens.H(padded_n).cloned().collect(),
             l_vec,
             r_vec,
-        );
+        )
+        .expect("The padded vectors all have length padded_n, a power of two");
 
         // We do not yet have a ClearOnDrop wrapper for Vec<Scalar>.
         // When PR 202 [1] is merged, we can simply wrap s_L and s_R at the point of creation.
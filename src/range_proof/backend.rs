@@ -0,0 +1,243 @@
+//! The `backend` module contains the [`MultiscalarBackend`] trait, which
+//! lets batch verification run its multiscalar multiplication on an
+//! implementation other than the one of `curve25519-dalek`.
+
+#![allow(non_snake_case)]
+
+use core::iter;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::util::AssertSizeHint;
+
+use super::{BatchCollector, RangeProof, RangeProofView, ValueCommitment};
+
+/// A variable-time multiscalar multiplication, used to verify batches
+/// of range proofs with [`BatchCollector::verify_with_backend`].
+///
+/// The inputs are public, so implementations need not run in constant
+/// time.
+pub trait MultiscalarBackend {
+    /// Computes \\(\sum\_i s\_i \cdot P\_i\\) for the `scalars`
+    /// \\(s\_i\\) and the `points` \\(P\_i\\), which have the same
+    /// length.  Returns `None` if any of the points is `None`.
+    fn optional_multiscalar_mul<I, J>(&self, scalars: I, points: J) -> Option<RistrettoPoint>
+    where
+        I: IntoIterator<Item = Scalar>,
+        J: IntoIterator<Item = Option<RistrettoPoint>>;
+}
+
+/// The multiscalar multiplication of `curve25519-dalek`, used by
+/// [`BatchCollector::verify`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DalekBackend;
+
+impl MultiscalarBackend for DalekBackend {
+    #[inline]
+    fn optional_multiscalar_mul<I, J>(&self, scalars: I, points: J) -> Option<RistrettoPoint>
+    where
+        I: IntoIterator<Item = Scalar>,
+        J: IntoIterator<Item = Option<RistrettoPoint>>,
+    {
+        RistrettoPoint::optional_multiscalar_mul(scalars, points)
+    }
+}
+
+impl<'a> BatchCollector<'a> {
+    /// Verifies all the proofs added to the batch at once, like
+    /// [`BatchCollector::verify`], computing the multiscalar
+    /// multiplication with `backend`.
+    pub fn verify_with_backend<B: MultiscalarBackend>(self, backend: &B) -> Result<(), ProofError> {
+        // Each generator must line up with its scalar in the flat
+        // layout, so both sides cover exactly every party's generators.
+        let gens_len = self.gens_capacity * self.party_capacity;
+        debug_assert_eq!(self.dynamic_scalars.len(), self.dynamic_points.len());
+
+        let mega_check = backend
+            .optional_multiscalar_mul(
+                self.dynamic_scalars
+                    .into_iter()
+                    .chain(AssertSizeHint::new(self.g_scalars.into_iter(), gens_len))
+                    .chain(AssertSizeHint::new(self.h_scalars.into_iter(), gens_len))
+                    .chain(iter::once(self.pedersen_B_blinding_scalar))
+                    .chain(iter::once(self.pedersen_B_scalar)),
+                self.dynamic_points
+                    .into_iter()
+                    .chain(AssertSizeHint::new(
+                        self.bp_gens
+                            .G(self.gens_capacity, self.party_capacity)
+                            .copied()
+                            .map(Some),
+                        gens_len,
+                    ))
+                    .chain(AssertSizeHint::new(
+                        self.bp_gens
+                            .H(self.gens_capacity, self.party_capacity)
+                            .copied()
+                            .map(Some),
+                        gens_len,
+                    ))
+                    .chain(iter::once(Some(self.pc_gens.B_blinding)))
+                    .chain(iter::once(Some(self.pc_gens.B))),
+            )
+            .ok_or(ProofError::VerificationError)?;
+
+        use group::Group;
+        if mega_check.is_identity().into() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+impl RangeProof {
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch_with_rng`],
+    /// computing the multiscalar multiplication with `backend`.
+    pub fn verify_batch_with_backend_with_rng<
+        'a,
+        T: RngCore + CryptoRng,
+        V: ValueCommitment + 'a,
+        B: MultiscalarBackend,
+    >(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        backend: &B,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let mut collector = BatchCollector::new(bp_gens, pc_gens);
+        for view in batch {
+            collector.add_proof(view, rng)?;
+        }
+
+        collector.verify_with_backend(backend)
+    }
+
+    /// Verifies a batch of proofs, computing the multiscalar multiplication with `backend`.
+    /// This is a convenience wrapper around [`RangeProof::verify_batch_with_backend_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_batch_with_backend<'a, V: ValueCommitment + 'a, B: MultiscalarBackend>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        backend: &B,
+    ) -> Result<(), ProofError> {
+        Self::verify_batch_with_backend_with_rng(
+            batch,
+            bp_gens,
+            pc_gens,
+            backend,
+            &mut thread_rng(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::cell::Cell;
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use merlin::Transcript;
+
+    /// Counts the multiplications and the points it is given, and
+    /// forwards them to `curve25519-dalek`.
+    #[derive(Default)]
+    struct CountingBackend {
+        calls: Cell<usize>,
+        points: Cell<usize>,
+    }
+
+    impl MultiscalarBackend for CountingBackend {
+        fn optional_multiscalar_mul<I, J>(&self, scalars: I, points: J) -> Option<RistrettoPoint>
+        where
+            I: IntoIterator<Item = Scalar>,
+            J: IntoIterator<Item = Option<RistrettoPoint>>,
+        {
+            let points: Vec<_> = points.into_iter().collect();
+            self.calls.set(self.calls.get() + 1);
+            self.points.set(self.points.get() + points.len());
+            DalekBackend.optional_multiscalar_mul(scalars, points)
+        }
+    }
+
+    /// Fails every multiplication, like an unavailable device.
+    struct FailingBackend;
+
+    impl MultiscalarBackend for FailingBackend {
+        fn optional_multiscalar_mul<I, J>(&self, _: I, _: J) -> Option<RistrettoPoint>
+        where
+            I: IntoIterator<Item = Scalar>,
+            J: IntoIterator<Item = Option<RistrettoPoint>>,
+        {
+            None
+        }
+    }
+
+    #[test]
+    fn custom_backend_verifies_batch() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+
+        let proofs: Vec<(RangeProof, Vec<CompressedRistretto>)> = (0..3u64)
+            .map(|i| {
+                let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+                let mut transcript = Transcript::new(b"BackendTest");
+                RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &[i, 1 << 31],
+                    &blindings,
+                    32,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let verify = |proofs: &[(RangeProof, Vec<CompressedRistretto>)], backend: &_| {
+            let mut transcripts = vec![Transcript::new(b"BackendTest"); proofs.len()];
+            let views = proofs
+                .iter()
+                .zip(transcripts.iter_mut())
+                .map(|((proof, Vs), t)| proof.verification_view(t, Vs, 32));
+            RangeProof::verify_batch_with_backend(views, &bp_gens, &pc_gens, backend)
+        };
+
+        let backend = CountingBackend::default();
+        assert_eq!(verify(&proofs, &backend), Ok(()));
+        assert_eq!(backend.calls.get(), 1);
+        // The generators of two 32-bit parties, B, B_blinding, and for
+        // each proof A, S, T_1, T_2, two commitments and 6 rounds of
+        // L and R.
+        assert_eq!(backend.points.get(), 2 * 2 * 32 + 2 + 3 * (4 + 2 + 2 * 6));
+
+        let mut bad = proofs.clone();
+        bad[1].1.swap(0, 1);
+        assert_eq!(
+            verify(&bad, &CountingBackend::default()),
+            Err(ProofError::VerificationError)
+        );
+
+        let mut transcripts = vec![Transcript::new(b"BackendTest"); proofs.len()];
+        let views = proofs
+            .iter()
+            .zip(transcripts.iter_mut())
+            .map(|((proof, Vs), t)| proof.verification_view(t, Vs, 32));
+        assert_eq!(
+            RangeProof::verify_batch_with_backend(views, &bp_gens, &pc_gens, &FailingBackend),
+            Err(ProofError::VerificationError)
+        );
+    }
+}
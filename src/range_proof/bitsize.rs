@@ -0,0 +1,377 @@
+//! The [`Bitsize`] of a range proof, and the entry points taking it.
+//!
+//! The `usize` bitsizes of the other entry points are checked when
+//! proving or verifying, and fail with [`ProofError::InvalidBitsize`].
+//! A `Bitsize` is checked once, when it is constructed, so the typed
+//! entry points below never fail with it.  Their proofs are the same
+//! as the ones of the `usize` entry points for the same bitsize.
+//!
+//! A `Bitsize` only covers the padded bitsizes 8, 16, 32, 64 and 128.
+//! The `usize` entry points accept any bitsize in \\(1 \leq n \leq
+//! 128\\), and pad it to one of these, so use them for the other
+//! bitsizes.
+
+#![allow(non_snake_case)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+
+use super::{RangeProof, ValueCommitment};
+
+/// The bitsize \\(n\\) of the values in a range proof, which proves
+/// that they are in \\([0, 2^n)\\).
+///
+/// Only the padded bitsizes 8, 16, 32, 64 and 128 are covered.  The
+/// `usize` entry points such as [`RangeProof::prove_single_with_rng`]
+/// accept any bitsize in \\(1 \leq n \leq 128\\).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Bitsize {
+    /// Values in \\([0, 2^8)\\).
+    B8,
+    /// Values in \\([0, 2^{16})\\).
+    B16,
+    /// Values in \\([0, 2^{32})\\).
+    B32,
+    /// Values in \\([0, 2^{64})\\).
+    B64,
+    /// Values in \\([0, 2^{128})\\).
+    B128,
+}
+
+impl Bitsize {
+    /// Returns the number of bits \\(n\\).
+    pub const fn bits(self) -> usize {
+        match self {
+            Bitsize::B8 => 8,
+            Bitsize::B16 => 16,
+            Bitsize::B32 => 32,
+            Bitsize::B64 => 64,
+            Bitsize::B128 => 128,
+        }
+    }
+}
+
+impl From<Bitsize> for usize {
+    fn from(n: Bitsize) -> usize {
+        n.bits()
+    }
+}
+
+impl TryFrom<usize> for Bitsize {
+    type Error = ProofError;
+
+    /// Returns [`ProofError::InvalidBitsize`] unless `n` is 8, 16, 32,
+    /// 64 or 128.
+    fn try_from(n: usize) -> Result<Self, Self::Error> {
+        match n {
+            8 => Ok(Bitsize::B8),
+            16 => Ok(Bitsize::B16),
+            32 => Ok(Bitsize::B32),
+            64 => Ok(Bitsize::B64),
+            128 => Ok(Bitsize::B128),
+            _ => Err(ProofError::InvalidBitsize),
+        }
+    }
+}
+
+impl RangeProof {
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, like [`RangeProof::prove_single_with_rng`]
+    /// with a typed bitsize.
+    pub fn prove_single_typed_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: Bitsize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            n.into(),
+            rng,
+        )
+    }
+
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, with a typed bitsize.
+    /// This is a convenience wrapper around [`RangeProof::prove_single_typed_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_single_typed(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: Bitsize,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_single_typed_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof for a set of values, like
+    /// [`RangeProof::prove_multiple_with_rng`] with a typed bitsize.
+    pub fn prove_multiple_typed_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: Bitsize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n.into(),
+            rng,
+        )
+    }
+
+    /// Create a rangeproof for a set of values, with a typed bitsize.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_typed_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_multiple_typed(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: Bitsize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_typed_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof for a given value commitment \\(V\\), like
+    /// [`RangeProof::verify_single_with_rng`] with a typed bitsize.
+    pub fn verify_single_typed_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        n: Bitsize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_single_with_rng(bp_gens, pc_gens, transcript, V, n.into(), rng)
+    }
+
+    /// Verifies a rangeproof for a given value commitment \\(V\\), with
+    /// a typed bitsize.
+    /// This is a convenience wrapper around [`RangeProof::verify_single_typed_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_single_typed(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        n: Bitsize,
+    ) -> Result<(), ProofError> {
+        self.verify_single_typed_with_rng(bp_gens, pc_gens, transcript, V, n, &mut thread_rng())
+    }
+
+    /// Verifies an aggregated rangeproof for the given value
+    /// commitments, like [`RangeProof::verify_multiple_with_rng`] with a
+    /// typed bitsize.
+    pub fn verify_multiple_typed_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: Bitsize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n.into(),
+            rng,
+        )
+    }
+
+    /// Verifies an aggregated rangeproof for the given value
+    /// commitments, with a typed bitsize.
+    /// This is a convenience wrapper around [`RangeProof::verify_multiple_typed_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_multiple_typed(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: Bitsize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_typed_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitsizes_round_trip() {
+        for n in [
+            Bitsize::B8,
+            Bitsize::B16,
+            Bitsize::B32,
+            Bitsize::B64,
+            Bitsize::B128,
+        ] {
+            assert_eq!(Bitsize::try_from(usize::from(n)), Ok(n));
+        }
+        assert_eq!(usize::from(Bitsize::B32), 32);
+    }
+
+    #[test]
+    fn invalid_bitsizes_are_rejected() {
+        for n in [0, 1, 7, 12, 33, 63, 65, 127, 129, 256] {
+            assert_eq!(Bitsize::try_from(n), Err(ProofError::InvalidBitsize));
+        }
+    }
+
+    #[test]
+    fn typed_proofs_match_untyped_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 2);
+        let blindings = [Scalar::from(3u64), Scalar::from(4u64)];
+
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        let (proof, Vs) = RangeProof::prove_multiple_typed(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[300, 7],
+            &blindings,
+            Bitsize::B16,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        assert_eq!(
+            proof.verify_multiple_typed(&bp_gens, &pc_gens, &mut transcript, &Vs, Bitsize::B16),
+            Ok(())
+        );
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, 16),
+            Ok(())
+        );
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        assert!(proof
+            .verify_multiple_typed(&bp_gens, &pc_gens, &mut transcript, &Vs, Bitsize::B32)
+            .is_err());
+
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        let (proof, V) = RangeProof::prove_single_typed(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            u64::MAX,
+            &blindings[0],
+            Bitsize::B64,
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        assert_eq!(
+            proof.verify_single_typed(&bp_gens, &pc_gens, &mut transcript, &V, Bitsize::B64),
+            Ok(())
+        );
+
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        let (proof, V) = RangeProof::prove_single_typed(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            u64::MAX,
+            &blindings[0],
+            Bitsize::B128,
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        assert_eq!(
+            proof.verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 128),
+            Ok(())
+        );
+
+        // Values must still fit the bitsize.
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        assert_eq!(
+            RangeProof::prove_single_typed(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                256,
+                &blindings[0],
+                Bitsize::B8,
+            )
+            .unwrap_err(),
+            ProofError::ValueOutOfRange
+        );
+        // A proof of 256 made without the check does not verify either.
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        let (proof, Vs) = super::super::tests::prove_unchecked(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[Scalar::from(256u64)],
+            &[8],
+        );
+        let mut transcript = Transcript::new(b"BitsizeTest");
+        assert!(proof
+            .verify_single_typed(&bp_gens, &pc_gens, &mut transcript, &Vs[0], Bitsize::B8)
+            .is_err());
+    }
+}
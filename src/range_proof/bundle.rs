@@ -0,0 +1,303 @@
+//! The `bundle` module contains the [`BatchBundle`] type, a
+//! serializable collection of range proofs meant to be verified
+//! together.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use byteorder::{ByteOrder, LittleEndian};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use digest::Digest;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use sha3::Sha3_512;
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::util::read32;
+
+use super::RangeProof;
+
+/// A range proof in a [`BatchBundle`], along with the commitments to
+/// the values and the bitsize it was created for.
+#[derive(Clone, Debug)]
+pub struct BundleEntry {
+    /// The aggregated range proof.
+    pub proof: RangeProof,
+    /// The commitments to the proven values.
+    pub value_commitments: Vec<CompressedRistretto>,
+    /// The bitsize of the range.
+    pub n: usize,
+}
+
+impl BundleEntry {
+    /// Returns the size in bytes of the serialized entry.
+    fn serialized_size(&self) -> usize {
+        // 3 header words, m points, and the proof.
+        3 * 4 + self.value_commitments.len() * 32 + self.proof.encoded_size()
+    }
+
+    /// Serializes the entry, see [`BatchBundle::to_bytes`].
+    fn write_bytes(&self, buf: &mut Vec<u8>) {
+        let proof = self.proof.to_bytes();
+        let mut header = [0u8; 12];
+        LittleEndian::write_u32(&mut header[0..4], self.n as u32);
+        LittleEndian::write_u32(&mut header[4..8], self.value_commitments.len() as u32);
+        LittleEndian::write_u32(&mut header[8..12], proof.len() as u32);
+        buf.extend_from_slice(&header);
+        for V in self.value_commitments.iter() {
+            buf.extend_from_slice(V.as_bytes());
+        }
+        buf.extend_from_slice(&proof);
+    }
+
+    /// Returns the SHA3-512 hash of the serialized entry.
+    fn content_hash(&self) -> [u8; 64] {
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        self.write_bytes(&mut buf);
+        let mut hash = [0u8; 64];
+        hash.copy_from_slice(&Sha3_512::digest(&buf));
+        hash
+    }
+}
+
+/// A collection of range proofs to be verified together.
+///
+/// Each proof is verified on its own transcript, so the order of the
+/// proofs does not affect verification.  It does however affect the
+/// serialized bytes: use [`BatchBundle::canonicalize`] before
+/// serializing when the bundle bytes must only depend on the set of
+/// proofs, for instance to content-address a batch.
+#[derive(Clone, Debug, Default)]
+pub struct BatchBundle {
+    entries: Vec<BundleEntry>,
+}
+
+impl BatchBundle {
+    /// Creates an empty bundle.
+    pub fn new() -> Self {
+        BatchBundle {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Appends a proof of `n`-bit ranges for the given value commitments.
+    pub fn push(
+        &mut self,
+        proof: RangeProof,
+        value_commitments: Vec<CompressedRistretto>,
+        n: usize,
+    ) {
+        self.entries.push(BundleEntry {
+            proof,
+            value_commitments,
+            n,
+        });
+    }
+
+    /// Returns the entries of the bundle, in order.
+    pub fn entries(&self) -> &[BundleEntry] {
+        &self.entries
+    }
+
+    /// Sorts the entries by the SHA3-512 hash of their encoding, so
+    /// that the same set of proofs always serializes to the same bytes
+    /// regardless of insertion order.
+    ///
+    /// This is sound since each proof is verified on its own
+    /// transcript: verification does not depend on the order of the
+    /// entries.
+    pub fn canonicalize(&mut self) {
+        self.entries
+            .sort_by_cached_key(|entry| entry.content_hash());
+    }
+
+    /// Returns the size in bytes of the bundle's encoding, the length
+    /// of [`BatchBundle::to_bytes`].
+    ///
+    /// This is the entry count plus, for each entry, its header, its
+    /// value commitments and its proof.
+    pub fn serialized_size(&self) -> usize {
+        4 + self
+            .entries
+            .iter()
+            .map(|e| e.serialized_size())
+            .sum::<usize>()
+    }
+
+    /// Serializes the bundle.
+    ///
+    /// # Layout
+    ///
+    /// The layout of the bundle encoding is a little-endian `u32`
+    /// number of entries, followed by each entry:
+    ///
+    /// * little-endian `u32` values for the bitsize \\(n\\), the number
+    ///   of commitments \\(m\\) and the proof length in bytes,
+    /// * \\(m\\) compressed Ristretto points \\(V_0, \dots, V_{m-1}\\),
+    /// * the proof, as encoded by [`RangeProof::to_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        let mut count = [0u8; 4];
+        LittleEndian::write_u32(&mut count, self.entries.len() as u32);
+        buf.extend_from_slice(&count);
+        for entry in self.entries.iter() {
+            entry.write_bytes(&mut buf);
+        }
+        buf
+    }
+
+    /// Deserializes the bundle from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a
+    /// `BatchBundle`.
+    pub fn from_bytes(slice: &[u8]) -> Result<BatchBundle, ProofError> {
+        fn take<'a>(slice: &mut &'a [u8], len: usize) -> Result<&'a [u8], ProofError> {
+            if slice.len() < len {
+                return Err(ProofError::FormatError);
+            }
+            let (head, tail) = slice.split_at(len);
+            *slice = tail;
+            Ok(head)
+        }
+
+        let mut slice = slice;
+        let count = LittleEndian::read_u32(take(&mut slice, 4)?) as usize;
+
+        // Don't trust the count for preallocation.
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let header = take(&mut slice, 12)?;
+            let n = LittleEndian::read_u32(&header[0..4]) as usize;
+            let m = LittleEndian::read_u32(&header[4..8]) as usize;
+            let proof_len = LittleEndian::read_u32(&header[8..12]) as usize;
+
+            let commitments = take(
+                &mut slice,
+                m.checked_mul(32).ok_or(ProofError::FormatError)?,
+            )?;
+            let value_commitments = commitments
+                .chunks(32)
+                .map(|V| CompressedRistretto(read32(V)))
+                .collect();
+            let proof = RangeProof::from_bytes(take(&mut slice, proof_len)?)?;
+
+            entries.push(BundleEntry {
+                proof,
+                value_commitments,
+                n,
+            });
+        }
+
+        if !slice.is_empty() {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok(BatchBundle { entries })
+    }
+
+    /// Verifies all proofs of the bundle in a single batch, each on a
+    /// fresh transcript created with the given `label`.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        label: &'static [u8],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let mut transcripts: Vec<Transcript> = self
+            .entries
+            .iter()
+            .map(|_| Transcript::new(label))
+            .collect();
+
+        RangeProof::verify_batch_with_rng(
+            self.entries
+                .iter()
+                .zip(transcripts.iter_mut())
+                .map(|(entry, transcript)| {
+                    entry
+                        .proof
+                        .verification_view(transcript, &entry.value_commitments, entry.n)
+                }),
+            bp_gens,
+            pc_gens,
+            rng,
+        )
+    }
+
+    /// Verifies all proofs of the bundle in a single batch.
+    ///
+    /// This is a convenience wrapper around
+    /// [`BatchBundle::verify_with_rng`], passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        label: &'static [u8],
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(bp_gens, pc_gens, label, &mut thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    #[test]
+    fn canonicalize_is_independent_of_insertion_order() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let mut rng = rand::thread_rng();
+
+        let proofs: Vec<_> = [(8, 1), (64, 2), (32, 1)]
+            .iter()
+            .map(|&(n, m)| {
+                let values: Vec<u64> = (0..m).map(|i| i as u64).collect();
+                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+                let mut transcript = Transcript::new(b"BundleTest");
+                let (proof, commitments) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+                (proof, commitments, n)
+            })
+            .collect();
+
+        let mut forward = BatchBundle::new();
+        for (proof, commitments, n) in proofs.iter().cloned() {
+            forward.push(proof, commitments, n);
+        }
+        let mut backward = BatchBundle::new();
+        for (proof, commitments, n) in proofs.iter().rev().cloned() {
+            backward.push(proof, commitments, n);
+        }
+        assert_ne!(forward.to_bytes(), backward.to_bytes());
+
+        forward.canonicalize();
+        backward.canonicalize();
+        assert_eq!(forward.to_bytes(), backward.to_bytes());
+
+        assert_eq!(forward.to_bytes().len(), forward.serialized_size());
+        assert_eq!(
+            BatchBundle::new().to_bytes().len(),
+            BatchBundle::new().serialized_size()
+        );
+
+        let decoded = BatchBundle::from_bytes(&forward.to_bytes()).unwrap();
+        assert_eq!(decoded.to_bytes(), forward.to_bytes());
+        assert!(decoded.verify(&bp_gens, &pc_gens, b"BundleTest").is_ok());
+    }
+}
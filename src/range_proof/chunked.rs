@@ -0,0 +1,327 @@
+//! Range proofs for more values than the generators' party capacity,
+//! split into several aggregated proofs.
+//!
+//! # Chunking rule
+//!
+//! Let `c` be the largest power of two not exceeding the chunk
+//! capacity, which the prover and the verifier pass explicitly.  The
+//! values are split, in order, into consecutive chunks: each chunk
+//! takes the largest power of two not exceeding `c` nor the number of
+//! remaining values.  For instance, `m = 24` values with a chunk
+//! capacity of `8` give chunks of sizes `8, 8, 8`, and `m = 13` values
+//! give `8, 4, 1`.
+//!
+//! The `i`-th chunk is proven on its own transcript, created from the
+//! caller's label and bound to the chunk capacity, the total number of
+//! values `m`, the number of chunks and the chunk index `i`.
+//!
+//! The chunking does not depend on the generators: the generators only
+//! need a party capacity of at least `c`.  This rule is part of the
+//! proof format and will not change.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::transcript::TranscriptProtocol;
+
+use super::RangeProof;
+
+/// Returns the sizes of the chunks `m` values are split into, given
+/// the `chunk_capacity`.
+fn chunk_sizes(m: usize, chunk_capacity: usize) -> Result<Vec<usize>, ProofError> {
+    if chunk_capacity == 0 {
+        return Err(ProofError::InvalidAggregation);
+    }
+    // Largest power of two not exceeding the capacity.
+    let capacity = 1usize << (usize::BITS - 1 - chunk_capacity.leading_zeros());
+
+    let mut sizes = Vec::new();
+    let mut remaining = m;
+    while remaining > 0 {
+        let size = capacity.min(1usize << (usize::BITS - 1 - remaining.leading_zeros()));
+        sizes.push(size);
+        remaining -= size;
+    }
+    Ok(sizes)
+}
+
+/// Creates the transcripts of the chunks of `sizes`, for the given
+/// `chunk_capacity`.
+fn chunk_transcripts(
+    label: &'static [u8],
+    sizes: &[usize],
+    chunk_capacity: usize,
+) -> Vec<Transcript> {
+    let m: usize = sizes.iter().sum();
+    (0..sizes.len())
+        .map(|index| {
+            let mut transcript = Transcript::new(label);
+            transcript.rangeproof_chunk_domain_sep(
+                index as u64,
+                sizes.len() as u64,
+                m as u64,
+                chunk_capacity as u64,
+            );
+            transcript
+        })
+        .collect()
+}
+
+impl RangeProof {
+    /// Create rangeproofs for a set of values which may exceed the
+    /// party capacity of `bp_gens`, by splitting them into chunks of at
+    /// most `chunk_capacity` values.
+    ///
+    /// See the chunking rule in [`RangeProof::verify_chunked_with_rng`].
+    /// Returns one proof per chunk, along with the commitments to the
+    /// chunk's values, in order.
+    pub fn prove_chunked_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        label: &'static [u8],
+        chunk_capacity: usize,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<Vec<(RangeProof, Vec<CompressedRistretto>)>, ProofError> {
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+
+        let sizes = chunk_sizes(values.len(), chunk_capacity)?;
+        let mut offset = 0;
+        sizes
+            .iter()
+            .zip(chunk_transcripts(label, &sizes, chunk_capacity))
+            .map(|(&size, mut transcript)| {
+                let range = offset..offset + size;
+                offset += size;
+                RangeProof::prove_multiple_with_rng(
+                    bp_gens,
+                    pc_gens,
+                    &mut transcript,
+                    &values[range.clone()],
+                    &blindings[range],
+                    n,
+                    rng,
+                )
+            })
+            .collect()
+    }
+
+    /// Create rangeproofs for a set of values which may exceed the
+    /// party capacity of `bp_gens`, by splitting them into chunks of at
+    /// most `chunk_capacity` values.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::prove_chunked_with_rng`], passing in a threadsafe
+    /// RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_chunked(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        label: &'static [u8],
+        chunk_capacity: usize,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<Vec<(RangeProof, Vec<CompressedRistretto>)>, ProofError> {
+        RangeProof::prove_chunked_with_rng(
+            bp_gens,
+            pc_gens,
+            label,
+            chunk_capacity,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies chunked rangeproofs created by
+    /// [`RangeProof::prove_chunked_with_rng`], in a single batched
+    /// multiscalar multiplication.
+    ///
+    /// # Chunking rule
+    ///
+    /// Let `c` be the largest power of two not exceeding
+    /// `chunk_capacity`, which must match the prover's.  The values are
+    /// split, in order, into consecutive chunks, each taking the largest
+    /// power of two not exceeding `c` nor the number of remaining
+    /// values.  The `i`-th chunk is proven on a transcript created from
+    /// `label` and bound to `chunk_capacity`, the number of values, the
+    /// number of chunks and the index `i`.
+    ///
+    /// The chunking does not depend on `bp_gens`, which only needs a
+    /// party capacity of at least `c`.  Returns
+    /// [`ProofError::InvalidAggregation`] if the chunks do not follow
+    /// this rule.
+    pub fn verify_chunked_with_rng<T: RngCore + CryptoRng>(
+        chunks: &[(RangeProof, Vec<CompressedRistretto>)],
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        label: &'static [u8],
+        chunk_capacity: usize,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let m = chunks
+            .iter()
+            .map(|(_, commitments)| commitments.len())
+            .sum();
+        let sizes = chunk_sizes(m, chunk_capacity)?;
+        if sizes.len() != chunks.len()
+            || sizes
+                .iter()
+                .zip(chunks.iter())
+                .any(|(&size, (_, commitments))| size != commitments.len())
+        {
+            return Err(ProofError::InvalidAggregation);
+        }
+
+        let mut transcripts = chunk_transcripts(label, &sizes, chunk_capacity);
+
+        RangeProof::verify_batch_with_rng(
+            chunks
+                .iter()
+                .zip(transcripts.iter_mut())
+                .map(|((proof, commitments), transcript)| {
+                    proof.verification_view(transcript, commitments, n)
+                }),
+            bp_gens,
+            pc_gens,
+            rng,
+        )
+    }
+
+    /// Verifies chunked rangeproofs created by
+    /// [`RangeProof::prove_chunked_with_rng`].
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_chunked_with_rng`], passing in a threadsafe
+    /// RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_chunked(
+        chunks: &[(RangeProof, Vec<CompressedRistretto>)],
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        label: &'static [u8],
+        chunk_capacity: usize,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        RangeProof::verify_chunked_with_rng(
+            chunks,
+            bp_gens,
+            pc_gens,
+            label,
+            chunk_capacity,
+            n,
+            &mut thread_rng(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunking_rule() {
+        assert_eq!(chunk_sizes(24, 8), Ok(vec![8, 8, 8]));
+        assert_eq!(chunk_sizes(13, 8), Ok(vec![8, 4, 1]));
+        assert_eq!(chunk_sizes(13, 6), Ok(vec![4, 4, 4, 1]));
+        assert_eq!(chunk_sizes(0, 8), Ok(vec![]));
+        assert_eq!(chunk_sizes(1, 0), Err(ProofError::InvalidAggregation));
+    }
+
+    #[test]
+    fn prove_and_verify_24_chunked_by_8() {
+        let n = 32;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 8);
+        let mut rng = rand::thread_rng();
+
+        let values: Vec<u64> = (0..24).map(|i| i * 1000).collect();
+        let blindings: Vec<Scalar> = (0..24).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut chunks = RangeProof::prove_chunked(
+            &bp_gens,
+            &pc_gens,
+            b"ChunkedTest",
+            8,
+            &values,
+            &blindings,
+            n,
+        )
+        .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|(_, commitments)| commitments.len() == 8));
+
+        let verify = |chunks: &[(RangeProof, Vec<CompressedRistretto>)],
+                      bp_gens: &BulletproofGens,
+                      chunk_capacity: usize| {
+            RangeProof::verify_chunked(chunks, bp_gens, &pc_gens, b"ChunkedTest", chunk_capacity, n)
+        };
+        assert!(verify(&chunks, &bp_gens, 8).is_ok());
+
+        // The chunking does not depend on the verifier's generators.
+        assert!(verify(&chunks, &BulletproofGens::new(n, 16), 8).is_ok());
+        assert_eq!(
+            verify(&chunks, &BulletproofGens::new(n, 16), 16),
+            Err(ProofError::InvalidAggregation)
+        );
+
+        // Chunks are bound to their index.
+        chunks.swap(0, 2);
+        assert!(verify(&chunks, &bp_gens, 8).is_err());
+        chunks.swap(0, 2);
+
+        // Corrupt the middle chunk.
+        chunks[1].1[3] = pc_gens.commit(Scalar::from(1u64), Scalar::ONE).compress();
+        assert!(verify(&chunks, &bp_gens, 8).is_err());
+    }
+
+    #[test]
+    fn chunks_are_bound_to_the_number_of_values() {
+        let n = 8;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 8);
+        let mut rng = rand::thread_rng();
+
+        let values: Vec<u64> = (0..13).collect();
+        let blindings: Vec<Scalar> = (0..13).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut chunks = RangeProof::prove_chunked(
+            &bp_gens,
+            &pc_gens,
+            b"ChunkedTest",
+            8,
+            &values,
+            &blindings,
+            n,
+        )
+        .unwrap();
+        assert!(
+            RangeProof::verify_chunked(&chunks, &bp_gens, &pc_gens, b"ChunkedTest", 8, n).is_ok()
+        );
+
+        // Dropping the last chunk still follows the chunking rule, but
+        // the remaining chunks were proven for 13 values in 3 chunks.
+        chunks.pop();
+        assert!(
+            RangeProof::verify_chunked(&chunks, &bp_gens, &pc_gens, b"ChunkedTest", 8, n).is_err()
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! The `commitments` module contains the [`Commitments`] wrapper, which
+//! serializes value commitments in the same way as a
+//! [`RangeProof`](super::RangeProof).
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use serde::de::{SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::util;
+
+/// A list of value commitments, such as the ones returned by
+/// [`RangeProof::prove_multiple`](super::RangeProof::prove_multiple).
+///
+/// In human-readable formats such as JSON, each commitment is
+/// serialized as the lowercase hex string of its 32-byte compressed
+/// encoding.  Otherwise, as in bincode, the list is serialized exactly
+/// like a `Vec<CompressedRistretto>`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Commitments(pub Vec<CompressedRistretto>);
+
+impl From<Vec<CompressedRistretto>> for Commitments {
+    fn from(commitments: Vec<CompressedRistretto>) -> Self {
+        Commitments(commitments)
+    }
+}
+
+impl From<Commitments> for Vec<CompressedRistretto> {
+    fn from(commitments: Commitments) -> Self {
+        commitments.0
+    }
+}
+
+impl Deref for Commitments {
+    type Target = [CompressedRistretto];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Serialize for Commitments {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !serializer.is_human_readable() {
+            return self.0.serialize(serializer);
+        }
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for commitment in &self.0 {
+            seq.serialize_element(&util::to_hex(commitment.as_bytes()))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Commitments {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct HexVisitor;
+
+        impl<'de> Visitor<'de> for HexVisitor {
+            type Value = Commitments;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a sequence of 32-byte hex strings")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Commitments, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut commitments = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(hex) = seq.next_element::<String>()? {
+                    let bytes = util::from_hex(&hex)
+                        .filter(|bytes| bytes.len() == 32)
+                        .ok_or_else(|| serde::de::Error::custom("invalid commitment encoding"))?;
+                    commitments.push(CompressedRistretto(util::read32(&bytes)));
+                }
+                Ok(Commitments(commitments))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_seq(HexVisitor)
+        } else {
+            Vec::deserialize(deserializer).map(Commitments)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+    use crate::range_proof::RangeProof;
+    use curve25519_dalek::scalar::Scalar;
+    use merlin::Transcript;
+
+    fn proof_and_commitments() -> (RangeProof, Commitments) {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut transcript = Transcript::new(b"SerdeTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[7, 1 << 20],
+            &[Scalar::from(3u64), Scalar::from(4u64)],
+            32,
+        )
+        .unwrap();
+        (proof, commitments.into())
+    }
+
+    #[test]
+    fn json_uses_hex_strings() {
+        let (proof, commitments) = proof_and_commitments();
+
+        let json = serde_json::to_value(&proof).unwrap();
+        assert_eq!(json, serde_json::json!(util::to_hex(&proof.to_bytes())));
+        assert_eq!(serde_json::from_value::<RangeProof>(json).unwrap(), proof);
+
+        let json = serde_json::to_value(&commitments).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                util::to_hex(commitments[0].as_bytes()),
+                util::to_hex(commitments[1].as_bytes()),
+            ])
+        );
+        assert_eq!(
+            serde_json::from_value::<Commitments>(json).unwrap(),
+            commitments
+        );
+    }
+
+    #[test]
+    fn json_rejects_malformed_hex() {
+        let (proof, _) = proof_and_commitments();
+        let hex = util::to_hex(&proof.to_bytes());
+
+        assert!(serde_json::from_value::<RangeProof>(serde_json::json!(&hex[1..])).is_err());
+        assert!(
+            serde_json::from_value::<RangeProof>(serde_json::json!(&hex[..hex.len() - 64]))
+                .is_err()
+        );
+        // A non-canonical t_x fails the validation of from_bytes.
+        let mut bytes = proof.to_bytes();
+        bytes[4 * 32..5 * 32].copy_from_slice(&[0xff; 32]);
+        assert!(
+            serde_json::from_value::<RangeProof>(serde_json::json!(util::to_hex(&bytes))).is_err()
+        );
+
+        assert!(serde_json::from_value::<Commitments>(serde_json::json!(["00"])).is_err());
+        assert!(serde_json::from_value::<Commitments>(serde_json::json!([[0u8; 32]])).is_err());
+    }
+
+    #[test]
+    fn bincode_keeps_raw_bytes() {
+        let (proof, commitments) = proof_and_commitments();
+
+        // The encoding of proofs and commitments before hex support.
+        let mut old_proof = bincode::serialize(&(proof.to_bytes().len() as u64)).unwrap();
+        old_proof.extend_from_slice(&proof.to_bytes());
+        assert_eq!(bincode::serialize(&proof).unwrap(), old_proof);
+        assert_eq!(
+            bincode::deserialize::<RangeProof>(&old_proof).unwrap(),
+            proof
+        );
+
+        let old_commitments = bincode::serialize(&commitments.0).unwrap();
+        assert_eq!(bincode::serialize(&commitments).unwrap(), old_commitments);
+        assert_eq!(
+            bincode::deserialize::<Commitments>(&old_commitments).unwrap(),
+            commitments
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Range proofs that one committed value is strictly less than another.
+//!
+//! To prove \\(v_1 < v_2\\) for the commitments
+//! \\(V_1 = v_1 \cdot B + \tilde{v}_1 \cdot \widetilde{B}\\) and
+//! \\(V_2 = v_2 \cdot B + \tilde{v}_2 \cdot \widetilde{B}\\), the prover
+//! shows that \\(v_2 - v_1 - 1 \in [0, 2^n)\\) for the commitment
+//! \\(V_2 - V_1 - B\\), which the verifier derives from \\(V_1\\) and
+//! \\(V_2\\).  When \\(v_1 = v_2\\) or \\(v_1 > v_2\\), the difference
+//! wraps around to a scalar far outside of \\([0, 2^n)\\), so no proof
+//! exists.
+//!
+//! The difference is computed modulo the group order, so the statement
+//! only means \\(v_1 < v_2\\) when both values are known to lie in a
+//! range much smaller than the group order, for instance because they
+//! are range-proved themselves.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::transcript::TranscriptProtocol;
+
+use super::{RangeProof, ValueCommitment};
+
+impl RangeProof {
+    /// Create a rangeproof that `v1 < v2`, for the values `v1` and
+    /// `v2` committed with the blinding factors `v1_blinding` and
+    /// `v2_blinding`, and `v2 - v1 - 1` of at most `n` bits.
+    ///
+    /// The commitments are not returned, as they are
+    /// `pc_gens.commit(v1, v1_blinding)` and
+    /// `pc_gens.commit(v2, v2_blinding)`.  Returns
+    /// [`ProofError::ValueOutOfRange`] if `v1 >= v2`, or if
+    /// `v2 - v1 - 1` does not fit in `n` bits.
+    pub fn prove_less_than_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v1: u64,
+        v1_blinding: &Scalar,
+        v2: u64,
+        v2_blinding: &Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<RangeProof, ProofError> {
+        if v1 >= v2 {
+            return Err(ProofError::ValueOutOfRange);
+        }
+        let difference = v2 - v1 - 1;
+        if n < 64 && difference >> n != 0 {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        transcript.less_than_domain_sep();
+        let (proof, _) = RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            difference,
+            &(v2_blinding - v1_blinding),
+            n,
+            rng,
+        )?;
+        Ok(proof)
+    }
+
+    /// Create a rangeproof that `v1 < v2`.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::prove_less_than_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_less_than(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v1: u64,
+        v1_blinding: &Scalar,
+        v2: u64,
+        v2_blinding: &Scalar,
+        n: usize,
+    ) -> Result<RangeProof, ProofError> {
+        RangeProof::prove_less_than_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v1,
+            v1_blinding,
+            v2,
+            v2_blinding,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof that the value committed to in `V1` is
+    /// strictly less than the value committed to in `V2`.
+    ///
+    /// Returns [`ProofError::PointDecompressionError`] for the component
+    /// `"V1"` or `"V2"` if `V1` or `V2` respectively is not a valid
+    /// encoding.
+    pub fn verify_less_than_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V1: &impl ValueCommitment,
+        V2: &impl ValueCommitment,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let V1 = V1.decompress().ok_or(ProofError::PointDecompressionError {
+            proof_index: 0,
+            component: "V1",
+        })?;
+        let V2 = V2.decompress().ok_or(ProofError::PointDecompressionError {
+            proof_index: 0,
+            component: "V2",
+        })?;
+        let difference: RistrettoPoint = V2 - V1 - pc_gens.B;
+
+        transcript.less_than_domain_sep();
+        self.verify_single_with_rng(bp_gens, pc_gens, transcript, &difference, n, rng)
+    }
+
+    /// Verifies a rangeproof that the value committed to in `V1` is
+    /// strictly less than the value committed to in `V2`.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_less_than_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_less_than(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V1: &impl ValueCommitment,
+        V2: &impl ValueCommitment,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_less_than_with_rng(bp_gens, pc_gens, transcript, V1, V2, n, &mut thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::ristretto::CompressedRistretto;
+
+    fn less_than_helper(v1: u64, v2: u64, n: usize) -> Result<(), ProofError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+        let (r1, r2) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+
+        let mut transcript = Transcript::new(b"LessThanTest");
+        let proof =
+            RangeProof::prove_less_than(&bp_gens, &pc_gens, &mut transcript, v1, &r1, v2, &r2, n)?;
+
+        let V1 = pc_gens.commit(Scalar::from(v1), r1).compress();
+        let V2 = pc_gens.commit(Scalar::from(v2), r2).compress();
+        let mut transcript = Transcript::new(b"LessThanTest");
+        proof.verify_less_than(&bp_gens, &pc_gens, &mut transcript, &V1, &V2, n)
+    }
+
+    #[test]
+    fn less_than_relations() {
+        assert_eq!(less_than_helper(3, 4, 8), Ok(()));
+        assert_eq!(less_than_helper(0, u64::MAX, 64), Ok(()));
+        assert_eq!(less_than_helper(100, 356, 8), Ok(()));
+
+        assert_eq!(less_than_helper(4, 4, 8), Err(ProofError::ValueOutOfRange));
+        assert_eq!(less_than_helper(5, 4, 8), Err(ProofError::ValueOutOfRange));
+        // The difference must fit in n bits.
+        assert_eq!(
+            less_than_helper(100, 357, 8),
+            Err(ProofError::ValueOutOfRange)
+        );
+    }
+
+    #[test]
+    fn less_than_proof_does_not_verify_other_relations() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let (r1, r2) = (Scalar::from(3u64), Scalar::from(8u64));
+
+        let mut transcript = Transcript::new(b"LessThanTest");
+        let proof =
+            RangeProof::prove_less_than(&bp_gens, &pc_gens, &mut transcript, 10, &r1, 20, &r2, 32)
+                .unwrap();
+
+        let verify = |V1: &RistrettoPoint, V2: &RistrettoPoint| {
+            let mut transcript = Transcript::new(b"LessThanTest");
+            proof.verify_less_than(&bp_gens, &pc_gens, &mut transcript, V1, V2, 32)
+        };
+        let V1 = pc_gens.commit(Scalar::from(10u64), r1);
+        let V2 = pc_gens.commit(Scalar::from(20u64), r2);
+        assert!(verify(&V1, &V2).is_ok());
+
+        // Swapped commitments claim v2 < v1, and equal values are not
+        // strictly ordered.
+        assert!(verify(&V2, &V1).is_err());
+        assert!(verify(&V1, &V1).is_err());
+        assert!(verify(&V1, &pc_gens.commit(Scalar::from(10u64), r2)).is_err());
+
+        // The proof does not verify as a plain range proof.
+        let mut transcript = Transcript::new(b"LessThanTest");
+        assert!(proof
+            .verify_single(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &(V2 - V1 - pc_gens.B),
+                32
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_commitments_are_attributed() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let (r1, r2) = (Scalar::from(3u64), Scalar::from(8u64));
+        let mut transcript = Transcript::new(b"LessThanTest");
+        let proof =
+            RangeProof::prove_less_than(&bp_gens, &pc_gens, &mut transcript, 1, &r1, 2, &r2, 8)
+                .unwrap();
+
+        let invalid = CompressedRistretto([0xff; 32]);
+        let V = pc_gens.commit(Scalar::from(2u64), r2).compress();
+        let mut transcript = Transcript::new(b"LessThanTest");
+        assert_eq!(
+            proof.verify_less_than(&bp_gens, &pc_gens, &mut transcript, &invalid, &V, 8),
+            Err(ProofError::PointDecompressionError {
+                proof_index: 0,
+                component: "V1",
+            })
+        );
+        let mut transcript = Transcript::new(b"LessThanTest");
+        assert_eq!(
+            proof.verify_less_than(&bp_gens, &pc_gens, &mut transcript, &V, &invalid, 8),
+            Err(ProofError::PointDecompressionError {
+                proof_index: 0,
+                component: "V2",
+            })
+        );
+    }
+}
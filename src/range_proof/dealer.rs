@@ -41,13 +41,39 @@ impl Dealer {
         n: usize,
         m: usize,
     ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
-        if !(n == 8 || n == 16 || n == 32 || n == 64) {
-            return Err(MPCError::InvalidBitsize);
-        }
+        Dealer::new_versioned(bp_gens, pc_gens, transcript, n, m, false)
+    }
+
+    /// Creates a new dealer coordinating `m` parties proving `n`-bit
+    /// ranges with the version 0 transcript, which does not bind the
+    /// protocol version.
+    ///
+    /// The resulting proofs only verify with
+    /// [`RangeProof::verify_multiple_v0_with_rng`].  This is only meant
+    /// for interoperating with verifiers which have not been upgraded.
+    pub fn new_v0<'a, 'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        n: usize,
+        m: usize,
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        Dealer::new_versioned(bp_gens, pc_gens, transcript, n, m, true)
+    }
+
+    fn new_versioned<'a, 'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        n: usize,
+        m: usize,
+        v0: bool,
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        let padded_n = util::padded_bitsize(n).ok_or(MPCError::InvalidBitsize)?;
         if !m.is_power_of_two() {
             return Err(MPCError::InvalidAggregation);
         }
-        if bp_gens.gens_capacity < n {
+        if bp_gens.gens_capacity < padded_n {
             return Err(MPCError::InvalidGeneratorsLength);
         }
         if bp_gens.party_capacity < m {
@@ -68,7 +94,11 @@ impl Dealer {
         // state.
         let initial_transcript = transcript.clone();
 
-        transcript.rangeproof_domain_sep(n as u64, m as u64);
+        if v0 {
+            transcript.rangeproof_domain_sep_v0(n as u64, m as u64);
+        } else {
+            transcript.rangeproof_domain_sep(n as u64, m as u64);
+        }
 
         Ok(DealerAwaitingBitCommitments {
             bp_gens,
@@ -77,8 +107,97 @@ impl Dealer {
             initial_transcript,
             n,
             m,
+            v0,
+            bitsizes: Vec::new(),
+            participants: Vec::new(),
         })
     }
+
+    /// Creates a new dealer coordinating `bitsizes.len()` parties,
+    /// where the \\(j\\)-th party proves a `bitsizes[j]`-bit range.
+    ///
+    /// Every party's bit vector is padded to the largest padded
+    /// bitsize \\(n'\\), with zero weight on the bits past its own
+    /// bitsize, so the parties must be created with
+    /// [`Party::new_padded`](super::party::Party::new_padded) and
+    /// \\(n'\\) as their padded bitsize.  The bitsizes are bound
+    /// into the transcript.
+    pub fn new_with_bitsizes<'a, 'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        bitsizes: &[usize],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        let m = bitsizes.len();
+        if !m.is_power_of_two() {
+            return Err(MPCError::InvalidAggregation);
+        }
+        let padded_n = util::max_padded_bitsize(bitsizes).ok_or(MPCError::InvalidBitsize)?;
+        if bp_gens.gens_capacity < padded_n {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+        if bp_gens.party_capacity < m {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+
+        // See `Dealer::new` for why we keep the initial transcript.
+        let initial_transcript = transcript.clone();
+
+        transcript.rangeproof_bitsizes_domain_sep(padded_n as u64, bitsizes);
+
+        Ok(DealerAwaitingBitCommitments {
+            bp_gens,
+            pc_gens,
+            transcript,
+            initial_transcript,
+            n: padded_n,
+            m,
+            v0: false,
+            bitsizes: bitsizes.to_vec(),
+            participants: Vec::new(),
+        })
+    }
+
+    /// Creates a new dealer coordinating `m` parties proving `n`-bit
+    /// ranges, binding the proof to the ordered list of the parties'
+    /// long-term identities.
+    ///
+    /// The `participants` list is appended to the transcript before
+    /// the range proof domain separator, so that every challenge
+    /// depends on it.  The list should be sent to the parties, which
+    /// check it with
+    /// [`PartyAwaitingParticipants::receive_participants`](super::party::PartyAwaitingParticipants::receive_participants)
+    /// before committing to their values.
+    ///
+    /// Verifiers must bind the same list with [`bind_participants`]
+    /// before verifying the resulting proof.
+    pub fn new_with_participants<'a, 'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        n: usize,
+        m: usize,
+        participants: &[[u8; 32]],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        if participants.len() != m {
+            return Err(MPCError::WrongNumParticipants);
+        }
+
+        bind_participants(transcript, participants);
+
+        let mut dealer = Dealer::new(bp_gens, pc_gens, transcript, n, m)?;
+        dealer.participants = participants.to_vec();
+        Ok(dealer)
+    }
+}
+
+/// Appends the ordered list of participant identities to the
+/// `transcript`, as done by [`Dealer::new_with_participants`].
+///
+/// Verifiers of a proof created with bound participants must call this
+/// on their transcript before verification.
+pub fn bind_participants(transcript: &mut Transcript, participants: &[[u8; 32]]) {
+    transcript.participants_domain_sep(participants);
 }
 
 /// A dealer waiting for the parties to send their [`BitCommitment`]s.
@@ -91,9 +210,24 @@ pub struct DealerAwaitingBitCommitments<'a, 'b> {
     initial_transcript: Transcript,
     n: usize,
     m: usize,
+    /// Whether the transcript is the version 0 one, which does not
+    /// bind the protocol version.
+    v0: bool,
+    /// The bitsize of each party, empty if every party proves an
+    /// `n`-bit range.  Otherwise `n` is the common padded bitsize.
+    bitsizes: Vec<usize>,
+    /// The ordered participant identities bound into the transcript,
+    /// empty if the dealer was created without participants.
+    participants: Vec<[u8; 32]>,
 }
 
 impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
+    /// Returns the ordered participant identities bound into the
+    /// transcript, to be sent to the parties.
+    pub fn participants(&self) -> &[[u8; 32]] {
+        &self.participants
+    }
+
     /// Receive each party's [`BitCommitment`]s and compute the [`BitChallenge`].
     pub fn receive_bit_commitments(
         self,
@@ -123,6 +257,8 @@ impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
             DealerAwaitingPolyCommitments {
                 n: self.n,
                 m: self.m,
+                v0: self.v0,
+                bitsizes: self.bitsizes,
                 transcript: self.transcript,
                 initial_transcript: self.initial_transcript,
                 bp_gens: self.bp_gens,
@@ -142,6 +278,8 @@ impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
 pub struct DealerAwaitingPolyCommitments<'a, 'b> {
     n: usize,
     m: usize,
+    v0: bool,
+    bitsizes: Vec<usize>,
     transcript: &'a mut Transcript,
     initial_transcript: Transcript,
     bp_gens: &'b BulletproofGens,
@@ -179,6 +317,8 @@ impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
             DealerAwaitingProofShares {
                 n: self.n,
                 m: self.m,
+                v0: self.v0,
+                bitsizes: self.bitsizes,
                 transcript: self.transcript,
                 initial_transcript: self.initial_transcript,
                 bp_gens: self.bp_gens,
@@ -203,6 +343,8 @@ impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
 pub struct DealerAwaitingProofShares<'a, 'b> {
     n: usize,
     m: usize,
+    v0: bool,
+    bitsizes: Vec<usize>,
     transcript: &'a mut Transcript,
     initial_transcript: Transcript,
     bp_gens: &'b BulletproofGens,
@@ -228,14 +370,15 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
             return Err(MPCError::WrongNumProofShares);
         }
 
+        // The shares cover the bit vectors padded to 8, 16, 32, 64 or 128 bits
+        let n = util::padded_bitsize(self.n).ok_or(MPCError::InvalidBitsize)?;
+
         // Validate lengths for each share
         let mut bad_shares = Vec::<usize>::new(); // no allocations until we append
         for (j, share) in proof_shares.iter().enumerate() {
-            share
-                .check_size(self.n, &self.bp_gens, j)
-                .unwrap_or_else(|_| {
-                    bad_shares.push(j);
-                });
+            share.check_size(n, &self.bp_gens, j).unwrap_or_else(|_| {
+                bad_shares.push(j);
+            });
         }
 
         if bad_shares.len() > 0 {
@@ -255,18 +398,18 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         let w = self.transcript.challenge_scalar(b"w");
         let Q = w * self.pc_gens.B;
 
-        let G_factors: Vec<Scalar> = iter::repeat(Scalar::ONE).take(self.n * self.m).collect();
+        let G_factors: Vec<Scalar> = iter::repeat(Scalar::ONE).take(n * self.m).collect();
         let H_factors: Vec<Scalar> = util::exp_iter(self.bit_challenge.y.invert())
-            .take(self.n * self.m)
+            .take(n * self.m)
             .collect();
 
         let l_vec: Vec<Scalar> = proof_shares
             .iter()
-            .flat_map(|ps| ps.l_vec.clone().into_iter())
+            .flat_map(|ps| ps.l_vec.iter().copied())
             .collect();
         let r_vec: Vec<Scalar> = proof_shares
             .iter()
-            .flat_map(|ps| ps.r_vec.clone().into_iter())
+            .flat_map(|ps| ps.r_vec.iter().copied())
             .collect();
 
         let ipp_proof = inner_product_proof::InnerProductProof::create(
@@ -274,11 +417,12 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
             &Q,
             &G_factors,
             &H_factors,
-            self.bp_gens.G(self.n, self.m).cloned().collect(),
-            self.bp_gens.H(self.n, self.m).cloned().collect(),
+            self.bp_gens.G(n, self.m).cloned().collect(),
+            self.bp_gens.H(n, self.m).cloned().collect(),
             l_vec,
             r_vec,
-        );
+        )
+        .expect("We already checked the parameters, so this should never happen");
 
         Ok(RangeProof {
             A: self.A.compress(),
@@ -327,26 +471,49 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
 
         // See comment in `Dealer::new` for why we use `initial_transcript`
         let transcript = &mut self.initial_transcript;
-        if proof
-            .verify_multiple_with_rng(self.bp_gens, self.pc_gens, transcript, &Vs, self.n, rng)
-            .is_ok()
-        {
+        let verified = if self.v0 {
+            proof.verify_multiple_v0_with_rng(
+                self.bp_gens,
+                self.pc_gens,
+                transcript,
+                &Vs,
+                self.n,
+                rng,
+            )
+        } else if self.bitsizes.is_empty() {
+            proof.verify_multiple_with_rng(self.bp_gens, self.pc_gens, transcript, &Vs, self.n, rng)
+        } else {
+            proof.verify_multiple_heterogeneous_with_rng(
+                self.bp_gens,
+                self.pc_gens,
+                transcript,
+                &Vs,
+                &self.bitsizes,
+                rng,
+            )
+        };
+        if verified.is_ok() {
             Ok(proof)
         } else {
             // Proof verification failed. Now audit the parties:
+            let padded_n = util::padded_bitsize(self.n).ok_or(MPCError::InvalidBitsize)?;
             let mut bad_shares = Vec::new();
             for j in 0..self.m {
-                match proof_shares[j].audit_share(
-                    &self.bp_gens,
-                    &self.pc_gens,
-                    j,
-                    &self.bit_commitments[j],
-                    &self.bit_challenge,
-                    &self.poly_commitments[j],
-                    &self.poly_challenge,
-                ) {
-                    Ok(_) => {}
-                    Err(_) => bad_shares.push(j),
+                if proof_shares[j]
+                    .audit_padded(
+                        &self.bp_gens,
+                        &self.pc_gens,
+                        j,
+                        self.bitsizes.get(j).copied().unwrap_or(self.n),
+                        padded_n,
+                        &self.bit_commitments[j],
+                        &self.bit_challenge,
+                        &self.poly_commitments[j],
+                        &self.poly_challenge,
+                    )
+                    .is_err()
+                {
+                    bad_shares.push(j);
                 }
             }
             Err(MPCError::MalformedProofShares { bad_shares })
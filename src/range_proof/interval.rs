@@ -0,0 +1,560 @@
+//! Range proofs for values in an arbitrary `[min, max)` window.
+//!
+//! To prove \\(v \in [\mathit{min}, \mathit{max})\\) for a commitment
+//! \\(V = v \cdot B + \tilde{v} \cdot \widetilde{B}\\), the prover
+//! shows with a single aggregated proof of size \\(m = 2\\) that
+//! \\(v - \mathit{min} \in [0, 2^n)\\) and
+//! \\(\mathit{max} - 1 - v \in [0, 2^n)\\), for the commitments
+//! \\(V - \mathit{min} \cdot B\\) and \\((\mathit{max} - 1) \cdot B - V\\).
+//! Since \\(\mathit{max} - \mathit{min} \leq 2^n\\), the two statements
+//! together imply \\(\mathit{min} \leq v < \mathit{max}\\).
+//!
+//! The verifier derives the shifted commitments from \\(V\\), so only
+//! \\(V\\), the bounds and \\(n\\) have to be transmitted.
+//!
+//! [`RangeProof::prove_interval`] proves the same statement for the
+//! inclusive interval \\([\mathit{min}, \mathit{max}]\\), choosing
+//! the smallest \\(n\\) with \\(\mathit{max} - \mathit{min} < 2^n\\),
+//! so that only \\(V\\) and the bounds have to be transmitted.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::util;
+
+use super::{RangeProof, ValueCommitment};
+
+/// Checks that `[min, max)` is non-empty and at most `2^n` wide.
+fn check_bounds(min: u64, max: u64, n: usize) -> Result<(), ProofError> {
+    if min >= max {
+        return Err(ProofError::InvalidRange);
+    }
+    if n < 128 && (max - min) as u128 > 1u128 << n {
+        return Err(ProofError::InvalidRange);
+    }
+    Ok(())
+}
+
+/// Returns the smallest bitsize `n` with `max - min < 2^n`, checking
+/// that `[min, max]` is non-empty and that the generators can prove
+/// two `n`-bit values.
+fn interval_bitsize(bp_gens: &BulletproofGens, min: u64, max: u64) -> Result<usize, ProofError> {
+    if min > max {
+        return Err(ProofError::InvalidRange);
+    }
+    let n = (64 - (max - min).leading_zeros() as usize).max(1);
+    match util::padded_bitsize(n) {
+        Some(padded_n) if padded_n <= bp_gens.gens_capacity && bp_gens.party_capacity >= 2 => Ok(n),
+        _ => Err(ProofError::InvalidGeneratorsLength),
+    }
+}
+
+/// Returns the commitments to \\(v - \mathit{low}\\) and
+/// \\(\mathit{high} - v\\) derived from the commitment `V` to \\(v\\).
+fn shifted_commitments(
+    pc_gens: &PedersenGens,
+    V: &RistrettoPoint,
+    low: u64,
+    high: u64,
+) -> [RistrettoPoint; 2] {
+    [
+        V - pc_gens.commit(Scalar::from(low), Scalar::ZERO),
+        pc_gens.commit(Scalar::from(high), Scalar::ZERO) - V,
+    ]
+}
+
+impl RangeProof {
+    /// Create a rangeproof that the value `v` lies in `[min, max)`,
+    /// for a window at most \\(2^n\\) wide.
+    ///
+    /// Returns the proof and the commitment to `v` with the blinding
+    /// factor `v_blinding`.  Returns an error if `min >= max`, if
+    /// `max - min > 2^n`, or if `v` is not in the window.
+    pub fn prove_single_in_range_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        min: u64,
+        max: u64,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        check_bounds(min, max, n)?;
+        if v < min || v >= max {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        let (proof, _) = RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v - min, max - 1 - v],
+            &[*v_blinding, -v_blinding],
+            n,
+            rng,
+        )?;
+
+        let V = pc_gens.commit(Scalar::from(v), *v_blinding).compress();
+        Ok((proof, V))
+    }
+
+    /// Create a rangeproof that the value `v` lies in `[min, max)`.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::prove_single_in_range_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_single_in_range(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        min: u64,
+        max: u64,
+        n: usize,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_single_in_range_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            min,
+            max,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof that the value committed to in `V` lies
+    /// in `[min, max)`.
+    ///
+    /// Returns [`ProofError::PointDecompressionError`] for the component
+    /// `"V"` if `V` is not a valid encoding.
+    pub fn verify_single_in_range_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        min: u64,
+        max: u64,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        check_bounds(min, max, n)?;
+        let V = V.decompress().ok_or(ProofError::PointDecompressionError {
+            proof_index: 0,
+            component: "V",
+        })?;
+
+        self.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &shifted_commitments(pc_gens, &V, min, max - 1),
+            n,
+            rng,
+        )
+    }
+
+    /// Verifies a rangeproof that the value committed to in `V` lies
+    /// in `[min, max)`.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_single_in_range_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_single_in_range(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        min: u64,
+        max: u64,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_single_in_range_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            V,
+            min,
+            max,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof that the value `v` lies in the inclusive
+    /// interval `[min, max]`.
+    ///
+    /// The bitsize is the smallest `n` with `max - min < 2^n`, so the
+    /// verifier only needs the commitment and the bounds.  Returns the
+    /// proof and the commitment to `v` with the blinding factor
+    /// `v_blinding`.  Returns an error if `min > max`, if the
+    /// generators are too small for `max - min`, or if `v` is not in
+    /// the interval.
+    pub fn prove_interval_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        min: u64,
+        max: u64,
+        rng: &mut T,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let n = interval_bitsize(bp_gens, min, max)?;
+        if v < min || v > max {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        let (proof, _) = RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v - min, max - v],
+            &[*v_blinding, -v_blinding],
+            n,
+            rng,
+        )?;
+
+        let V = pc_gens.commit(Scalar::from(v), *v_blinding).compress();
+        Ok((proof, V))
+    }
+
+    /// Create a rangeproof that the value `v` lies in `[min, max]`.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::prove_interval_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_interval(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        min: u64,
+        max: u64,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_interval_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            min,
+            max,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof that the value committed to in `V` lies
+    /// in the inclusive interval `[min, max]`.
+    ///
+    /// Returns [`ProofError::PointDecompressionError`] for the component
+    /// `"V"` if `V` is not a valid encoding.
+    pub fn verify_interval_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        min: u64,
+        max: u64,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let n = interval_bitsize(bp_gens, min, max)?;
+        let V = V.decompress().ok_or(ProofError::PointDecompressionError {
+            proof_index: 0,
+            component: "V",
+        })?;
+
+        self.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &shifted_commitments(pc_gens, &V, min, max),
+            n,
+            rng,
+        )
+    }
+
+    /// Verifies a rangeproof that the value committed to in `V` lies
+    /// in `[min, max]`.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_interval_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_interval(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        min: u64,
+        max: u64,
+    ) -> Result<(), ProofError> {
+        self.verify_interval_with_rng(bp_gens, pc_gens, transcript, V, min, max, &mut thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_range_helper(v: u64, min: u64, max: u64, n: usize) -> Result<(), ProofError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let mut transcript = Transcript::new(b"InRangeTest");
+        let (proof, V) = RangeProof::prove_single_in_range(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            v,
+            &blinding,
+            min,
+            max,
+            n,
+        )?;
+
+        let mut transcript = Transcript::new(b"InRangeTest");
+        proof.verify_single_in_range(&bp_gens, &pc_gens, &mut transcript, &V, min, max, n)
+    }
+
+    #[test]
+    fn values_in_range() {
+        assert_eq!(in_range_helper(1000, 1000, 2000, 16), Ok(()));
+        assert_eq!(in_range_helper(1999, 1000, 2000, 16), Ok(()));
+        assert_eq!(in_range_helper(42, 0, 256, 8), Ok(()));
+        assert_eq!(in_range_helper(u64::MAX - 1, 0, u64::MAX, 64), Ok(()));
+    }
+
+    #[test]
+    fn invalid_bounds() {
+        assert_eq!(in_range_helper(5, 10, 10, 8), Err(ProofError::InvalidRange));
+        assert_eq!(in_range_helper(5, 0, 257, 8), Err(ProofError::InvalidRange));
+        assert_eq!(
+            in_range_helper(2000, 1000, 2000, 16),
+            Err(ProofError::ValueOutOfRange)
+        );
+    }
+
+    #[test]
+    fn proof_does_not_verify_for_other_window() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let mut transcript = Transcript::new(b"InRangeTest");
+        let (proof, V) = RangeProof::prove_single_in_range(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1500,
+            &blinding,
+            1000,
+            2000,
+            16,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"InRangeTest");
+        assert!(proof
+            .verify_single_in_range(&bp_gens, &pc_gens, &mut transcript, &V, 0, 1000, 16)
+            .is_err());
+    }
+
+    fn interval_helper(v: u64, min: u64, max: u64) -> Result<(), ProofError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let mut transcript = Transcript::new(b"IntervalTest");
+        let (proof, V) = RangeProof::prove_interval(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            v,
+            &blinding,
+            min,
+            max,
+        )?;
+
+        let proof: RangeProof = bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+
+        let mut transcript = Transcript::new(b"IntervalTest");
+        proof.verify_interval(&bp_gens, &pc_gens, &mut transcript, &V, min, max)
+    }
+
+    #[test]
+    fn interval_boundaries() {
+        assert_eq!(interval_helper(1000, 1000, 2000), Ok(()));
+        assert_eq!(interval_helper(1001, 1000, 2000), Ok(()));
+        assert_eq!(interval_helper(1999, 1000, 2000), Ok(()));
+        assert_eq!(interval_helper(2000, 1000, 2000), Ok(()));
+        assert_eq!(interval_helper(7, 7, 7), Ok(()));
+        assert_eq!(interval_helper(0, 0, u64::MAX), Ok(()));
+        assert_eq!(interval_helper(u64::MAX, 0, u64::MAX), Ok(()));
+
+        assert_eq!(
+            interval_helper(999, 1000, 2000),
+            Err(ProofError::ValueOutOfRange)
+        );
+        assert_eq!(
+            interval_helper(2001, 1000, 2000),
+            Err(ProofError::ValueOutOfRange)
+        );
+        assert_eq!(interval_helper(5, 10, 9), Err(ProofError::InvalidRange));
+    }
+
+    #[test]
+    fn interval_proof_binds_upper_bound() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        // [18, 120] is 103 values wide, so n = 7 and the padded
+        // bitsize is 8.  A proof of v - min alone in 8 bits would
+        // accept values up to 18 + 255.
+        let mut transcript = Transcript::new(b"IntervalTest");
+        let (proof, V) =
+            RangeProof::prove_interval(&bp_gens, &pc_gens, &mut transcript, 30, &blinding, 18, 120)
+                .unwrap();
+        assert_eq!(V, pc_gens.commit(Scalar::from(30u64), blinding).compress());
+
+        // The same proof does not verify for a commitment to a value
+        // above the interval but within the padded bitsize.
+        let above = pc_gens.commit(Scalar::from(121u64), blinding);
+        let mut transcript = Transcript::new(b"IntervalTest");
+        assert!(proof
+            .verify_interval(&bp_gens, &pc_gens, &mut transcript, &above, 18, 120)
+            .is_err());
+
+        // Nor can such a value be proven in the interval.
+        let mut transcript = Transcript::new(b"IntervalTest");
+        assert_eq!(
+            RangeProof::prove_interval(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                200,
+                &blinding,
+                18,
+                120
+            )
+            .unwrap_err(),
+            ProofError::ValueOutOfRange
+        );
+    }
+
+    #[test]
+    fn interval_too_wide_for_generators() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+        let mut transcript = Transcript::new(b"IntervalTest");
+        assert_eq!(
+            RangeProof::prove_interval(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                0,
+                &Scalar::ONE,
+                0,
+                1 << 16,
+            )
+            .unwrap_err(),
+            ProofError::InvalidGeneratorsLength
+        );
+    }
+
+    #[test]
+    fn interval_proof_does_not_verify_just_outside() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        for (v, min, max) in [(1000, 1000, 2000), (2000, 1000, 2000)] {
+            let mut transcript = Transcript::new(b"IntervalTest");
+            let (proof, V) = RangeProof::prove_interval(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                v,
+                &blinding,
+                min,
+                max,
+            )
+            .unwrap();
+
+            // Shrink the interval by one on each side, so that `v` lies
+            // just outside of it.
+            for (min, max) in [(min + 1, max), (min, max - 1)] {
+                let mut transcript = Transcript::new(b"IntervalTest");
+                assert!(proof
+                    .verify_interval(&bp_gens, &pc_gens, &mut transcript, &V, min, max)
+                    .is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_commitments_are_attributed() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let blinding = Scalar::from(5u64);
+        let mut transcript = Transcript::new(b"IntervalTest");
+        let (proof, _) = RangeProof::prove_interval(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1500,
+            &blinding,
+            1000,
+            2000,
+        )
+        .unwrap();
+
+        let invalid = CompressedRistretto([0xff; 32]);
+        let error = Err(ProofError::PointDecompressionError {
+            proof_index: 0,
+            component: "V",
+        });
+        let mut transcript = Transcript::new(b"IntervalTest");
+        assert_eq!(
+            proof.verify_interval(&bp_gens, &pc_gens, &mut transcript, &invalid, 1000, 2000),
+            error
+        );
+        let mut transcript = Transcript::new(b"IntervalTest");
+        assert_eq!(
+            proof.verify_single_in_range(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &invalid,
+                1000,
+                2000,
+                16
+            ),
+            error
+        );
+    }
+}
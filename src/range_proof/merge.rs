@@ -0,0 +1,233 @@
+//! Range proofs for the sum of two committed values.
+//!
+//! To merge two commitments \\(V_1, V_2\\) to the values \\(v_1, v_2\\),
+//! for instance when combining two confidential amounts into one, the
+//! prover proves that \\(v_1 + v_2 \in [0, 2^n)\\) for the commitment
+//! \\(V_1 + V_2\\), with the blinding factor
+//! \\(\tilde{v}_1 + \tilde{v}_2\\).
+//!
+//! The verifier computes the merged commitment itself, so only the
+//! proof has to be transmitted.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+
+use super::{RangeProof, ValueCommitment};
+
+impl RangeProof {
+    /// Create a rangeproof that the sum of the values `v1` and `v2`
+    /// lies in \\([0, 2^n)\\).
+    ///
+    /// Returns the proof and the merged commitment to `v1 + v2` with
+    /// the blinding factor `v1_blinding + v2_blinding`, which equals
+    /// the sum of the commitments to `v1` and `v2`.
+    ///
+    /// The sum is computed without wrapping: returns
+    /// [`ProofError::ValueOutOfRange`] if `v1 + v2` does not fit in
+    /// `n` bits, including when it overflows a `u64`.
+    pub fn prove_merge_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v1: u64,
+        v1_blinding: &Scalar,
+        v2: u64,
+        v2_blinding: &Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let v = v1.checked_add(v2).ok_or(ProofError::ValueOutOfRange)?;
+        if n < 64 && v >> n != 0 {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            &(v1_blinding + v2_blinding),
+            n,
+            rng,
+        )
+    }
+
+    /// Create a rangeproof that the sum of the values `v1` and `v2`
+    /// lies in \\([0, 2^n)\\).
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::prove_merge_with_rng`], passing in a threadsafe
+    /// RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_merge(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v1: u64,
+        v1_blinding: &Scalar,
+        v2: u64,
+        v2_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_merge_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v1,
+            v1_blinding,
+            v2,
+            v2_blinding,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof that the sum of the values committed to
+    /// in `V1` and `V2` lies in \\([0, 2^n)\\).
+    ///
+    /// Returns [`ProofError::PointDecompressionError`] for the component
+    /// `"V1"` or `"V2"` if `V1` or `V2` respectively is not a valid
+    /// encoding.
+    pub fn verify_merge_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V1: &impl ValueCommitment,
+        V2: &impl ValueCommitment,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let V1 = V1.decompress().ok_or(ProofError::PointDecompressionError {
+            proof_index: 0,
+            component: "V1",
+        })?;
+        let V2 = V2.decompress().ok_or(ProofError::PointDecompressionError {
+            proof_index: 0,
+            component: "V2",
+        })?;
+
+        self.verify_single_with_rng(bp_gens, pc_gens, transcript, &(V1 + V2), n, rng)
+    }
+
+    /// Verifies a rangeproof that the sum of the values committed to
+    /// in `V1` and `V2` lies in \\([0, 2^n)\\).
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_merge_with_rng`], passing in a threadsafe
+    /// RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_merge(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V1: &impl ValueCommitment,
+        V2: &impl ValueCommitment,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_merge_with_rng(bp_gens, pc_gens, transcript, V1, V2, n, &mut thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge_helper(v1: u64, v2: u64, n: usize) -> Result<(), ProofError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+        let (r1, r2) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let V1 = pc_gens.commit(Scalar::from(v1), r1).compress();
+        let V2 = pc_gens.commit(Scalar::from(v2), r2).compress();
+
+        let mut transcript = Transcript::new(b"MergeTest");
+        let (proof, V) =
+            RangeProof::prove_merge(&bp_gens, &pc_gens, &mut transcript, v1, &r1, v2, &r2, n)?;
+        assert_eq!(
+            V,
+            (V1.decompress().unwrap() + V2.decompress().unwrap()).compress()
+        );
+
+        let mut transcript = Transcript::new(b"MergeTest");
+        proof.verify_merge(&bp_gens, &pc_gens, &mut transcript, &V1, &V2, n)
+    }
+
+    #[test]
+    fn merged_values_in_range() {
+        assert_eq!(merge_helper(100, 200, 16), Ok(()));
+        assert_eq!(merge_helper(0, 0, 8), Ok(()));
+        assert_eq!(merge_helper(255, 0, 8), Ok(()));
+        assert_eq!(merge_helper(1 << 63, (1 << 63) - 1, 64), Ok(()));
+    }
+
+    #[test]
+    fn overflowing_sum_is_rejected() {
+        assert_eq!(merge_helper(200, 56, 8), Err(ProofError::ValueOutOfRange));
+        assert_eq!(
+            merge_helper(u64::MAX, 1, 64),
+            Err(ProofError::ValueOutOfRange)
+        );
+    }
+
+    #[test]
+    fn merge_proof_does_not_verify_for_other_commitments() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let (r1, r2) = (Scalar::from(3u64), Scalar::from(4u64));
+        let V1 = pc_gens.commit(Scalar::from(10u64), r1).compress();
+        let V2 = pc_gens.commit(Scalar::from(20u64), r2).compress();
+        let other = pc_gens.commit(Scalar::from(21u64), r2).compress();
+
+        let mut transcript = Transcript::new(b"MergeTest");
+        let (proof, _) =
+            RangeProof::prove_merge(&bp_gens, &pc_gens, &mut transcript, 10, &r1, 20, &r2, 32)
+                .unwrap();
+
+        let mut transcript = Transcript::new(b"MergeTest");
+        assert!(proof
+            .verify_merge(&bp_gens, &pc_gens, &mut transcript, &V1, &other, 32)
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_commitments_are_attributed() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let (r1, r2) = (Scalar::from(3u64), Scalar::from(4u64));
+        let mut transcript = Transcript::new(b"MergeTest");
+        let (proof, _) =
+            RangeProof::prove_merge(&bp_gens, &pc_gens, &mut transcript, 1, &r1, 2, &r2, 8)
+                .unwrap();
+
+        let invalid = CompressedRistretto([0xff; 32]);
+        let V = pc_gens.commit(Scalar::from(2u64), r2).compress();
+        let mut transcript = Transcript::new(b"MergeTest");
+        assert_eq!(
+            proof.verify_merge(&bp_gens, &pc_gens, &mut transcript, &invalid, &V, 8),
+            Err(ProofError::PointDecompressionError {
+                proof_index: 0,
+                component: "V1",
+            })
+        );
+        let mut transcript = Transcript::new(b"MergeTest");
+        assert_eq!(
+            proof.verify_merge(&bp_gens, &pc_gens, &mut transcript, &V, &invalid, 8),
+            Err(ProofError::PointDecompressionError {
+                proof_index: 0,
+                component: "V2",
+            })
+        );
+    }
+}
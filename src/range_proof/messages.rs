@@ -3,16 +3,27 @@
 //!
 //! For more explanation of how the `dealer`, `party`, and `messages` modules orchestrate the protocol execution, see
 //! [the API for the aggregated multiparty computation protocol](../aggregation/index.html#api-for-the-aggregated-multiparty-computation-protocol).
+//!
+//! Every message has a `to_bytes`/`from_bytes` encoding made of
+//! 32-byte compressed points and canonical scalars, like
+//! [`RangeProof::to_bytes`](super::RangeProof::to_bytes), as well as
+//! `serde` implementations, so that the protocol can be run over a
+//! network.  Decoding only checks the encoding itself: the dealer
+//! checks the sizes of the received shares against the aggregation.
 
 extern crate alloc;
 
+use alloc::vec;
 use alloc::vec::Vec;
 use core::iter;
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::VartimeMultiscalarMul;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
+use crate::errors::{MPCError, ProofError};
 use crate::generators::{BulletproofGens, PedersenGens};
+use crate::util::read32;
 
 /// A commitment to the bits of a party's value.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
@@ -22,6 +33,37 @@ pub struct BitCommitment {
     pub(super) S_j: RistrettoPoint,
 }
 
+impl BitCommitment {
+    /// Serializes the commitment into a byte array of 3 compressed
+    /// Ristretto points \\(V_j, A_j, S_j\\).
+    pub fn to_bytes(&self) -> [u8; 96] {
+        let mut buf = [0u8; 96];
+        buf[..32].copy_from_slice(self.V_j.as_bytes());
+        buf[32..64].copy_from_slice(self.A_j.compress().as_bytes());
+        buf[64..].copy_from_slice(self.S_j.compress().as_bytes());
+        buf
+    }
+
+    /// Deserializes the commitment from a byte slice.
+    ///
+    /// Returns an error if the slice is not 96 bytes long, or if any
+    /// of \\(V_j, A_j, S_j\\) is not a valid compressed Ristretto
+    /// point.  \\(V_j\\) is kept compressed, as the dealer passes it
+    /// on to the verifier, but is checked here so that an invalid
+    /// value commitment is rejected on receipt.
+    pub fn from_bytes(slice: &[u8]) -> Result<BitCommitment, ProofError> {
+        if slice.len() != 96 {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok(BitCommitment {
+            V_j: read_point(&slice[..32])?.compress(),
+            A_j: read_point(&slice[32..])?,
+            S_j: read_point(&slice[64..])?,
+        })
+    }
+}
+
 /// Challenge values derived from all parties' [`BitCommitment`]s.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct BitChallenge {
@@ -29,6 +71,32 @@ pub struct BitChallenge {
     pub(super) z: Scalar,
 }
 
+impl BitChallenge {
+    /// Serializes the challenge into a byte array of 2 scalars
+    /// \\(y, z\\).
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(self.y.as_bytes());
+        buf[32..].copy_from_slice(self.z.as_bytes());
+        buf
+    }
+
+    /// Deserializes the challenge from a byte slice.
+    ///
+    /// Returns an error if the slice is not 64 bytes long, or if
+    /// either scalar is not canonical.
+    pub fn from_bytes(slice: &[u8]) -> Result<BitChallenge, ProofError> {
+        if slice.len() != 64 {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok(BitChallenge {
+            y: read_scalar(&slice[..32])?,
+            z: read_scalar(&slice[32..])?,
+        })
+    }
+}
+
 /// A commitment to a party's polynomial coefficents.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct PolyCommitment {
@@ -36,15 +104,65 @@ pub struct PolyCommitment {
     pub(super) T_2_j: RistrettoPoint,
 }
 
+impl PolyCommitment {
+    /// Serializes the commitment into a byte array of 2 compressed
+    /// Ristretto points \\(T_{1,j}, T_{2,j}\\).
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(self.T_1_j.compress().as_bytes());
+        buf[32..].copy_from_slice(self.T_2_j.compress().as_bytes());
+        buf
+    }
+
+    /// Deserializes the commitment from a byte slice.
+    ///
+    /// Returns an error if the slice is not 64 bytes long, or if
+    /// either point is not a valid compressed Ristretto point.
+    pub fn from_bytes(slice: &[u8]) -> Result<PolyCommitment, ProofError> {
+        if slice.len() != 64 {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok(PolyCommitment {
+            T_1_j: read_point(&slice[..32])?,
+            T_2_j: read_point(&slice[32..])?,
+        })
+    }
+}
+
 /// Challenge values derived from all parties' [`PolyCommitment`]s.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct PolyChallenge {
     pub(super) x: Scalar,
 }
 
+impl PolyChallenge {
+    /// Serializes the challenge into the 32 bytes of the scalar \\(x\\).
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.x.to_bytes()
+    }
+
+    /// Deserializes the challenge from a byte slice.
+    ///
+    /// Returns an error if the slice is not 32 bytes long, or if the
+    /// scalar is not canonical.
+    pub fn from_bytes(slice: &[u8]) -> Result<PolyChallenge, ProofError> {
+        if slice.len() != 32 {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok(PolyChallenge {
+            x: read_scalar(slice)?,
+        })
+    }
+}
+
 /// A party's proof share, ready for aggregation into the final
 /// [`RangeProof`](::RangeProof).
-#[derive(Serialize, Deserialize, Clone, Debug)]
+///
+/// The share is scrubbed when dropped, since the \\(\mathbf{l}(x),
+/// \mathbf{r}(x)\\) vectors are derived from the party's bits.
+#[derive(Serialize, Deserialize, Clone, Debug, ZeroizeOnDrop)]
 pub struct ProofShare {
     pub(super) t_x: Scalar,
     pub(super) t_x_blinding: Scalar,
@@ -54,6 +172,64 @@ pub struct ProofShare {
 }
 
 impl ProofShare {
+    /// Serializes the share into a byte array of \\(2n + 3\\) scalars,
+    /// where \\(n\\) is the padded bitsize of the party's value.
+    ///
+    /// The share is derived from the party's secret value, and is only
+    /// meant to be sent to the dealer.  Unlike the share itself, the
+    /// returned bytes are not zeroized on drop.
+    ///
+    /// # Layout
+    ///
+    /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
+    /// * \\(n\\) scalars \\(\mathbf{l}(x)\\),
+    /// * \\(n\\) scalars \\(\mathbf{r}(x)\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity((3 + 2 * self.l_vec.len()) * 32);
+        buf.extend_from_slice(self.t_x.as_bytes());
+        buf.extend_from_slice(self.t_x_blinding.as_bytes());
+        buf.extend_from_slice(self.e_blinding.as_bytes());
+        for l_i in self.l_vec.iter() {
+            buf.extend_from_slice(l_i.as_bytes());
+        }
+        for r_i in self.r_vec.iter() {
+            buf.extend_from_slice(r_i.as_bytes());
+        }
+        buf
+    }
+
+    /// Deserializes the share from a byte slice.
+    ///
+    /// Returns an error if the slice does not have \\(2n + 3\\)
+    /// 32-byte elements, or if any scalar is not canonical.  The
+    /// sizes are checked against the aggregation by the dealer, which
+    /// can audit deserialized shares like local ones.
+    pub fn from_bytes(slice: &[u8]) -> Result<ProofShare, ProofError> {
+        if slice.len() % 32 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let num_elements = slice.len() / 32;
+        if num_elements < 3 || (num_elements - 3) % 2 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let n = (num_elements - 3) / 2;
+
+        let mut scalars = slice
+            .chunks_exact(32)
+            .map(read_scalar)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let share = ProofShare {
+            t_x: scalars[0],
+            t_x_blinding: scalars[1],
+            e_blinding: scalars[2],
+            l_vec: scalars[3..3 + n].to_vec(),
+            r_vec: scalars[3 + n..].to_vec(),
+        };
+        scalars.zeroize();
+        Ok(share)
+    }
+
     /// Checks consistency of all sizes in the proof share and returns the size of the l/r vector.
     pub(super) fn check_size(
         &self,
@@ -80,13 +256,82 @@ impl ProofShare {
         Ok(())
     }
 
-    /// Audit an individual proof share to determine whether it is
-    /// malformed.
-    pub(super) fn audit_share(
+    /// Audits the share of the party at position `j` against the
+    /// messages recorded from that run of the protocol.
+    ///
+    /// This is the check
+    /// [`receive_shares`](super::dealer::DealerAwaitingProofShares::receive_shares)
+    /// runs on every share when the aggregated proof fails to verify,
+    /// so a coordinator can blame a single party after the fact.
+    /// Returns [`MPCError::MalformedProofShares`] naming `j` if the
+    /// share is inconsistent with the party's commitments, for
+    /// instance if its value does not fit in `n` bits.
+    pub fn audit(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        j: usize,
+        n: usize,
+        bit_commitment: &BitCommitment,
+        bit_challenge: &BitChallenge,
+        poly_commitment: &PolyCommitment,
+        poly_challenge: &PolyChallenge,
+    ) -> Result<(), MPCError> {
+        let padded_n =
+            crate::util::padded_bitsize(n).ok_or_else(|| MPCError::MalformedProofShares {
+                bad_shares: vec![j],
+            })?;
+        self.audit_padded(
+            bp_gens,
+            pc_gens,
+            j,
+            n,
+            padded_n,
+            bit_commitment,
+            bit_challenge,
+            poly_commitment,
+            poly_challenge,
+        )
+    }
+
+    /// Audits the share of the party at position `j` proving an
+    /// `n`-bit range padded to `padded_n` bits, as in a proof with
+    /// per-party bitsizes.
+    pub(super) fn audit_padded(
         &self,
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         j: usize,
+        n: usize,
+        padded_n: usize,
+        bit_commitment: &BitCommitment,
+        bit_challenge: &BitChallenge,
+        poly_commitment: &PolyCommitment,
+        poly_challenge: &PolyChallenge,
+    ) -> Result<(), MPCError> {
+        self.audit_share(
+            bp_gens,
+            pc_gens,
+            j,
+            n,
+            padded_n,
+            bit_commitment,
+            bit_challenge,
+            poly_commitment,
+            poly_challenge,
+        )
+        .map_err(|_| MPCError::MalformedProofShares {
+            bad_shares: vec![j],
+        })
+    }
+
+    fn audit_share(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        j: usize,
+        n: usize,
+        padded_n: usize,
         bit_commitment: &BitCommitment,
         bit_challenge: &BitChallenge,
         poly_commitment: &PolyCommitment,
@@ -97,9 +342,11 @@ impl ProofShare {
         use core::ops::Not;
         use group::Group;
 
-        let n = self.l_vec.len();
+        if self.l_vec.len() != padded_n || util::padded_bitsize(n).map_or(true, |p| p > padded_n) {
+            return Err(());
+        }
 
-        self.check_size(n, bp_gens, j)?;
+        self.check_size(padded_n, bp_gens, j)?;
 
         let (y, z) = (&bit_challenge.y, &bit_challenge.z);
         let x = &poly_challenge.x;
@@ -108,7 +355,7 @@ impl ProofShare {
         let zz = z * z;
         let minus_z = -z;
         let z_j = util::scalar_exp_vartime(z, j as u64); // z^j
-        let y_jn = util::scalar_exp_vartime(y, (j * n) as u64); // y^(j*n)
+        let y_jn = util::scalar_exp_vartime(y, (j * padded_n) as u64); // y^(j*n)
         let y_jn_inv = y_jn.invert(); // y^(-j*n)
         let y_inv = y.invert(); // y^(-1)
 
@@ -120,7 +367,7 @@ impl ProofShare {
         let h = self
             .r_vec
             .iter()
-            .zip(util::exp_iter(Scalar::from(2u64)))
+            .zip(util::padded_powers_of_2(n, padded_n))
             .zip(util::exp_iter(y_inv))
             .map(|((r_i, exp_2), exp_y_inv)| {
                 z + exp_y_inv * y_jn_inv * (-r_i) + exp_y_inv * y_jn_inv * (zz * z_j * exp_2)
@@ -135,8 +382,8 @@ impl ProofShare {
             iter::once(&bit_commitment.A_j)
                 .chain(iter::once(&bit_commitment.S_j))
                 .chain(iter::once(&pc_gens.B_blinding))
-                .chain(bp_gens.share(j).G(n))
-                .chain(bp_gens.share(j).H(n)),
+                .chain(bp_gens.share(j).G(padded_n))
+                .chain(bp_gens.share(j).H(padded_n)),
         );
         if P_check.is_identity().not().into() {
             return Err(());
@@ -144,7 +391,7 @@ impl ProofShare {
 
         let V_j = bit_commitment.V_j.decompress().ok_or(())?;
 
-        let sum_of_powers_y = util::sum_of_powers(&y, n);
+        let sum_of_powers_y = util::sum_of_powers(&y, padded_n);
         let sum_of_powers_2 = util::sum_of_powers(&Scalar::from(2u64), n);
         let delta = (z - zz) * sum_of_powers_y * y_jn - z * zz * sum_of_powers_2 * z_j;
         let t_check = RistrettoPoint::vartime_multiscalar_mul(
@@ -167,3 +414,16 @@ impl ProofShare {
         }
     }
 }
+
+/// Reads a canonical scalar from the first 32 bytes of `data`.
+fn read_scalar(data: &[u8]) -> Result<Scalar, ProofError> {
+    Option::from(Scalar::from_canonical_bytes(read32(data))).ok_or(ProofError::FormatError)
+}
+
+/// Reads and decompresses a Ristretto point from the first 32 bytes
+/// of `data`.
+fn read_point(data: &[u8]) -> Result<RistrettoPoint, ProofError> {
+    CompressedRistretto(read32(data))
+        .decompress()
+        .ok_or(ProofError::FormatError)
+}
@@ -7,9 +7,11 @@ extern crate rand;
 
 #[cfg(feature = "std")]
 use self::rand::thread_rng;
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
-use curve25519_dalek::traits::VartimeMultiscalarMul;
+use curve25519_dalek::traits::{Identity, VartimeMultiscalarMul};
 
+use core::hash::{Hash, Hasher};
 use core::iter;
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
@@ -22,14 +24,37 @@ use crate::inner_product_proof::InnerProductProof;
 use crate::transcript::TranscriptProtocol;
 use crate::util;
 
-use rand_core::{CryptoRng, RngCore};
+use rand_chacha::ChaChaRng;
+use rand_core::{CryptoRng, RngCore, SeedableRng};
 use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+use subtle::{Choice, ConstantTimeEq};
 
 // Modules for MPC protocol
 
+pub mod backend;
+pub mod bitsize;
+pub mod bundle;
+pub mod commitments;
 pub mod dealer;
 pub mod messages;
 pub mod party;
+pub mod signature;
+pub mod streaming;
+#[cfg(feature = "prover-introspection")]
+pub mod trace;
+pub mod verification_key;
+
+mod chunked;
+mod comparison;
+mod interval;
+mod merge;
+mod rewind;
+mod seeded;
+mod signed;
+mod weighted_bits;
+
+#[cfg(feature = "prover-introspection")]
+use self::trace::ProverTrace;
 
 /// The `RangeProof` struct represents a proof that one or more values
 /// are in a range.
@@ -42,11 +67,20 @@ pub mod party;
 /// the values, are not included in the proof, and must be known to
 /// the verifier.
 ///
-/// This implementation requires that both the bitsize `n` and the
-/// aggregation size `m` be powers of two, so that `n = 8, 16, 32, 64`
-/// and `m = 1, 2, 4, 8, 16, ...`.  Note that the aggregation size is
-/// not given as an explicit parameter, but is determined by the
-/// number of values or commitments passed to the prover or verifier.
+/// The aggregation size `m` can be any positive number up to the
+/// party capacity of the generators.  Note that the aggregation size is
+/// not given as an explicit parameter, but is determined by the number
+/// of values or commitments passed to the prover or verifier.
+/// Internally, the prover pads the values with zero values and zero
+/// blindings up to the next power of two, and the verifier pads the
+/// commitments with the matching identity points, so the padding never
+/// appears in the proof's inputs or outputs.
+///
+/// The bitsize `n` can be any of `1 ≤ n ≤ 128`.  Internally the bit
+/// vectors are zero-padded to the next of `n = 8, 16, 32, 64, 128`, so a
+/// proof costs as much as one for the padded bitsize, and the padding
+/// bits carry no weight in the value.  The proof is bound to `n`
+/// itself, and only verifies under the bitsize it was created with.
 ///
 /// # Note
 ///
@@ -74,11 +108,75 @@ pub struct RangeProof {
     ipp_proof: InnerProductProof,
 }
 
-pub trait ValueCommitment: Copy {
+/// A commitment to a value, in compressed or decompressed form.
+///
+/// The verifier needs both forms of each commitment: the compressed
+/// one is appended to the transcript, and the decompressed one enters
+/// the verification equation.
+pub trait ValueCommitment {
+    /// Returns the commitment as a point, or `None` if it is not a
+    /// valid encoding.
     fn decompress(&self) -> Option<RistrettoPoint>;
+    /// Returns the compressed encoding of the commitment.
     fn compress(&self) -> CompressedRistretto;
 }
 
+impl<T: ValueCommitment + ?Sized> ValueCommitment for &T {
+    fn decompress(&self) -> Option<RistrettoPoint> {
+        (**self).decompress()
+    }
+    fn compress(&self) -> CompressedRistretto {
+        (**self).compress()
+    }
+}
+
+/// A commitment stored in both compressed and decompressed form, so
+/// that verifying several proofs over it decompresses it only once.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DecompressedCommitment {
+    point: RistrettoPoint,
+    compressed: CompressedRistretto,
+}
+
+impl DecompressedCommitment {
+    /// Decompresses `compressed`, returning `None` if it is not a valid
+    /// encoding.
+    pub fn from_compressed(compressed: &CompressedRistretto) -> Option<Self> {
+        compressed.decompress().map(|point| DecompressedCommitment {
+            point,
+            compressed: *compressed,
+        })
+    }
+
+    /// Returns the commitment as a point.
+    pub fn point(&self) -> RistrettoPoint {
+        self.point
+    }
+
+    /// Returns the compressed encoding of the commitment.
+    pub fn compressed(&self) -> CompressedRistretto {
+        self.compressed
+    }
+}
+
+impl From<RistrettoPoint> for DecompressedCommitment {
+    fn from(point: RistrettoPoint) -> Self {
+        DecompressedCommitment {
+            point,
+            compressed: point.compress(),
+        }
+    }
+}
+
+impl ValueCommitment for DecompressedCommitment {
+    fn decompress(&self) -> Option<RistrettoPoint> {
+        Some(self.point)
+    }
+    fn compress(&self) -> CompressedRistretto {
+        self.compressed
+    }
+}
+
 impl ValueCommitment for (RistrettoPoint, CompressedRistretto) {
     fn decompress(&self) -> Option<RistrettoPoint> {
         Some(self.0)
@@ -111,6 +209,12 @@ impl RangeProof {
     /// blinding scalar `v_blinding`.
     /// This is a convenience wrapper around [`RangeProof::prove_multiple`].
     ///
+    /// The commitment \\(V\\) is appended to the transcript before any
+    /// challenge is drawn, so a proof cannot be updated to another
+    /// blinding factor of the same value.  To re-blind a commitment,
+    /// create a new proof with the new blinding factor, which is
+    /// unlinkable to the old one.
+    ///
     /// # Example
     /// ```
     /// extern crate rand;
@@ -210,6 +314,9 @@ impl RangeProof {
 
     /// Create a rangeproof for a set of values.
     ///
+    /// Returns [`ProofError::ValueOutOfRange`] if a value does not fit
+    /// in `n` bits.
+    ///
     /// # Example
     /// ```
     /// extern crate rand;
@@ -271,66 +378,124 @@ impl RangeProof {
         n: usize,
         rng: &mut T,
     ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
-        use self::dealer::*;
-        use self::party::*;
-
         if values.len() != blindings.len() {
             return Err(ProofError::WrongNumBlindingFactors);
         }
 
-        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, values.len())?;
-
-        let parties: Vec<_> = values
-            .iter()
-            .zip(blindings.iter())
-            .map(|(&v, &v_blinding)| Party::new(bp_gens, pc_gens, v, v_blinding, n))
-            // Collect the iterator of Results into a Result<Vec>, then unwrap it
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
-            .into_iter()
-            .enumerate()
-            .map(|(j, p)| {
-                p.assign_position_with_rng(j, rng)
-                    .expect("We already checked the parameters, so this should never happen")
-            })
-            .unzip();
-
-        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+        RangeProof::prove_from_iter_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values.iter().copied().zip(blindings.iter().copied()),
+            n,
+            rng,
+        )
+    }
 
-        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+    /// Create a rangeproof for the pairs of values and blinding
+    /// factors yielded by `values`, like
+    /// [`RangeProof::prove_multiple_with_rng`], without collecting them
+    /// into slices first.
+    ///
+    /// The number of values is known from the iterator's length, so an
+    /// aggregation size the generators cannot handle is rejected with
+    /// [`ProofError::InvalidAggregation`] before any value is consumed.
+    /// Each value comes with its blinding factor, so
+    /// [`ProofError::WrongNumBlindingFactors`] is never returned.
+    pub fn prove_from_iter_with_rng<T, I>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: I,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError>
+    where
+        T: RngCore + CryptoRng,
+        I: IntoIterator<Item = (u64, Scalar)>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        use self::party::*;
 
-        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
-            .into_iter()
-            .map(|p| p.apply_challenge_with_rng(&bit_challenge, rng))
-            .unzip();
+        let values = values.into_iter();
+        let padded_m = padded_aggregation_size(bp_gens, values.len())?;
 
-        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+        let mut parties = Vec::with_capacity(padded_m);
+        for (v, v_blinding) in values {
+            parties.push(Party::new(bp_gens, pc_gens, v, v_blinding, n)?);
+        }
 
-        let proof_shares: Vec<_> = parties
-            .into_iter()
-            .map(|p| p.apply_challenge(&poly_challenge))
-            // Collect the iterator of Results into a Result<Vec>, then unwrap it
-            .collect::<Result<Vec<_>, _>>()?;
+        RangeProof::prove_with_parties(bp_gens, pc_gens, transcript, parties, n, rng)
+    }
 
-        let proof = dealer.receive_trusted_shares(&proof_shares)?;
+    /// Create a rangeproof for the pairs of values and blinding
+    /// factors yielded by `values`.
+    /// This is a convenience wrapper around [`RangeProof::prove_from_iter_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_from_iter<I>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: I,
+        n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError>
+    where
+        I: IntoIterator<Item = (u64, Scalar)>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        RangeProof::prove_from_iter_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            n,
+            &mut thread_rng(),
+        )
+    }
 
-        Ok((proof, value_commitments))
+    /// Create a rangeproof for a set of values like
+    /// [`RangeProof::prove_multiple_with_rng`], appending its encoding
+    /// to `buf` instead of returning it.
+    ///
+    /// The appended bytes are those of [`RangeProof::to_bytes`], and
+    /// take [`RangeProof::serialized_size`] bytes.  This saves the
+    /// separate allocation of `to_bytes` when the proof is written to
+    /// an existing buffer, such as an outgoing message.  On error,
+    /// `buf` is left unchanged.
+    pub fn prove_multiple_into_with_rng<T: RngCore + CryptoRng>(
+        buf: &mut Vec<u8>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<Vec<CompressedRistretto>, ProofError> {
+        let (proof, value_commitments) = RangeProof::prove_multiple_with_rng(
+            bp_gens, pc_gens, transcript, values, blindings, n, rng,
+        )?;
+        proof.write_bytes(buf);
+        Ok(value_commitments)
     }
 
-    /// Create a rangeproof for a set of values.
-    /// This is a convenience wrapper around [`RangeProof::prove_multiple_with_rng`],
+    /// Create a rangeproof for a set of values, appending its encoding
+    /// to `buf`.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_into_with_rng`],
     /// passing in a threadsafe RNG.
     #[cfg(feature = "std")]
-    pub fn prove_multiple(
+    pub fn prove_multiple_into(
+        buf: &mut Vec<u8>,
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
         values: &[u64],
         blindings: &[Scalar],
         n: usize,
-    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
-        RangeProof::prove_multiple_with_rng(
+    ) -> Result<Vec<CompressedRistretto>, ProofError> {
+        RangeProof::prove_multiple_into_with_rng(
+            buf,
             bp_gens,
             pc_gens,
             transcript,
@@ -341,647 +506,5146 @@ impl RangeProof {
         )
     }
 
-    /// Verifies a rangeproof for a given value commitment \\(V\\).
+    /// Create a rangeproof for a set of values given as scalars, for
+    /// callers whose values are already field elements.
     ///
-    /// This is a convenience wrapper around `verify_multiple` for the `m=1` case.
-    pub fn verify_single_with_rng<T: RngCore + CryptoRng>(
-        &self,
+    /// Each value must be less than \\(2\^n\\) when reduced to its
+    /// integer representative.  Unlike out-of-range `u64` values, which
+    /// are rejected with [`ProofError::ValueOutOfRange`], this is
+    /// checked with a debug assertion only: otherwise the resulting
+    /// proof is simply invalid.  Note that the check cannot be
+    /// relied upon to validate untrusted scalars; only a verified range
+    /// proof establishes that a committed value is small.
+    pub fn prove_multiple_scalars_with_rng<T: RngCore + CryptoRng>(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        V: &impl ValueCommitment,
+        values: &[Scalar],
+        blindings: &[Scalar],
         n: usize,
         rng: &mut T,
-    ) -> Result<(), ProofError> {
-        self.verify_multiple_with_rng(bp_gens, pc_gens, transcript, &[*V], n, rng)
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        use self::party::*;
+
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+
+        debug_assert!(
+            values.iter().all(|v| util::scalar_fits_bits(v, n)),
+            "values must be less than 2^n"
+        );
+
+        let parties: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| {
+                Party::new_from_scalar_unchecked(bp_gens, pc_gens, v, v_blinding, n)
+            })
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        RangeProof::prove_with_parties(bp_gens, pc_gens, transcript, parties, n, rng)
     }
 
-    /// Verifies a rangeproof for a given value commitment \\(V\\).
-    ///
-    /// This is a convenience wrapper around [`RangeProof::verify_single_with_rng`],
+    /// Create a rangeproof for a set of values given as scalars.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_scalars_with_rng`],
     /// passing in a threadsafe RNG.
     #[cfg(feature = "std")]
-    pub fn verify_single(
-        &self,
+    pub fn prove_multiple_scalars(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        V: &impl ValueCommitment,
+        values: &[Scalar],
+        blindings: &[Scalar],
         n: usize,
-    ) -> Result<(), ProofError> {
-        self.verify_single_with_rng(bp_gens, pc_gens, transcript, V, n, &mut thread_rng())
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_scalars_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
     }
 
-    /// Verifies an aggregated rangeproof for the given value commitments.
-    pub fn verify_multiple_with_rng<T: RngCore + CryptoRng>(
-        &self,
+    /// Create a rangeproof for a given pair of a 128-bit value `v` and
+    /// blinding scalar `v_blinding`, for bitsizes up to `n = 128`.
+    pub fn prove_single_u128_with_rng<T: RngCore + CryptoRng>(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        value_commitments: &[impl ValueCommitment],
+        v: u128,
+        v_blinding: &Scalar,
         n: usize,
         rng: &mut T,
-    ) -> Result<(), ProofError> {
-        Self::verify_batch_with_rng(
-            iter::once(self.verification_view(transcript, value_commitments, n)),
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let (p, Vs) = RangeProof::prove_multiple_u128_with_rng(
             bp_gens,
             pc_gens,
+            transcript,
+            &[v],
+            &[*v_blinding],
+            n,
             rng,
-        )
+        )?;
+        Ok((p, Vs[0]))
     }
 
-    /// Verifies an aggregated rangeproof for the given value commitments.
-    /// This is a convenience wrapper around [`RangeProof::verify_multiple_with_rng`],
+    /// Create a rangeproof for a given pair of a 128-bit value `v` and
+    /// blinding scalar `v_blinding`.
+    /// This is a convenience wrapper around [`RangeProof::prove_single_u128_with_rng`],
     /// passing in a threadsafe RNG.
     #[cfg(feature = "std")]
-    pub fn verify_multiple(
-        &self,
+    pub fn prove_single_u128(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        value_commitments: &[impl ValueCommitment],
+        v: u128,
+        v_blinding: &Scalar,
         n: usize,
-    ) -> Result<(), ProofError> {
-        self.verify_multiple_with_rng(
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_single_u128_with_rng(
             bp_gens,
             pc_gens,
             transcript,
-            value_commitments,
+            v,
+            v_blinding,
             n,
             &mut thread_rng(),
         )
     }
 
-    /// Create a view to this range proof for batch verification.
-    pub fn verification_view<'a, V: ValueCommitment>(
-        &'a self,
-        transcript: &'a mut Transcript,
-        value_commitments: &'a [V],
+    /// Create a rangeproof for a set of 128-bit values, for bitsizes
+    /// up to `n = 128`.
+    ///
+    /// As with [`RangeProof::prove_multiple_with_rng`], values that do
+    /// not fit in `n` bits are rejected with
+    /// [`ProofError::ValueOutOfRange`].
+    pub fn prove_multiple_u128_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u128],
+        blindings: &[Scalar],
         n: usize,
-    ) -> RangeProofView<'a, V> {
-        RangeProofView {
-            proof: self,
-            transcript,
-            value_commitments,
-            n,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        use self::party::*;
+
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
         }
+
+        let parties: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| {
+                Party::new_from_scalar(bp_gens, pc_gens, Scalar::from(v), v_blinding, n)
+            })
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        RangeProof::prove_with_parties(bp_gens, pc_gens, transcript, parties, n, rng)
     }
 
-    pub fn verify_batch<'a, V: ValueCommitment + 'a>(
-        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+    /// Create a rangeproof for a set of 128-bit values.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_u128_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_multiple_u128(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
-    ) -> Result<(), ProofError> {
-        Self::verify_batch_with_rng(batch, bp_gens, pc_gens, &mut thread_rng())
+        transcript: &mut Transcript,
+        values: &[u128],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_u128_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
     }
 
-    pub fn verify_batch_with_rng<'a, T: RngCore + CryptoRng, V: ValueCommitment + 'a>(
-        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+    /// Create an aggregated rangeproof for a set of values of
+    /// different bitsizes, proving that `values[j]` is in
+    /// \\([0, 2^{n_j})\\) for \\(n_j\\) = `bitsizes[j]`.
+    ///
+    /// Every value's bit vector is padded to the largest padded
+    /// bitsize \\(n'\\), with zero weight on the bits past its own
+    /// bitsize, so the proof has the size of an aggregated
+    /// \\(n'\\)-bit proof.  It must be verified with
+    /// [`RangeProof::verify_multiple_heterogeneous_with_rng`] and the
+    /// same `bitsizes`.
+    pub fn prove_multiple_heterogeneous_with_rng<T: RngCore + CryptoRng>(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        bitsizes: &[usize],
         rng: &mut T,
-    ) -> Result<(), ProofError> {
-        let mut collector = BatchCollector::new(bp_gens, pc_gens);
-        for el in batch {
-            collector.add_proof(el, rng)?
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        use self::dealer::*;
+        use self::party::*;
+
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        if values.len() != bitsizes.len() {
+            return Err(ProofError::InvalidInputLength);
         }
+        let padded_n = util::max_padded_bitsize(bitsizes).ok_or(ProofError::InvalidBitsize)?;
 
-        collector.verify()
+        let mut parties: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .zip(bitsizes.iter())
+            .map(|((&v, &v_blinding), &n)| {
+                Party::new_padded(bp_gens, pc_gens, v, v_blinding, n, padded_n)
+            })
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The padding parties prove `padded_n`-bit ranges.
+        let m = pad_parties(bp_gens, pc_gens, &mut parties, padded_n)?;
+        let bitsizes = padded_bitsizes(bitsizes, padded_n, parties.len());
+
+        let dealer = Dealer::new_with_bitsizes(bp_gens, pc_gens, transcript, &bitsizes)?;
+
+        RangeProof::run_local_protocol(dealer, parties, m, rng)
     }
 
-    /// Serializes the proof into a byte array of \\(2 \lg n + 9\\)
-    /// 32-byte elements, where \\(n\\) is the number of secret bits.
-    ///
-    /// # Layout
-    ///
-    /// The layout of the range proof encoding is:
-    ///
-    /// * four compressed Ristretto points \\(A,S,T_1,T_2\\),
-    /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
-    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
-    /// * two scalars \\(a, b\\).
-    pub fn to_bytes(&self) -> Vec<u8> {
-        // 7 elements: points A, S, T1, T2, scalars tx, tx_bl, e_bl.
-        let mut buf = Vec::with_capacity(7 * 32 + self.ipp_proof.serialized_size());
-        buf.extend_from_slice(self.A.as_bytes());
-        buf.extend_from_slice(self.S.as_bytes());
-        buf.extend_from_slice(self.T_1.as_bytes());
-        buf.extend_from_slice(self.T_2.as_bytes());
-        buf.extend_from_slice(self.t_x.as_bytes());
-        buf.extend_from_slice(self.t_x_blinding.as_bytes());
-        buf.extend_from_slice(self.e_blinding.as_bytes());
-        buf.extend(self.ipp_proof.to_bytes_iter());
-        buf
+    /// Create an aggregated rangeproof for a set of values of
+    /// different bitsizes.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_heterogeneous_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_multiple_heterogeneous(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        bitsizes: &[usize],
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_heterogeneous_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            bitsizes,
+            &mut thread_rng(),
+        )
     }
 
-    /// Deserializes the proof from a byte slice.
-    ///
-    /// Returns an error if the byte slice cannot be parsed into a `RangeProof`.
-    pub fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
-        if slice.len() % 32 != 0 {
-            return Err(ProofError::FormatError);
-        }
-        if slice.len() < 7 * 32 {
-            return Err(ProofError::FormatError);
-        }
+    /// Runs the aggregation protocol locally between a dealer and the
+    /// given `parties`, which are assigned positions in order.
+    fn prove_with_parties<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        mut parties: Vec<party::PartyAwaitingPosition>,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        use self::dealer::*;
 
-        use crate::util::read32;
+        let m = pad_parties(bp_gens, pc_gens, &mut parties, n)?;
 
-        let A = CompressedRistretto(read32(&slice[0 * 32..]));
-        let S = CompressedRistretto(read32(&slice[1 * 32..]));
-        let T_1 = CompressedRistretto(read32(&slice[2 * 32..]));
-        let T_2 = CompressedRistretto(read32(&slice[3 * 32..]));
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, parties.len())?;
 
-        let t_x = Option::from(Scalar::from_canonical_bytes(read32(&slice[4 * 32..])))
-            .ok_or(ProofError::FormatError)?;
-        let t_x_blinding = Option::from(Scalar::from_canonical_bytes(read32(&slice[5 * 32..])))
-            .ok_or(ProofError::FormatError)?;
-        let e_blinding = Option::from(Scalar::from_canonical_bytes(read32(&slice[6 * 32..])))
-            .ok_or(ProofError::FormatError)?;
+        RangeProof::run_local_protocol(dealer, parties, m, rng)
+    }
 
-        let ipp_proof = InnerProductProof::from_bytes(&slice[7 * 32..])?;
+    /// Runs the remaining rounds of the aggregation protocol between
+    /// the `dealer` and the padded `parties`, of which the first `m`
+    /// are real.
+    fn run_local_protocol<T: RngCore + CryptoRng>(
+        dealer: dealer::DealerAwaitingBitCommitments,
+        parties: Vec<party::PartyAwaitingPosition>,
+        m: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        let (parties, bit_commitments) = assign_positions(parties, rng);
 
-        Ok(RangeProof {
-            A,
-            S,
-            T_1,
-            T_2,
-            t_x,
-            t_x_blinding,
-            e_blinding,
-            ipp_proof,
-        })
-    }
-}
+        let value_commitments: Vec<_> = bit_commitments.iter().take(m).map(|c| c.V_j).collect();
 
-impl Serialize for RangeProof {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_bytes(&self.to_bytes())
-    }
-}
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
 
-impl<'de> Deserialize<'de> for RangeProof {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let bytes = Vec::deserialize(deserializer)?;
-        // Using Error::custom requires T: Display, which our error
-        // type only implements when it implements std::error::Error.
-        #[cfg(feature = "std")]
-        return RangeProof::from_bytes(&bytes).map_err(serde::de::Error::custom);
-        // In no-std contexts, drop the error message.
-        #[cfg(not(feature = "std"))]
-        return RangeProof::from_bytes(&bytes)
-            .map_err(|_| serde::de::Error::custom("deserialization error"));
-    }
-}
+        let (parties, poly_commitments) = apply_bit_challenge(parties, &bit_challenge, rng);
 
-// TODO(merge): naming
-pub struct RangeProofView<'a, V: ValueCommitment> {
-    proof: &'a RangeProof,
-    transcript: &'a mut Transcript,
-    value_commitments: &'a [V],
-    n: usize,
-}
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
 
-// Internal type which constructs the multiscalar mul for a batch.
-// TODO(merge): g_scalars and h_scalars should probably be laid flat in memory as they are matrices
-struct BatchCollector<'a> {
-    dynamic_scalars: Vec<Scalar>,
-    dynamic_points: Vec<Option<RistrettoPoint>>,
-    pedersen_B_scalar: Scalar,
-    pedersen_B_blinding_scalar: Scalar,
-    g_scalars: Vec<Vec<Scalar>>,
-    h_scalars: Vec<Vec<Scalar>>,
-    party_capacity: usize,
-    gens_capacity: usize,
-    bp_gens: &'a BulletproofGens,
-    pc_gens: &'a PedersenGens,
-}
+        let proof_shares = apply_poly_challenge(parties, &poly_challenge)?;
 
-impl<'a> BatchCollector<'a> {
-    fn new(bp_gens: &'a BulletproofGens, pc_gens: &'a PedersenGens) -> Self {
-        Self {
-            dynamic_scalars: vec![],
-            dynamic_points: vec![],
-            pedersen_B_scalar: Scalar::ZERO,
-            pedersen_B_blinding_scalar: Scalar::ZERO,
-            g_scalars: vec![],
-            h_scalars: vec![],
-            party_capacity: 0,
-            gens_capacity: 0,
+        let proof = dealer.receive_trusted_shares(&proof_shares)?;
+
+        Ok((proof, value_commitments))
+    }
+
+    /// Create a rangeproof for a set of values.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_multiple(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_with_rng(
             bp_gens,
             pc_gens,
-        }
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
     }
 
-    fn add_proof<T: RngCore + CryptoRng, V: ValueCommitment>(
-        &mut self,
-        view: RangeProofView<V>,
+    /// Create a version 0 rangeproof for a set of values, whose
+    /// transcript does not bind the protocol version.
+    ///
+    /// The proof only verifies with
+    /// [`RangeProof::verify_multiple_v0_with_rng`].  This is only meant
+    /// for interoperating with verifiers which have not been upgraded;
+    /// new proofs should be created with
+    /// [`RangeProof::prove_multiple_with_rng`].
+    pub fn prove_multiple_v0_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
         rng: &mut T,
-    ) -> Result<(), ProofError> {
-        let m = view.value_commitments.len();
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        use self::dealer::*;
+        use self::party::*;
 
-        // First, replay the "interactive" protocol using the proof
-        // data to recompute all challenges.
-        if !(view.n == 8 || view.n == 16 || view.n == 32 || view.n == 64) {
-            return Err(ProofError::InvalidBitsize);
-        }
-        if self.bp_gens.gens_capacity < view.n {
-            return Err(ProofError::InvalidGeneratorsLength);
-        }
-        if self.bp_gens.party_capacity < m {
-            return Err(ProofError::InvalidGeneratorsLength);
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
         }
 
-        view.transcript
-            .rangeproof_domain_sep(view.n as u64, m as u64);
+        let mut parties: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| Party::new(bp_gens, pc_gens, v, v_blinding, n))
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
 
-        for V in view.value_commitments.iter() {
-            // Allow the commitments to be zero (0 value, 0 blinding)
-            // See https://github.com/dalek-cryptography/bulletproofs/pull/248#discussion_r255167177
-            view.transcript.append_point(b"V", &V.compress());
-        }
+        let m = pad_parties(bp_gens, pc_gens, &mut parties, n)?;
 
-        view.transcript
-            .validate_and_append_point(b"A", &view.proof.A)?;
-        view.transcript
-            .validate_and_append_point(b"S", &view.proof.S)?;
+        let dealer = Dealer::new_v0(bp_gens, pc_gens, transcript, n, parties.len())?;
 
-        let y = view.transcript.challenge_scalar(b"y");
-        let z = view.transcript.challenge_scalar(b"z");
-        let zz = z * z;
-        let minus_z = -z;
+        RangeProof::run_local_protocol(dealer, parties, m, rng)
+    }
 
-        view.transcript
-            .validate_and_append_point(b"T_1", &view.proof.T_1)?;
-        view.transcript
-            .validate_and_append_point(b"T_2", &view.proof.T_2)?;
+    /// Create a version 0 rangeproof for a set of values.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_v0_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_multiple_v0(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_v0_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
+    }
 
-        let x = view.transcript.challenge_scalar(b"x");
+    /// Create a rangeproof for a set of values, recording the
+    /// prover's intermediate values into a [`ProverTrace`].
+    ///
+    /// The emitted proof is identical to the one produced by
+    /// [`RangeProof::prove_multiple_with_rng`] given the same RNG.
+    ///
+    /// ## WARNING
+    ///
+    /// The returned trace **leaks the witness**.  This function is
+    /// only meant for research and debugging, and the
+    /// `prover-introspection` feature must never be enabled in
+    /// production.
+    #[cfg(feature = "prover-introspection")]
+    pub fn prove_multiple_traced<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>, ProverTrace), ProofError> {
+        use self::dealer::*;
+        use self::party::*;
 
-        view.transcript.append_scalar(b"t_x", &view.proof.t_x);
-        view.transcript
-            .append_scalar(b"t_x_blinding", &view.proof.t_x_blinding);
-        view.transcript
-            .append_scalar(b"e_blinding", &view.proof.e_blinding);
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
 
-        let w = view.transcript.challenge_scalar(b"w");
+        let mut trace = ProverTrace::default();
+
+        let mut parties: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| Party::new(bp_gens, pc_gens, v, v_blinding, n))
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+        let m = pad_parties(bp_gens, pc_gens, &mut parties, n)?;
 
-        // Challenge value for batching statements to be verified
-        let c = Scalar::random(rng);
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, parties.len())?;
 
-        let (x_sq, x_inv_sq, s) = view
-            .proof
-            .ipp_proof
-            .verification_scalars(view.n * m, view.transcript)?;
-        let s_inv = s.iter().rev();
+        let (parties, bit_commitments) = assign_positions(parties, rng);
 
-        let a = view.proof.ipp_proof.a;
-        let b = view.proof.ipp_proof.b;
+        for (j, p) in parties.iter().enumerate() {
+            trace.party_mut(j).bits = Some(p.trace());
+        }
 
-        // Construct concat_z_and_2, an iterator of the values of
-        // z^0 * \vec(2)^n || z^1 * \vec(2)^n || ... || z^(m-1) * \vec(2)^n
-        let powers_of_2: Vec<Scalar> = util::exp_iter(Scalar::from(2u64)).take(view.n).collect();
-        let concat_z_and_2: Vec<Scalar> = util::exp_iter(z)
-            .take(m)
-            .flat_map(|exp_z| powers_of_2.iter().map(move |exp_2| exp_2 * exp_z))
-            .collect();
+        let value_commitments: Vec<_> = bit_commitments.iter().take(m).map(|c| c.V_j).collect();
 
-        let mut g = s.iter().map(|s_i| minus_z - a * s_i);
-        let mut h = s_inv
-            .zip(util::exp_iter(y.invert()))
-            .zip(concat_z_and_2.iter())
-            .map(|((s_i_inv, exp_y_inv), z_and_2)| z + exp_y_inv * (zz * z_and_2 - b * s_i_inv));
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
 
-        let value_commitment_scalars = util::exp_iter(z).take(m).map(|z_exp| c * zz * z_exp);
-        let basepoint_scalar =
-            w * (view.proof.t_x - a * b) + c * (delta(view.n, m, &y, &z) - view.proof.t_x);
+        let (parties, poly_commitments) = apply_bit_challenge(parties, &bit_challenge, rng);
 
-        // Collect for batched multiscalar mul.
+        for (j, p) in parties.iter().enumerate() {
+            trace.party_mut(j).poly = Some(p.trace());
+        }
 
-        // Batch challenge - not in transcript as each proof has its own transcript.
-        let batch_factor = Scalar::random(rng);
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+        trace.x = Some(poly_challenge.x);
 
-        self.dynamic_scalars.extend(
-            iter::once(Scalar::ONE)
-                .chain(iter::once(x))
-                .chain(iter::once(c * x))
-                .chain(iter::once(c * x * x))
-                .chain(x_sq.iter().cloned())
-                .chain(x_inv_sq.iter().cloned())
-                .chain(value_commitment_scalars)
-                .map(|s| s * batch_factor),
+        let proof_shares = apply_poly_challenge(parties, &poly_challenge)?;
+
+        let proof = dealer.receive_trusted_shares(&proof_shares)?;
+
+        Ok((proof, value_commitments, trace))
+    }
+
+    /// Verifies a rangeproof for a given value commitment \\(V\\).
+    ///
+    /// This is a convenience wrapper around `verify_multiple` for the `m=1` case.
+    pub fn verify_single_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_with_rng(bp_gens, pc_gens, transcript, &[V], n, rng)
+    }
+
+    /// Verifies a rangeproof for a given value commitment \\(V\\).
+    ///
+    /// This is a convenience wrapper around [`RangeProof::verify_single_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_single(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_single_with_rng(bp_gens, pc_gens, transcript, V, n, &mut thread_rng())
+    }
+
+    /// Verifies a rangeproof for the value commitment
+    /// \\(V = \sum_i c_i \cdot C_i\\), computed by the verifier from the
+    /// `base_commitments` \\(C_i\\) and the `coefficients` \\(c_i\\).
+    ///
+    /// The prover creates the proof with [`RangeProof::prove_single`]
+    /// for the value \\(\sum_i c_i \cdot v_i\\) and the blinding
+    /// \\(\sum_i c_i \cdot \tilde{v}_i\\), and does not need to send
+    /// \\(V\\).  Returns [`ProofError::InvalidInputLength`] if the
+    /// number of coefficients does not match the number of commitments.
+    pub fn verify_single_over_combination_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        base_commitments: &[impl ValueCommitment],
+        coefficients: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        if base_commitments.len() != coefficients.len() {
+            return Err(ProofError::InvalidInputLength);
+        }
+
+        let V = RistrettoPoint::optional_multiscalar_mul(
+            coefficients,
+            base_commitments.iter().map(|C| C.decompress()),
+        )
+        .ok_or(ProofError::FormatError)?;
+
+        self.verify_single_with_rng(bp_gens, pc_gens, transcript, &V, n, rng)
+    }
+
+    /// Verifies a rangeproof for a linear combination of commitments.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_single_over_combination_with_rng`], passing
+    /// in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_single_over_combination(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        base_commitments: &[impl ValueCommitment],
+        coefficients: &[Scalar],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_single_over_combination_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            base_commitments,
+            coefficients,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies an aggregated rangeproof for the given value commitments.
+    pub fn verify_multiple_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        Self::verify_batch_with_rng(
+            iter::once(self.verification_view(transcript, value_commitments, n)),
+            bp_gens,
+            pc_gens,
+            rng,
+        )
+    }
+
+    /// Verifies an aggregated rangeproof for the given value commitments.
+    /// This is a convenience wrapper around [`RangeProof::verify_multiple_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_multiple(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies an aggregated rangeproof for the given value
+    /// commitments like [`RangeProof::verify_multiple_with_rng`],
+    /// rejecting identity or invalid commitments, see
+    /// [`RangeProofView::strict`].
+    ///
+    /// Returns [`ProofError::InvalidCommitment`] with the index of the
+    /// first such commitment.
+    pub fn verify_multiple_strict_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        Self::verify_batch_with_rng(
+            iter::once(
+                self.verification_view(transcript, value_commitments, n)
+                    .strict(),
+            ),
+            bp_gens,
+            pc_gens,
+            rng,
+        )
+    }
+
+    /// Verifies an aggregated rangeproof, rejecting identity or invalid
+    /// commitments.
+    /// This is a convenience wrapper around [`RangeProof::verify_multiple_strict_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_multiple_strict(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_strict_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a version 0 aggregated rangeproof, created before the
+    /// protocol version was bound into the transcript, for the given
+    /// value commitments.
+    ///
+    /// Version 0 and current proofs do not verify with each other's
+    /// verification path.
+    pub fn verify_multiple_v0_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        Self::verify_batch_with_rng(
+            iter::once(self.verification_view_v0(transcript, value_commitments, n)),
+            bp_gens,
+            pc_gens,
+            rng,
+        )
+    }
+
+    /// Verifies a version 0 aggregated rangeproof.
+    /// This is a convenience wrapper around [`RangeProof::verify_multiple_v0_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_multiple_v0(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_v0_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies an aggregated rangeproof for value commitments given
+    /// in compressed form, as received from the prover.
+    ///
+    /// This is the same check as [`RangeProof::verify_multiple_with_rng`],
+    /// but each commitment is decompressed once up front, so a
+    /// malformed commitment is rejected with
+    /// [`ProofError::PointDecompressionError`] before the proof is
+    /// replayed.
+    pub fn verify_multiple_compressed_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let value_commitments = value_commitments
+            .iter()
+            .map(DecompressedCommitment::from_compressed)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(ProofError::PointDecompressionError {
+                proof_index: 0,
+                component: "V",
+            })?;
+        self.verify_multiple_with_rng(bp_gens, pc_gens, transcript, &value_commitments, n, rng)
+    }
+
+    /// Verifies an aggregated rangeproof for compressed value commitments.
+    /// This is a convenience wrapper around [`RangeProof::verify_multiple_compressed_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_multiple_compressed(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_compressed_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies an aggregated rangeproof for the given value
+    /// commitments like [`RangeProof::verify_multiple_with_rng`], and
+    /// returns the sum \\(\sum_j V_j\\) of the commitments on success.
+    ///
+    /// Each commitment is decompressed once, for both the verification
+    /// and the sum.  The transcript and the challenges are the same as
+    /// for [`RangeProof::verify_multiple_with_rng`].  Returns
+    /// [`ProofError::PointDecompressionError`] if a commitment is not
+    /// a valid encoding.
+    pub fn verify_multiple_and_sum_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<RistrettoPoint, ProofError> {
+        let value_commitments = value_commitments
+            .iter()
+            .map(|V| {
+                V.decompress().map(|point| DecompressedCommitment {
+                    point,
+                    compressed: V.compress(),
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(ProofError::PointDecompressionError {
+                proof_index: 0,
+                component: "V",
+            })?;
+        self.verify_multiple_with_rng(bp_gens, pc_gens, transcript, &value_commitments, n, rng)?;
+        Ok(value_commitments.iter().map(|V| V.point).sum())
+    }
+
+    /// Verifies an aggregated rangeproof and returns the sum of its
+    /// value commitments.
+    /// This is a convenience wrapper around [`RangeProof::verify_multiple_and_sum_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_multiple_and_sum(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+    ) -> Result<RistrettoPoint, ProofError> {
+        self.verify_multiple_and_sum_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Returns the sum \\(\sum_j V_j\\) of the value commitments of an
+    /// aggregated proof, which commits to the sum of the values with
+    /// the sum of the blinding factors.
+    ///
+    /// Returns `None` if a commitment is not a valid encoding.  This
+    /// does not verify anything; use
+    /// [`RangeProof::verify_multiple_and_sum_with_rng`] to verify the
+    /// proof and compute the sum at once.
+    pub fn implied_aggregate(value_commitments: &[impl ValueCommitment]) -> Option<RistrettoPoint> {
+        value_commitments.iter().map(|V| V.decompress()).sum()
+    }
+
+    /// Verifies an aggregated rangeproof created by
+    /// [`RangeProof::prove_multiple_heterogeneous_with_rng`], where the
+    /// \\(j\\)-th value commitment is checked against a
+    /// `bitsizes[j]`-bit range.
+    pub fn verify_multiple_heterogeneous_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        bitsizes: &[usize],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        Self::verify_batch_with_rng(
+            iter::once(self.verification_view_heterogeneous(
+                transcript,
+                value_commitments,
+                bitsizes,
+            )),
+            bp_gens,
+            pc_gens,
+            rng,
+        )
+    }
+
+    /// Verifies an aggregated rangeproof of values of different bitsizes.
+    /// This is a convenience wrapper around [`RangeProof::verify_multiple_heterogeneous_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_multiple_heterogeneous(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        bitsizes: &[usize],
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_heterogeneous_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            bitsizes,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Create a view to this range proof for batch verification.
+    pub fn verification_view<'a, V: ValueCommitment>(
+        &'a self,
+        transcript: &'a mut Transcript,
+        value_commitments: &'a [V],
+        n: usize,
+    ) -> RangeProofView<'a, V> {
+        RangeProofView {
+            proof: self,
+            transcript,
+            value_commitments,
+            bitsizes: Bitsizes::Uniform(n),
+            strict: false,
+        }
+    }
+
+    /// Create a view to this version 0 range proof for batch
+    /// verification, see [`RangeProof::verify_multiple_v0_with_rng`].
+    pub fn verification_view_v0<'a, V: ValueCommitment>(
+        &'a self,
+        transcript: &'a mut Transcript,
+        value_commitments: &'a [V],
+        n: usize,
+    ) -> RangeProofView<'a, V> {
+        RangeProofView {
+            proof: self,
+            transcript,
+            value_commitments,
+            bitsizes: Bitsizes::UniformV0(n),
+            strict: false,
+        }
+    }
+
+    /// Create a view to this range proof for batch verification, where
+    /// the \\(j\\)-th value commitment is checked against a
+    /// `bitsizes[j]`-bit range.
+    pub fn verification_view_heterogeneous<'a, V: ValueCommitment>(
+        &'a self,
+        transcript: &'a mut Transcript,
+        value_commitments: &'a [V],
+        bitsizes: &'a [usize],
+    ) -> RangeProofView<'a, V> {
+        RangeProofView {
+            proof: self,
+            transcript,
+            value_commitments,
+            bitsizes: Bitsizes::PerValue(bitsizes),
+            strict: false,
+        }
+    }
+
+    pub fn verify_batch<'a, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> Result<(), ProofError> {
+        Self::verify_batch_with_rng(batch, bp_gens, pc_gens, &mut thread_rng())
+    }
+
+    pub fn verify_batch_with_rng<'a, T: RngCore + CryptoRng, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let mut verifier = BatchVerifier::new(bp_gens, pc_gens);
+        for el in batch {
+            verifier.add(el, rng)?
+        }
+
+        verifier.finalize()
+    }
+
+    /// Checks that no value commitment appears in two different proofs
+    /// of `views`, for applications which forbid reusing a commitment.
+    ///
+    /// Commitments are compared by their compressed encoding.  A
+    /// commitment repeated within a single proof is not reported.
+    /// Returns [`ProofError::DuplicateAcrossProofs`] for the first
+    /// commitment found in an earlier proof.  This does not verify the
+    /// proofs.
+    pub fn commitments_disjoint<V: ValueCommitment>(
+        views: &[RangeProofView<'_, V>],
+    ) -> Result<(), ProofError> {
+        let mut owners = BTreeMap::new();
+        for (proof_b, view) in views.iter().enumerate() {
+            for (commitment_index, V) in view.value_commitments.iter().enumerate() {
+                let proof_a = *owners.entry(V.compress().to_bytes()).or_insert(proof_b);
+                if proof_a != proof_b {
+                    return Err(ProofError::DuplicateAcrossProofs {
+                        proof_a,
+                        proof_b,
+                        commitment_index,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch_with_rng`],
+    /// calling `progress` with the number of proofs added so far and
+    /// the size of the batch after each proof is added.
+    ///
+    /// The final multiscalar multiplication runs after the last call,
+    /// and fails the batch as a whole.  If a proof is malformed, the
+    /// batch fails as soon as it is added, without further calls.
+    pub fn verify_batch_with_progress_with_rng<'a, T, V, I>(
+        batch: I,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        mut progress: impl FnMut(usize, usize),
+        rng: &mut T,
+    ) -> Result<(), ProofError>
+    where
+        T: RngCore + CryptoRng,
+        V: ValueCommitment + 'a,
+        I: IntoIterator<Item = RangeProofView<'a, V>>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let batch = batch.into_iter();
+        let total = batch.len();
+        let mut verifier = BatchVerifier::new(bp_gens, pc_gens);
+        for (i, el) in batch.enumerate() {
+            verifier.add(el, rng)?;
+            progress(i + 1, total);
+        }
+
+        verifier.finalize()
+    }
+
+    /// Verifies a batch of proofs, reporting progress to `progress`.
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_batch_with_progress_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_batch_with_progress<'a, V, I>(
+        batch: I,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<(), ProofError>
+    where
+        V: ValueCommitment + 'a,
+        I: IntoIterator<Item = RangeProofView<'a, V>>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Self::verify_batch_with_progress_with_rng(
+            batch,
+            bp_gens,
+            pc_gens,
+            progress,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Returns the base-2 logarithm of the soundness error added by
+    /// verifying `batch_size` proofs in one batch.
+    ///
+    /// This applies to the verifiers drawing their batching factors
+    /// from an RNG, such as [`RangeProof::verify_batch_with_rng`], and
+    /// not to [`RangeProof::verify_batch_deterministic`].  Each
+    /// proof's check is scaled by an independent, uniformly random
+    /// batching factor over the scalar field of order
+    /// \\(\ell \approx 2^{252}\\), so a batch containing an invalid
+    /// proof passes with probability at most
+    /// \\(\mathtt{batch\_size} / \ell\\), on top of the soundness error
+    /// of the proofs themselves.  For instance, a batch of \\(2^{20}\\)
+    /// proofs adds an error of \\(2^{-232}\\).  An empty batch adds none,
+    /// and returns negative infinity.
+    #[cfg(feature = "std")]
+    pub fn batch_soundness_error_bits(batch_size: usize) -> f64 {
+        // ℓ = 2^252 + 27742317777372353535851937790883648493, whose
+        // logarithm rounds to exactly 252 in double precision.
+        (batch_size as f64).log2() - 252.0
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch_with_rng`],
+    /// without an RNG.
+    ///
+    /// The scalars combining the checks are derived in two passes.
+    /// First, every proof of the batch, its value commitments, its
+    /// bitsizes and the initial state of its transcript are absorbed
+    /// into a transcript of the whole batch.  Then the scalars of each
+    /// proof are drawn from that transcript and the proof's index.
+    /// The result is reproducible, and this does not need the `std`
+    /// feature.
+    ///
+    /// # Soundness
+    ///
+    /// Batching is sound if the errors of invalid proofs cannot be
+    /// made to cancel out in the combined check.  Since every scalar
+    /// depends on every proof, changing any proof of the batch changes
+    /// all the scalars, so a prover controlling several proofs cannot
+    /// fix their errors one proof at a time.  Modelling the transcript
+    /// as a random oracle, a batch containing an invalid proof passes
+    /// with probability at most about \\(q / \ell\\), where \\(q\\)
+    /// is the number of batches the prover tries and
+    /// \\(\ell \approx 2^{252}\\) the order of the scalar field.
+    /// Unlike [`RangeProof::batch_soundness_error_bits`], this bound
+    /// depends on the prover's work, since the prover can evaluate the
+    /// scalars of a candidate batch offline.
+    pub fn verify_batch_deterministic<'a, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> Result<(), ProofError> {
+        let views: Vec<_> = batch.into_iter().collect();
+        let mut factors = TranscriptBatchFactors::new();
+        for view in views.iter() {
+            factors.absorb(view);
+        }
+
+        let mut collector = BatchCollector::new(bp_gens, pc_gens);
+        for view in views {
+            collector.add_proof_with_factors(view, &mut factors)?;
+        }
+
+        collector.verify()
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch_with_rng`],
+    /// replaying the proofs' transcripts on multiple threads.
+    ///
+    /// Each proof's contribution to the combined multiscalar
+    /// multiplication is computed independently, and the contributions
+    /// are then summed.  Since `rng` cannot be shared across threads,
+    /// each proof draws its random batching factors from its own
+    /// [`TranscriptRng`](merlin::TranscriptRng), keyed by `rng`.
+    #[cfg(feature = "rayon")]
+    pub fn verify_batch_par_with_rng<
+        'a,
+        T: RngCore + CryptoRng,
+        V: ValueCommitment + Send + Sync + 'a,
+    >(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        use rayon::prelude::*;
+
+        let views: Vec<_> = batch
+            .into_iter()
+            .map(|view| {
+                let proof_rng = view.transcript.build_rng().finalize(&mut *rng);
+                (view, proof_rng)
+            })
+            .collect();
+
+        views
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, (view, mut proof_rng))| {
+                let mut collector = BatchCollector::new(bp_gens, pc_gens);
+                collector.add_proof_at(i, view, &mut proof_rng)?;
+                Ok(collector)
+            })
+            .try_reduce(
+                || BatchCollector::new(bp_gens, pc_gens),
+                |mut collector, other| {
+                    collector.merge(other);
+                    Ok(collector)
+                },
+            )?
+            .verify()
+    }
+
+    /// Verifies a batch of proofs on multiple threads.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_batch_par_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "rayon")]
+    pub fn verify_batch_par<'a, V: ValueCommitment + Send + Sync + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> Result<(), ProofError> {
+        Self::verify_batch_par_with_rng(batch, bp_gens, pc_gens, &mut thread_rng())
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch`],
+    /// but reports which proofs failed.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_batch_identify_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_batch_identify<'a, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> Result<(), ProofError> {
+        Self::verify_batch_identify_with_rng(batch, bp_gens, pc_gens, &mut thread_rng())
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch_with_rng`],
+    /// but reports which proofs failed.
+    ///
+    /// When all proofs are valid this costs a single multiscalar
+    /// multiplication, plus a copy of each initial transcript state.
+    /// Only if the combined check fails, each proof is verified on its
+    /// own from its copied transcript, and the indexes of the proofs
+    /// in `batch` that failed are returned in
+    /// [`ProofError::BatchVerificationError`].
+    pub fn verify_batch_identify_with_rng<'a, T: RngCore + CryptoRng, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let failures = Self::batch_failures_with_rng(batch, bp_gens, pc_gens, rng);
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ProofError::BatchVerificationError {
+                bad_indices: failures.into_iter().map(|(i, _)| i).collect(),
+            })
+        }
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch`],
+    /// but names the first proof that failed.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_batch_indexed_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_batch_indexed<'a, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> Result<(), (usize, ProofError)> {
+        Self::verify_batch_indexed_with_rng(batch, bp_gens, pc_gens, &mut thread_rng())
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch_with_rng`],
+    /// but names the first proof that failed.
+    ///
+    /// As with [`RangeProof::verify_batch_identify_with_rng`], valid
+    /// batches cost a single multiscalar multiplication, and the
+    /// proofs are only verified one by one if the combined check
+    /// fails.  On failure, returns the index in `batch` of the first
+    /// invalid proof along with its error.
+    pub fn verify_batch_indexed_with_rng<'a, T: RngCore + CryptoRng, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        rng: &mut T,
+    ) -> Result<(), (usize, ProofError)> {
+        match Self::batch_failures_with_rng(batch, bp_gens, pc_gens, rng)
+            .into_iter()
+            .next()
+        {
+            Some(failure) => Err(failure),
+            None => Ok(()),
+        }
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch`],
+    /// and returns the result of each proof.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_batch_identify_failures_with_rng`], passing
+    /// in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_batch_identify_failures<'a, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> Vec<Result<(), ProofError>> {
+        Self::verify_batch_identify_failures_with_rng(batch, bp_gens, pc_gens, &mut thread_rng())
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch_with_rng`],
+    /// and returns the result of each proof, in the order of `batch`.
+    ///
+    /// As with [`RangeProof::verify_batch_identify_with_rng`], valid
+    /// batches cost a single multiscalar multiplication, and the
+    /// proofs are only verified one by one if the combined check
+    /// fails.
+    pub fn verify_batch_identify_failures_with_rng<
+        'a,
+        T: RngCore + CryptoRng,
+        V: ValueCommitment + 'a,
+    >(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        rng: &mut T,
+    ) -> Vec<Result<(), ProofError>> {
+        let mut count = 0;
+        let failures = Self::batch_failures_with_rng(
+            batch.into_iter().inspect(|_| count += 1),
+            bp_gens,
+            pc_gens,
+            rng,
+        );
+
+        let mut results = vec![Ok(()); count];
+        for (i, e) in failures {
+            results[i] = Err(e);
+        }
+        results
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch`],
+    /// and partitions it into valid and invalid proofs.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_batch_partition_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_batch_partition<'a, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> (Vec<usize>, Vec<usize>) {
+        Self::verify_batch_partition_with_rng(batch, bp_gens, pc_gens, &mut thread_rng())
+    }
+
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch_with_rng`],
+    /// and partitions it into valid and invalid proofs.
+    ///
+    /// Returns the indexes in `batch` of the valid proofs and of the
+    /// invalid proofs, each in increasing order.  This lets a relay
+    /// forward the valid proofs of a batch instead of rejecting all
+    /// of them.
+    ///
+    /// The whole batch is checked with a single multiscalar
+    /// multiplication first.  If that fails, the batch is bisected,
+    /// and each half is checked as a batch until the invalid proofs
+    /// are isolated, so a batch with a few invalid proofs costs a
+    /// number of checks logarithmic in its size.
+    pub fn verify_batch_partition_with_rng<'a, T: RngCore + CryptoRng, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        rng: &mut T,
+    ) -> (Vec<usize>, Vec<usize>) {
+        let mut verifier = BatchVerifier::new(bp_gens, pc_gens);
+        let mut added = Vec::new();
+        let mut valid = Vec::new();
+        let mut invalid = Vec::new();
+        for (i, view) in batch.into_iter().enumerate() {
+            // Keep the initial transcript state, to check subsets of
+            // the batch if it fails.
+            let (proof, value_commitments, bitsizes) =
+                (view.proof, view.value_commitments, view.bitsizes);
+            let transcript = view.transcript.clone();
+            match verifier.add(view, rng) {
+                Ok(()) => added.push((i, proof, transcript, value_commitments, bitsizes)),
+                Err(_) => invalid.push(i),
+            }
+        }
+
+        if verifier.finalize().is_ok() {
+            valid.extend(added.iter().map(|&(i, ..)| i));
+        } else {
+            Self::bisect_failed_batch(&added, bp_gens, pc_gens, rng, &mut valid, &mut invalid);
+            invalid.sort_unstable();
+        }
+
+        (valid, invalid)
+    }
+
+    /// Splits a batch which is known to fail into its valid and
+    /// invalid proofs, appending their indexes in order.
+    fn bisect_failed_batch<T: RngCore + CryptoRng, V: ValueCommitment>(
+        batch: &[(usize, &RangeProof, Transcript, &[V], Bitsizes<'_>)],
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        rng: &mut T,
+        valid: &mut Vec<usize>,
+        invalid: &mut Vec<usize>,
+    ) {
+        if batch.len() == 1 {
+            invalid.push(batch[0].0);
+            return;
+        }
+
+        let (left, right) = batch.split_at(batch.len() / 2);
+        if Self::verify_sub_batch(left, bp_gens, pc_gens, rng) {
+            valid.extend(left.iter().map(|&(i, ..)| i));
+            // The failure must be in the other half.
+            Self::bisect_failed_batch(right, bp_gens, pc_gens, rng, valid, invalid);
+        } else {
+            Self::bisect_failed_batch(left, bp_gens, pc_gens, rng, valid, invalid);
+            if Self::verify_sub_batch(right, bp_gens, pc_gens, rng) {
+                valid.extend(right.iter().map(|&(i, ..)| i));
+            } else {
+                Self::bisect_failed_batch(right, bp_gens, pc_gens, rng, valid, invalid);
+            }
+        }
+    }
+
+    /// Verifies part of a batch from copies of the initial transcripts.
+    fn verify_sub_batch<T: RngCore + CryptoRng, V: ValueCommitment>(
+        batch: &[(usize, &RangeProof, Transcript, &[V], Bitsizes<'_>)],
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        rng: &mut T,
+    ) -> bool {
+        let mut verifier = BatchVerifier::new(bp_gens, pc_gens);
+        for &(_, proof, ref transcript, value_commitments, bitsizes) in batch {
+            let mut transcript = transcript.clone();
+            // The proofs were already added once, so their commitments
+            // passed the strict checks if they were requested.
+            let view = RangeProofView {
+                proof,
+                transcript: &mut transcript,
+                value_commitments,
+                bitsizes,
+                strict: false,
+            };
+            if verifier.add(view, rng).is_err() {
+                return false;
+            }
+        }
+        verifier.finalize().is_ok()
+    }
+
+    /// Verifies a batch of proofs, and returns the index and error of
+    /// each invalid proof, ordered by index.
+    fn batch_failures_with_rng<'a, T: RngCore + CryptoRng, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        rng: &mut T,
+    ) -> Vec<(usize, ProofError)> {
+        let mut verifier = BatchVerifier::new(bp_gens, pc_gens);
+        let mut added = Vec::new();
+        let mut failures = Vec::new();
+        for (i, view) in batch.into_iter().enumerate() {
+            // Keep the initial transcript state, to verify the proof
+            // on its own if the batch fails.
+            let (proof, value_commitments, bitsizes) =
+                (view.proof, view.value_commitments, view.bitsizes);
+            let transcript = view.transcript.clone();
+            match verifier.add(view, rng) {
+                Ok(()) => added.push((i, proof, transcript, value_commitments, bitsizes)),
+                Err(e) => failures.push((i, e)),
+            }
+        }
+
+        if verifier.finalize().is_err() {
+            for (i, proof, mut transcript, value_commitments, bitsizes) in added {
+                // The proof was already added once, so its commitments
+                // passed the strict checks if they were requested.
+                let view = RangeProofView {
+                    proof,
+                    transcript: &mut transcript,
+                    value_commitments,
+                    bitsizes,
+                    strict: false,
+                };
+                if let Err(e) = Self::verify_batch_with_rng(iter::once(view), bp_gens, pc_gens, rng)
+                {
+                    failures.push((i, e));
+                }
+            }
+            failures.sort_unstable_by_key(|&(i, _)| i);
+        }
+
+        failures
+    }
+
+    /// Serializes the proof into a byte array of \\(2 \lg n + 9\\)
+    /// 32-byte elements, where \\(n\\) is the number of secret bits.
+    ///
+    /// # Layout
+    ///
+    /// The layout of the range proof encoding is:
+    ///
+    /// * four compressed Ristretto points \\(A,S,T_1,T_2\\),
+    /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
+    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
+    /// * two scalars \\(a, b\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_size());
+        self.write_bytes(&mut buf);
+        debug_assert_eq!(buf.len(), self.encoded_size());
+        buf
+    }
+
+    /// Writes the encoding of [`RangeProof::to_bytes`] to `w`, one
+    /// 32-byte element at a time, without allocating.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        for element in self.elements() {
+            w.write_all(element)?;
+        }
+        Ok(())
+    }
+
+    /// Appends the encoding of [`RangeProof::to_bytes`] to `buf`.
+    fn write_bytes(&self, buf: &mut Vec<u8>) {
+        buf.reserve(self.encoded_size());
+        for element in self.elements() {
+            buf.extend_from_slice(element);
+        }
+    }
+
+    /// Returns the 32-byte elements of the encoding, in order.
+    fn elements(&self) -> impl Iterator<Item = &[u8; 32]> + '_ {
+        iter::once(self.A.as_bytes())
+            .chain(iter::once(self.S.as_bytes()))
+            .chain(iter::once(self.T_1.as_bytes()))
+            .chain(iter::once(self.T_2.as_bytes()))
+            .chain(iter::once(self.t_x.as_bytes()))
+            .chain(iter::once(self.t_x_blinding.as_bytes()))
+            .chain(iter::once(self.e_blinding.as_bytes()))
+            .chain(self.ipp_proof.elements())
+    }
+
+    /// Returns the size in bytes of a serialized proof for `m` values
+    /// of bitsize `n`.
+    ///
+    /// The proof consists of 7 elements, the points \\(A,S,T_1,T_2\\)
+    /// and the scalars \\(t_x, \tilde{t}_x, \tilde{e}\\), followed by
+    /// an inner product proof over vectors of length \\(n \cdot m\\),
+    /// for \\(n\\) and \\(m\\) padded as when proving.
+    ///
+    /// The size is computed from the logarithms of the padded
+    /// dimensions, so it does not overflow for any `n` and `m`, even
+    /// those no proof can be created for.
+    pub const fn serialized_size(n: usize, m: usize) -> usize {
+        let lg_n = if n <= 8 { 3 } else { util::ceil_log2(n) };
+        7 * 32 + InnerProductProof::serialized_size_lg(lg_n + util::ceil_log2(m))
+    }
+
+    /// Returns the size in bytes of this proof's encoding, as returned
+    /// by [`RangeProof::to_bytes`].
+    ///
+    /// This is [`RangeProof::serialized_size`] for the padded
+    /// dimensions the proof was created with.
+    pub fn encoded_size(&self) -> usize {
+        7 * 32 + self.ipp_proof.len_bytes()
+    }
+
+    /// Returns whether the proof aggregates several values, that is
+    /// whether it was created for \\(m > 1\\) values of bitsize `n`.
+    ///
+    /// The number of values is derived from the length \\(n \cdot m\\)
+    /// of the inner product proof, with \\(n\\) and \\(m\\) padded as
+    /// when proving, so a proof of 3 values counts as a proof of 4.
+    /// Returns `false` if `n` is not a supported bitsize.
+    pub fn is_aggregated(&self, n: usize) -> bool {
+        let nm = 1usize << self.ipp_proof.L_vec.len();
+        match util::padded_bitsize(n) {
+            Some(padded_n) => nm > padded_n,
+            None => false,
+        }
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns [`ProofError::FormatError`] in the following cases:
+    /// * the slice is not a whole number of 32-byte elements, or has
+    ///   fewer than the 7 elements preceding the inner product proof,
+    /// * any of the 3 scalars is not canonical,
+    /// * the slice is longer than a proof of the largest supported
+    ///   dimensions, 128-bit values and
+    ///   [`BulletproofGens::MAX_PARTY_CAPACITY`] parties,
+    /// * the inner product proof fails [`InnerProductProof::from_bytes`].
+    ///
+    /// The points are stored compressed without being checked, so a
+    /// proof with an invalid point is only rejected at verification.
+    /// Use [`RangeProof::from_bytes_strict`] to reject it here.
+    pub fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        RangeProof::from_bytes_with_max_size(slice, 128, BulletproofGens::MAX_PARTY_CAPACITY)
+    }
+
+    /// Deserializes the proof from a byte slice like
+    /// [`RangeProof::from_bytes`], rejecting proofs larger than
+    /// [`RangeProof::serialized_size`] for `m` values of bitsize `n`.
+    ///
+    /// This lets a caller which only accepts proofs up to known
+    /// dimensions reject larger encodings before parsing them.
+    pub fn from_bytes_with_max_size(
+        slice: &[u8],
+        n: usize,
+        m: usize,
+    ) -> Result<RangeProof, ProofError> {
+        if slice.len() > RangeProof::serialized_size(n, m) {
+            return Err(ProofError::FormatError);
+        }
+        if slice.len() % 32 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        if slice.len() < 7 * 32 {
+            return Err(ProofError::FormatError);
+        }
+
+        use crate::util::read32;
+
+        let A = CompressedRistretto(read32(&slice[0 * 32..]));
+        let S = CompressedRistretto(read32(&slice[1 * 32..]));
+        let T_1 = CompressedRistretto(read32(&slice[2 * 32..]));
+        let T_2 = CompressedRistretto(read32(&slice[3 * 32..]));
+
+        let t_x = Option::from(Scalar::from_canonical_bytes(read32(&slice[4 * 32..])))
+            .ok_or(ProofError::FormatError)?;
+        let t_x_blinding = Option::from(Scalar::from_canonical_bytes(read32(&slice[5 * 32..])))
+            .ok_or(ProofError::FormatError)?;
+        let e_blinding = Option::from(Scalar::from_canonical_bytes(read32(&slice[6 * 32..])))
+            .ok_or(ProofError::FormatError)?;
+
+        let ipp_proof = InnerProductProof::from_bytes(&slice[7 * 32..])?;
+
+        Ok(RangeProof {
+            A,
+            S,
+            T_1,
+            T_2,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+        })
+    }
+
+    /// Deserializes the proof from a byte slice like
+    /// [`RangeProof::from_bytes`], and also checks that every point
+    /// of the proof decompresses: \\(A, S, T_1, T_2\\) and the
+    /// \\(L\\) and \\(R\\) points of the inner product proof.
+    ///
+    /// Returns [`ProofError::FormatError`] if the byte slice cannot be
+    /// parsed into a `RangeProof` or contains an invalid point, so
+    /// that such proofs are rejected on receipt rather than at
+    /// verification.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        let proof = RangeProof::from_bytes(slice)?;
+
+        let mut points = [&proof.A, &proof.S, &proof.T_1, &proof.T_2]
+            .iter()
+            .copied()
+            .chain(proof.ipp_proof.L_vec.iter())
+            .chain(proof.ipp_proof.R_vec.iter());
+        if points.all(|P| P.decompress().is_some()) {
+            Ok(proof)
+        } else {
+            Err(ProofError::FormatError)
+        }
+    }
+
+    /// Assembles a proof from its components, as returned by the
+    /// accessors below.
+    ///
+    /// No validation is done: the points are not checked to
+    /// decompress, and nothing ties the components together.  A proof
+    /// built from inconsistent components is only rejected at
+    /// verification.
+    pub fn new_unchecked(
+        A: CompressedRistretto,
+        S: CompressedRistretto,
+        T_1: CompressedRistretto,
+        T_2: CompressedRistretto,
+        t_x: Scalar,
+        t_x_blinding: Scalar,
+        e_blinding: Scalar,
+        ipp_proof: InnerProductProof,
+    ) -> RangeProof {
+        RangeProof {
+            A,
+            S,
+            T_1,
+            T_2,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+        }
+    }
+
+    /// Returns the commitment \\(A\\) to the bits of the values.
+    pub fn A(&self) -> CompressedRistretto {
+        self.A
+    }
+
+    /// Returns the commitment \\(S\\) to the blinding factors of the bits.
+    pub fn S(&self) -> CompressedRistretto {
+        self.S
+    }
+
+    /// Returns the commitment \\(T_1\\) to the \\(t_1\\) coefficient of \\(t(x)\\).
+    pub fn T_1(&self) -> CompressedRistretto {
+        self.T_1
+    }
+
+    /// Returns the commitment \\(T_2\\) to the \\(t_2\\) coefficient of \\(t(x)\\).
+    pub fn T_2(&self) -> CompressedRistretto {
+        self.T_2
+    }
+
+    /// Returns the evaluation \\(t(x)\\) of the polynomial at the challenge point.
+    ///
+    /// This and the blinding factors below are part of the public
+    /// proof encoding, not secrets of the prover, so they can be bound
+    /// into further proofs.
+    pub fn t_x(&self) -> Scalar {
+        self.t_x
+    }
+
+    /// Returns the blinding factor \\(\tilde{t}_x\\) of the synthetic commitment to \\(t(x)\\).
+    pub fn t_x_blinding(&self) -> Scalar {
+        self.t_x_blinding
+    }
+
+    /// Returns the blinding factor \\(\tilde{e}\\) of the synthetic
+    /// commitment to the inner-product arguments.
+    pub fn e_blinding(&self) -> Scalar {
+        self.e_blinding
+    }
+
+    /// Returns the inner-product argument of the proof.
+    pub fn ipp_proof(&self) -> &InnerProductProof {
+        &self.ipp_proof
+    }
+}
+
+/// Serializes the proof as its [`RangeProof::to_bytes`] encoding: a
+/// lowercase hex string in human-readable formats such as JSON, and
+/// raw bytes otherwise, as in bincode.
+impl Serialize for RangeProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&util::to_hex(&self.to_bytes()))
+        } else {
+            serializer.serialize_bytes(&self.to_bytes())
+        }
+    }
+}
+
+/// Deserializes the proof with [`RangeProof::from_bytes`], from a hex
+/// string in human-readable formats and from raw bytes otherwise.
+impl<'de> Deserialize<'de> for RangeProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = if deserializer.is_human_readable() {
+            let hex = alloc::string::String::deserialize(deserializer)?;
+            util::from_hex(&hex).ok_or_else(|| serde::de::Error::custom("invalid hex encoding"))?
+        } else {
+            Vec::deserialize(deserializer)?
+        };
+        // Using Error::custom requires T: Display, which our error
+        // type only implements when it implements std::error::Error.
+        #[cfg(feature = "std")]
+        return RangeProof::from_bytes(&bytes).map_err(serde::de::Error::custom);
+        // In no-std contexts, drop the error message.
+        #[cfg(not(feature = "std"))]
+        return RangeProof::from_bytes(&bytes)
+            .map_err(|_| serde::de::Error::custom("deserialization error"));
+    }
+}
+
+impl ConstantTimeEq for RangeProof {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.A.as_bytes().ct_eq(other.A.as_bytes())
+            & self.S.as_bytes().ct_eq(other.S.as_bytes())
+            & self.T_1.as_bytes().ct_eq(other.T_1.as_bytes())
+            & self.T_2.as_bytes().ct_eq(other.T_2.as_bytes())
+            & self.t_x.ct_eq(&other.t_x)
+            & self.t_x_blinding.ct_eq(&other.t_x_blinding)
+            & self.e_blinding.ct_eq(&other.e_blinding)
+            & self.ipp_proof.ct_eq(&other.ipp_proof)
+    }
+}
+
+impl PartialEq for RangeProof {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for RangeProof {}
+
+/// Hashes the proof encoding, which is public, so that proofs can be
+/// used as keys consistently with their equality.
+impl Hash for RangeProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state);
+    }
+}
+
+// TODO(merge): naming
+pub struct RangeProofView<'a, V: ValueCommitment> {
+    proof: &'a RangeProof,
+    transcript: &'a mut Transcript,
+    value_commitments: &'a [V],
+    bitsizes: Bitsizes<'a>,
+    /// Whether to reject identity and invalid value commitments.
+    strict: bool,
+}
+
+impl<'a, V: ValueCommitment> RangeProofView<'a, V> {
+    /// Rejects the proof with [`ProofError::InvalidCommitment`] if any
+    /// of its value commitments is the identity or does not decompress.
+    ///
+    /// By default, a commitment to 0 with a zero blinding factor, the
+    /// identity, is accepted.  Applications whose rules forbid it can
+    /// make the library enforce them with this mode.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+}
+
+/// The bitsizes of the values in an aggregated range proof.
+#[derive(Clone, Copy)]
+enum Bitsizes<'a> {
+    /// Every value is in \\([0, 2^n)\\).
+    Uniform(usize),
+    /// Every value is in \\([0, 2^n)\\), with the version 0
+    /// transcript.
+    UniformV0(usize),
+    /// The \\(j\\)-th value is in \\([0, 2^{n_j})\\), padded to the
+    /// largest padded bitsize.
+    PerValue(&'a [usize]),
+}
+
+/// An incremental batch verifier for range proofs.
+///
+/// Proofs are added one at a time with [`BatchVerifier::add`], which
+/// replays each proof's transcript and accumulates its verification
+/// equation, and are all checked at once by a single multiscalar
+/// multiplication in [`BatchVerifier::finalize`].  This is the same
+/// procedure as [`RangeProof::verify_batch_with_rng`], which is
+/// implemented on top of this type.
+///
+/// Proofs of different bitsizes and aggregation sizes can be mixed in
+/// the same batch, as long as `bp_gens` has sufficient capacity.
+pub struct BatchVerifier<'a> {
+    collector: BatchCollector<'a>,
+}
+
+impl<'a> BatchVerifier<'a> {
+    /// Creates an empty batch verifier.
+    pub fn new(bp_gens: &'a BulletproofGens, pc_gens: &'a PedersenGens) -> Self {
+        BatchVerifier {
+            collector: BatchCollector::new(bp_gens, pc_gens),
+        }
+    }
+
+    /// Adds a proof to the batch.
+    ///
+    /// Returns an error if the proof is malformed or does not match
+    /// the generators.  In that case the proof is not added, and the
+    /// batch verifier can still be used for other proofs.
+    pub fn add<T: RngCore + CryptoRng, V: ValueCommitment>(
+        &mut self,
+        view: RangeProofView<V>,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.collector.add_proof(view, rng)
+    }
+
+    /// Verifies all the proofs added to the batch at once.
+    ///
+    /// Verifying an empty batch succeeds.
+    pub fn finalize(self) -> Result<(), ProofError> {
+        self.collector.verify()
+    }
+}
+
+/// Accumulates range proofs into the multiscalar multiplication that
+/// verifies them as a batch.
+///
+/// This allows folding each proof into a running batch as it arrives,
+/// with [`BatchCollector::add_proof`], and checking all of them at
+/// once with [`BatchCollector::verify`].  A proof that is rejected by
+/// `add_proof` leaves the collector unchanged, so the proofs added so
+/// far can still be verified.
+///
+/// All the proofs in a collector are checked against the
+/// `BulletproofGens` and `PedersenGens` it was created with, so they
+/// must all have been created with the same generators.
+///
+/// The scalars of the generators are kept in flat buffers which only
+/// grow when a proof needs more generators than the previous ones, so
+/// adding proofs of the same or smaller sizes does not reallocate.
+pub struct BatchCollector<'a> {
+    dynamic_scalars: Vec<Scalar>,
+    dynamic_points: Vec<Option<RistrettoPoint>>,
+    pedersen_B_scalar: Scalar,
+    pedersen_B_blinding_scalar: Scalar,
+    // The scalars for the G and H generators, laid flat in the order of
+    // `bp_gens.G(gens_capacity, party_capacity)`: the scalar of the
+    // i-th generator of party j is at index j * gens_capacity + i.
+    g_scalars: Vec<Scalar>,
+    h_scalars: Vec<Scalar>,
+    party_capacity: usize,
+    gens_capacity: usize,
+    // The number of proofs passed to the collector, including the
+    // rejected ones, which is the index of the next proof.
+    num_proofs: usize,
+    bp_gens: &'a BulletproofGens,
+    pc_gens: &'a PedersenGens,
+}
+
+impl<'a> BatchCollector<'a> {
+    /// Creates an empty batch.
+    pub fn new(bp_gens: &'a BulletproofGens, pc_gens: &'a PedersenGens) -> Self {
+        Self {
+            dynamic_scalars: vec![],
+            dynamic_points: vec![],
+            pedersen_B_scalar: Scalar::ZERO,
+            pedersen_B_blinding_scalar: Scalar::ZERO,
+            g_scalars: vec![],
+            h_scalars: vec![],
+            party_capacity: 0,
+            gens_capacity: 0,
+            num_proofs: 0,
+            bp_gens,
+            pc_gens,
+        }
+    }
+
+    /// Creates an empty batch with room for `n_proofs` proofs of `m`
+    /// values of bitsize `n`.
+    ///
+    /// Each such proof contributes its points \\(A,S,T_1,T_2\\),
+    /// the points of its inner product proof and its `m` value
+    /// commitments, so this reserves the space for these up front.
+    /// Proofs of other sizes can still be added.
+    pub fn with_capacity(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        n_proofs: usize,
+        n: usize,
+        m: usize,
+    ) -> Self {
+        // All but the five scalars t_x, t_x_blinding, e_blinding, a
+        // and b of the proof encoding are points.
+        let points_per_proof = RangeProof::serialized_size(n, m) / 32 - 5 + m;
+        let len = n_proofs * points_per_proof;
+        Self {
+            dynamic_scalars: Vec::with_capacity(len),
+            dynamic_points: Vec::with_capacity(len),
+            ..Self::new(bp_gens, pc_gens)
+        }
+    }
+
+    /// Adds a proof to the batch, drawing its batching scalars from
+    /// `rng`.
+    ///
+    /// Returns an error if the proof is malformed, for instance if one
+    /// of its points cannot be decompressed, or does not match the
+    /// generators.  In that case the proof is not added.
+    ///
+    /// Errors about a point of the proof, such as
+    /// [`ProofError::PointDecompressionError`], identify the proof by
+    /// its index in the order the proofs were passed to the collector,
+    /// counting the rejected ones.
+    pub fn add_proof<T: RngCore + CryptoRng, V: ValueCommitment>(
+        &mut self,
+        view: RangeProofView<V>,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.add_proof_with_factors(view, rng)
+    }
+
+    fn add_proof_with_factors<F: BatchFactors, V: ValueCommitment>(
+        &mut self,
+        view: RangeProofView<V>,
+        factors: &mut F,
+    ) -> Result<(), ProofError> {
+        self.add_proof_at(self.num_proofs, view, factors)
+    }
+
+    /// Adds the proof at index `proof_index` of the batch, which is
+    /// used to identify the proof in errors.
+    fn add_proof_at<F: BatchFactors, V: ValueCommitment>(
+        &mut self,
+        proof_index: usize,
+        view: RangeProofView<V>,
+        factors: &mut F,
+    ) -> Result<(), ProofError> {
+        self.num_proofs += 1;
+        let num_values = view.value_commitments.len();
+
+        if view.strict {
+            for (index, V) in view.value_commitments.iter().enumerate() {
+                if V.compress() == CompressedRistretto::identity() || V.decompress().is_none() {
+                    return Err(ProofError::InvalidCommitment { index });
+                }
+            }
+        }
+
+        // First, replay the "interactive" protocol using the proof
+        // data to recompute all challenges.
+        let m = padded_aggregation_size(self.bp_gens, num_values)?;
+        let (padded_n, bitsizes) = match view.bitsizes {
+            Bitsizes::Uniform(n) | Bitsizes::UniformV0(n) => {
+                let padded_n = util::padded_bitsize(n).ok_or(ProofError::InvalidBitsize)?;
+                if let Bitsizes::UniformV0(_) = view.bitsizes {
+                    view.transcript.rangeproof_domain_sep_v0(n as u64, m as u64);
+                } else {
+                    view.transcript.rangeproof_domain_sep(n as u64, m as u64);
+                }
+                (padded_n, vec![n; m])
+            }
+            Bitsizes::PerValue(bitsizes) => {
+                if bitsizes.len() != num_values {
+                    return Err(ProofError::InvalidInputLength);
+                }
+                let padded_n =
+                    util::max_padded_bitsize(bitsizes).ok_or(ProofError::InvalidBitsize)?;
+                let bitsizes = padded_bitsizes(bitsizes, padded_n, m);
+                view.transcript
+                    .rangeproof_bitsizes_domain_sep(padded_n as u64, &bitsizes);
+                (padded_n, bitsizes)
+            }
+        };
+        if self.bp_gens.gens_capacity < padded_n {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        for V in view.value_commitments.iter() {
+            // Allow the commitments to be zero (0 value, 0 blinding)
+            // See https://github.com/dalek-cryptography/bulletproofs/pull/248#discussion_r255167177
+            view.transcript.append_point(b"V", &V.compress());
+        }
+        // The prover's padding parties commit to 0 with 0 blinding.
+        for _ in num_values..m {
+            view.transcript
+                .append_point(b"V", &CompressedRistretto::identity());
+        }
+
+        let invalid_point =
+            |label: &'static str| ProofError::TranscriptPointValidation { proof_index, label };
+
+        view.transcript
+            .validate_and_append_point(b"A", &view.proof.A)
+            .map_err(|_| invalid_point("A"))?;
+        view.transcript
+            .validate_and_append_point(b"S", &view.proof.S)
+            .map_err(|_| invalid_point("S"))?;
+
+        let y = view.transcript.challenge_scalar(b"y");
+        let z = view.transcript.challenge_scalar(b"z");
+        let zz = z * z;
+        let minus_z = -z;
+
+        view.transcript
+            .validate_and_append_point(b"T_1", &view.proof.T_1)
+            .map_err(|_| invalid_point("T_1"))?;
+        view.transcript
+            .validate_and_append_point(b"T_2", &view.proof.T_2)
+            .map_err(|_| invalid_point("T_2"))?;
+
+        let x = view.transcript.challenge_scalar(b"x");
+
+        view.transcript.append_scalar(b"t_x", &view.proof.t_x);
+        view.transcript
+            .append_scalar(b"t_x_blinding", &view.proof.t_x_blinding);
+        view.transcript
+            .append_scalar(b"e_blinding", &view.proof.e_blinding);
+
+        let w = view.transcript.challenge_scalar(b"w");
+
+        let (x_sq, x_inv_sq, s) = view
+            .proof
+            .ipp_proof
+            .verification_scalars(padded_n * m, view.transcript)
+            .map_err(|e| {
+                // Find the point the inner product proof rejected, if
+                // it failed on one rather than on its length.
+                let ipp = &view.proof.ipp_proof;
+                let rounds = (padded_n * m).trailing_zeros() as usize;
+                if ipp.L_vec.len() != rounds {
+                    return e;
+                }
+                ipp.L_vec
+                    .iter()
+                    .zip(ipp.R_vec.iter())
+                    .flat_map(|(L, R)| iter::once(("L", L)).chain(iter::once(("R", R))))
+                    .find(|(_, P)| **P == CompressedRistretto::identity())
+                    .map_or(e, |(label, _)| invalid_point(label))
+            })?;
+        let s_inv = s.iter().rev();
+
+        let a = view.proof.ipp_proof.a;
+        let b = view.proof.ipp_proof.b;
+
+        // Challenge value for batching statements to be verified, and
+        // batch challenge for batching the proofs.  Not in the
+        // transcript, as each proof has its own transcript.
+        let (c, batch_factor) = factors.draw(proof_index);
+
+        // Construct concat_z_and_2, an iterator of the values of
+        // z^2 * (z^0 * \vec(2)^n_0 || z^1 * \vec(2)^n_1 || ... || z^(m-1) * \vec(2)^n_(m-1)),
+        // where the padding bits past each `n_j` have zero weight.
+        // It is consumed once by `h`, so it is not collected, and each
+        // party's entries are computed by doubling z^(j+2).
+        let concat_z_and_2 = util::exp_iter(z)
+            .zip(bitsizes.iter())
+            .flat_map(|(exp_z, &n_j)| util::scaled_padded_powers_of_2(zz * exp_z, n_j, padded_n));
+
+        let mut g = s.iter().map(|s_i| minus_z - a * s_i);
+        let mut h = s_inv
+            .zip(util::exp_iter(y.invert()))
+            .zip(concat_z_and_2)
+            .map(|((s_i_inv, exp_y_inv), zz_and_2)| z + exp_y_inv * (zz_and_2 - b * s_i_inv));
+
+        // The padding commitments are the identity, so they are skipped.
+        let value_commitment_scalars = util::exp_iter(z)
+            .take(num_values)
+            .map(|z_exp| c * zz * z_exp);
+        let basepoint_scalar = w * (view.proof.t_x - a * b)
+            + c * (delta(&bitsizes, padded_n, &y, &z) - view.proof.t_x);
+
+        // Decompress the points before modifying the batch, so that a
+        // malformed proof leaves it unchanged, and report the first
+        // point which is not a valid encoding.
+        let ipp = &view.proof.ipp_proof;
+        let points = iter::once(("A", view.proof.A.decompress()))
+            .chain(iter::once(("S", view.proof.S.decompress())))
+            .chain(iter::once(("T_1", view.proof.T_1.decompress())))
+            .chain(iter::once(("T_2", view.proof.T_2.decompress())))
+            .chain(ipp.L_vec.iter().map(|L| ("L", L.decompress())))
+            .chain(ipp.R_vec.iter().map(|R| ("R", R.decompress())))
+            .chain(view.value_commitments.iter().map(|V| ("V", V.decompress())))
+            .map(|(component, P)| {
+                P.ok_or(ProofError::PointDecompressionError {
+                    proof_index,
+                    component,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Collect for batched multiscalar mul.
+
+        self.dynamic_scalars.extend(
+            iter::once(Scalar::ONE)
+                .chain(iter::once(x))
+                .chain(iter::once(c * x))
+                .chain(iter::once(c * x * x))
+                .chain(x_sq.iter().cloned())
+                .chain(x_inv_sq.iter().cloned())
+                .chain(value_commitment_scalars)
+                .map(|s| s * batch_factor),
+        );
+
+        self.dynamic_points.extend(points.into_iter().map(Some));
+
+        self.pedersen_B_blinding_scalar +=
+            (-view.proof.e_blinding - c * view.proof.t_x_blinding) * batch_factor;
+        self.pedersen_B_scalar += basepoint_scalar * batch_factor;
+
+        // Support (m,n) that are less than the bp_gens capacity.
+        self.grow(padded_n, m);
+        debug_assert!(padded_n <= self.gens_capacity && m <= self.party_capacity);
+
+        for cur_m in 0..m {
+            let offset = cur_m * self.gens_capacity;
+            for idx in offset..offset + padded_n {
+                self.g_scalars[idx] += g.next().unwrap() * batch_factor;
+                self.h_scalars[idx] += h.next().unwrap() * batch_factor;
+            }
+        }
+        debug_assert!(g.next().is_none() && h.next().is_none());
+
+        Ok(())
+    }
+
+    /// Adds the contributions accumulated by `other` to this collector.
+    #[cfg(feature = "rayon")]
+    fn merge(&mut self, other: BatchCollector<'a>) {
+        self.dynamic_scalars.extend(other.dynamic_scalars);
+        self.dynamic_points.extend(other.dynamic_points);
+        self.num_proofs += other.num_proofs;
+        self.pedersen_B_scalar += other.pedersen_B_scalar;
+        self.pedersen_B_blinding_scalar += other.pedersen_B_blinding_scalar;
+
+        self.grow(other.gens_capacity, other.party_capacity);
+        for j in 0..other.party_capacity {
+            let src = j * other.gens_capacity;
+            let dst = j * self.gens_capacity;
+            for i in 0..other.gens_capacity {
+                self.g_scalars[dst + i] += other.g_scalars[src + i];
+                self.h_scalars[dst + i] += other.h_scalars[src + i];
+            }
+        }
+    }
+
+    /// Grows the generator scalars to cover at least `gens_capacity`
+    /// generators for each of `party_capacity` parties, moving the
+    /// scalars accumulated so far to their new offsets.
+    fn grow(&mut self, gens_capacity: usize, party_capacity: usize) {
+        let gens_capacity = gens_capacity.max(self.gens_capacity);
+        let party_capacity = party_capacity.max(self.party_capacity);
+        let len = gens_capacity * party_capacity;
+
+        if gens_capacity == self.gens_capacity {
+            // The offsets are unchanged, new parties go at the end.
+            self.g_scalars.resize(len, Scalar::ZERO);
+            self.h_scalars.resize(len, Scalar::ZERO);
+        } else {
+            let old_capacity = self.gens_capacity;
+            let relayout = |old: &[Scalar]| {
+                let mut new = vec![Scalar::ZERO; len];
+                if old_capacity > 0 {
+                    for (row, old_row) in new
+                        .chunks_exact_mut(gens_capacity)
+                        .zip(old.chunks_exact(old_capacity))
+                    {
+                        row[..old_capacity].copy_from_slice(old_row);
+                    }
+                }
+                new
+            };
+            self.g_scalars = relayout(&self.g_scalars);
+            self.h_scalars = relayout(&self.h_scalars);
+        }
+
+        self.gens_capacity = gens_capacity;
+        self.party_capacity = party_capacity;
+    }
+
+    /// Verifies all the proofs added to the batch at once.
+    ///
+    /// Verifying an empty batch succeeds.
+    pub fn verify(self) -> Result<(), ProofError> {
+        self.verify_with_backend(&backend::DalekBackend)
+    }
+}
+
+/// A source of the random scalars a batch verifier uses to combine
+/// the checks of each proof.
+trait BatchFactors {
+    /// Returns the scalar combining the checks within the proof at
+    /// index `proof_index` of the batch, and the scalar combining the
+    /// proof with the rest of the batch.
+    fn draw(&mut self, proof_index: usize) -> (Scalar, Scalar);
+}
+
+impl<T: RngCore + CryptoRng> BatchFactors for T {
+    fn draw(&mut self, _: usize) -> (Scalar, Scalar) {
+        let c = Scalar::random(self);
+        (c, Scalar::random(self))
+    }
+}
+
+/// Derives the batching scalars from a transcript of the whole batch,
+/// for verification without an RNG.
+///
+/// Every proof of the batch must be absorbed with
+/// [`TranscriptBatchFactors::absorb`] before the first scalar is
+/// drawn, so that each scalar depends on all the proofs and not only
+/// on the proof it scales.
+struct TranscriptBatchFactors {
+    batch: Transcript,
+}
+
+impl TranscriptBatchFactors {
+    fn new() -> Self {
+        TranscriptBatchFactors {
+            batch: Transcript::new(b"RangeProofDeterministicBatch"),
+        }
+    }
+
+    /// Absorbs the next proof of the batch, with everything that
+    /// determines its transcript once replayed: the initial state of
+    /// the transcript, the bitsizes, the value commitments and the
+    /// proof itself.
+    fn absorb<V: ValueCommitment>(&mut self, view: &RangeProofView<V>) {
+        // Summarize the initial state without modifying the verifier's
+        // transcript.
+        let mut initial_state = [0u8; 64];
+        view.transcript
+            .clone()
+            .challenge_bytes(b"batch_initial_state", &mut initial_state);
+        self.batch.append_message(b"initial_state", &initial_state);
+
+        match view.bitsizes {
+            Bitsizes::Uniform(n) => self.batch.append_u64(b"n", n as u64),
+            Bitsizes::UniformV0(n) => self.batch.append_u64(b"n_v0", n as u64),
+            Bitsizes::PerValue(bitsizes) => {
+                self.batch
+                    .append_u64(b"num_bitsizes", bitsizes.len() as u64);
+                for &n in bitsizes {
+                    self.batch.append_u64(b"n_j", n as u64);
+                }
+            }
+        }
+        self.batch
+            .append_u64(b"m", view.value_commitments.len() as u64);
+        for V in view.value_commitments.iter() {
+            self.batch.append_point(b"V", &V.compress());
+        }
+        self.batch.append_message(b"proof", &view.proof.to_bytes());
+    }
+}
+
+impl BatchFactors for TranscriptBatchFactors {
+    fn draw(&mut self, proof_index: usize) -> (Scalar, Scalar) {
+        let mut transcript = self.batch.clone();
+        transcript.append_u64(b"proof_index", proof_index as u64);
+        (
+            transcript.challenge_scalar(b"c"),
+            transcript.challenge_scalar(b"batch_factor"),
+        )
+    }
+}
+
+/// Returns the aggregation size `m` rounded up to the next power of
+/// two, checking that it is positive and fits in the generators.
+fn padded_aggregation_size(bp_gens: &BulletproofGens, m: usize) -> Result<usize, ProofError> {
+    match m.checked_next_power_of_two() {
+        Some(padded_m) if m > 0 && padded_m <= bp_gens.party_capacity => Ok(padded_m),
+        _ => Err(ProofError::InvalidAggregation),
+    }
+}
+
+/// Pads `parties` with parties committing to 0 with 0 blinding, up to
+/// the next power of two, and returns the original number of parties.
+fn pad_parties(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    parties: &mut Vec<party::PartyAwaitingPosition>,
+    n: usize,
+) -> Result<usize, ProofError> {
+    let m = parties.len();
+    let padded_m = padded_aggregation_size(bp_gens, m)?;
+    for _ in m..padded_m {
+        parties.push(party::Party::new(bp_gens, pc_gens, 0, Scalar::ZERO, n)?);
+    }
+    Ok(m)
+}
+
+/// Assigns each of the `parties` its position, in order, and returns
+/// their bit commitments.
+///
+/// Each party draws its randomness from its own RNG, derived from `rng`
+/// by [`party_rngs`], so the result does not depend on whether the
+/// `rayon` feature is enabled.
+fn assign_positions<'a, T: RngCore + CryptoRng>(
+    parties: Vec<party::PartyAwaitingPosition<'a>>,
+    rng: &mut T,
+) -> (
+    Vec<party::PartyAwaitingBitChallenge<'a>>,
+    Vec<messages::BitCommitment>,
+) {
+    #[cfg(feature = "rayon")]
+    use rayon::prelude::*;
+
+    let rngs = party_rngs(rng, parties.len());
+
+    #[cfg(feature = "rayon")]
+    let parties = parties.into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let parties = parties.into_iter();
+
+    parties
+        .zip(rngs)
+        .enumerate()
+        .map(|(j, (p, mut rng))| {
+            p.assign_position_with_rng(j, &mut rng)
+                .expect("We already checked the parameters, so this should never happen")
+        })
+        .unzip()
+}
+
+/// Applies the `bit_challenge` to each of the `parties`, and returns
+/// their polynomial commitments.
+///
+/// Each party draws its randomness from its own RNG, derived from `rng`
+/// by [`party_rngs`], so the result does not depend on whether the
+/// `rayon` feature is enabled.
+fn apply_bit_challenge<T: RngCore + CryptoRng>(
+    parties: Vec<party::PartyAwaitingBitChallenge>,
+    bit_challenge: &messages::BitChallenge,
+    rng: &mut T,
+) -> (
+    Vec<party::PartyAwaitingPolyChallenge>,
+    Vec<messages::PolyCommitment>,
+) {
+    #[cfg(feature = "rayon")]
+    use rayon::prelude::*;
+
+    let rngs = party_rngs(rng, parties.len());
+
+    #[cfg(feature = "rayon")]
+    let parties = parties.into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let parties = parties.into_iter();
+
+    parties
+        .zip(rngs)
+        .map(|(p, mut rng)| p.apply_challenge_with_rng(bit_challenge, &mut rng))
+        .unzip()
+}
+
+/// Applies the `poly_challenge` to each of the `parties`, and returns
+/// their proof shares.
+fn apply_poly_challenge(
+    parties: Vec<party::PartyAwaitingPolyChallenge>,
+    poly_challenge: &messages::PolyChallenge,
+) -> Result<Vec<messages::ProofShare>, crate::errors::MPCError> {
+    #[cfg(feature = "rayon")]
+    use rayon::prelude::*;
+
+    #[cfg(feature = "rayon")]
+    let parties = parties.into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let parties = parties.into_iter();
+
+    parties
+        .map(|p| p.apply_challenge(poly_challenge))
+        // Collect the iterator of Results into a Result<Vec>
+        .collect()
+}
+
+/// Derives an independent [`ChaChaRng`] for each of `count` parties,
+/// seeding party `j` with the `j`-th 32-byte block drawn from `rng`.
+///
+/// The derivation is the same with and without the `rayon` feature, and
+/// ChaCha20's output is fixed by RFC 8439, so a seeded `rng` gives the
+/// same proof in every build.
+fn party_rngs<T: RngCore + CryptoRng>(rng: &mut T, count: usize) -> Vec<ChaChaRng> {
+    (0..count)
+        .map(|_| {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            ChaChaRng::from_seed(seed)
+        })
+        .collect()
+}
+
+/// Compute
+/// \\[
+/// \delta(y,z) = (z - z^{2}) \langle \mathbf{1}, {\mathbf{y}}^{n' \cdot m} \rangle - \sum_{j=0}^{m-1} z^{j+3} \cdot \langle \mathbf{1}, {\mathbf{2}}^{n_j} \rangle
+/// \\]
+/// where \\(n_j\\) is the bitsize of the \\(j\\)-th of the \\(m\\)
+/// parties and \\(n' \geq n_j\\) is the padded bitsize.
+fn delta(bitsizes: &[usize], padded_n: usize, y: &Scalar, z: &Scalar) -> Scalar {
+    let m = bitsizes.len();
+    let sum_y = util::sum_of_powers(y, padded_n * m);
+    let two = Scalar::from(2u64);
+    let sum_z_and_2: Scalar = util::exp_iter(*z)
+        .zip(bitsizes.iter())
+        .map(|(exp_z, &n_j)| exp_z * util::sum_of_powers(&two, n_j))
+        .sum();
+
+    (z - z * z) * sum_y - z * z * z * sum_z_and_2
+}
+
+/// Extends per-value `bitsizes` to the `m` parties of a padded
+/// aggregation, where the padding parties prove `padded_n`-bit ranges.
+fn padded_bitsizes(bitsizes: &[usize], padded_n: usize, m: usize) -> Vec<usize> {
+    let mut padded = bitsizes.to_vec();
+    padded.resize(m, padded_n);
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::generators::PedersenGens;
+
+    #[test]
+    fn test_delta() {
+        let mut rng = rand::thread_rng();
+        let y = Scalar::random(&mut rng);
+        let z = Scalar::random(&mut rng);
+
+        // Choose n = 256 to ensure we overflow the group order during
+        // the computation, to check that that's done correctly
+        let n = 256;
+
+        // code copied from previous implementation
+        let z2 = z * z;
+        let z3 = z2 * z;
+        let mut power_g = Scalar::ZERO;
+        let mut exp_y = Scalar::ONE; // start at y^0 = 1
+        let mut exp_2 = Scalar::ONE; // start at 2^0 = 1
+        for _ in 0..n {
+            power_g += (z - z2) * exp_y - z3 * exp_2;
+
+            exp_y = exp_y * y; // y^i -> y^(i+1)
+            exp_2 = exp_2 + exp_2; // 2^i -> 2^(i+1)
+        }
+
+        assert_eq!(power_g, delta(&[n], n, &y, &z),);
+    }
+
+    /// Given a bitsize `n`, test the following:
+    ///
+    /// 1. Generate `m` random values and create a proof they are all in range;
+    /// 2. Serialize to wire format;
+    /// 3. Deserialize from wire format;
+    /// 4. Verify the proof.
+    fn singleparty_create_and_verify_helper(n: usize, m: usize) {
+        // Split the test into two scopes, so that it's explicit what
+        // data is shared between the prover and the verifier.
+
+        // Use bincode for serialization
+        //use bincode; // already present in lib.rs
+
+        // Both prover and verifier have access to the generators and the proof
+        let max_bitsize = 128;
+        let max_parties = 32;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(max_bitsize, max_parties);
+
+        // Prover's scope
+        let (proof_bytes, value_commitments) = {
+            use self::rand::Rng;
+            let mut rng = rand::thread_rng();
+
+            // 0. Create witness data
+            // Truncating keeps the maximum in range for n > 64.
+            let (min, max) = (0u64, (u128::MAX >> (128 - n)) as u64);
+            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
+            let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+            // 1. Create the proof
+            let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+            let (proof, value_commitments) = RangeProof::prove_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &values,
+                &blindings,
+                n,
+            )
+            .unwrap();
+
+            // 2. Return serialized proof and value commitments
+            (bincode::serialize(&proof).unwrap(), value_commitments)
+        };
+
+        // Verifier's scope
+        {
+            // 3. Deserialize
+            let proof: RangeProof = bincode::deserialize(&proof_bytes).unwrap();
+
+            // 4. Verify with the same customization label as above
+            let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+
+            assert!(proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_1() {
+        singleparty_create_and_verify_helper(32, 1);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_2() {
+        singleparty_create_and_verify_helper(32, 2);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_4() {
+        singleparty_create_and_verify_helper(32, 4);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_8() {
+        singleparty_create_and_verify_helper(32, 8);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_1() {
+        singleparty_create_and_verify_helper(64, 1);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_2() {
+        singleparty_create_and_verify_helper(64, 2);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_4() {
+        singleparty_create_and_verify_helper(64, 4);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_8() {
+        singleparty_create_and_verify_helper(64, 8);
+    }
+
+    // With the `rayon` feature, large aggregations are proved on
+    // multiple threads.
+    #[test]
+    fn create_and_verify_n_64_m_16() {
+        singleparty_create_and_verify_helper(64, 16);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_32() {
+        singleparty_create_and_verify_helper(64, 32);
+    }
+
+    #[test]
+    fn create_and_verify_n_128_m_1() {
+        singleparty_create_and_verify_helper(128, 1);
+    }
+
+    #[test]
+    fn create_and_verify_n_128_m_2() {
+        singleparty_create_and_verify_helper(128, 2);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_3() {
+        singleparty_create_and_verify_helper(32, 3);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_5() {
+        singleparty_create_and_verify_helper(32, 5);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_6() {
+        singleparty_create_and_verify_helper(32, 6);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_7() {
+        singleparty_create_and_verify_helper(32, 7);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_3() {
+        singleparty_create_and_verify_helper(64, 3);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_5() {
+        singleparty_create_and_verify_helper(64, 5);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_6() {
+        singleparty_create_and_verify_helper(64, 6);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_7() {
+        singleparty_create_and_verify_helper(64, 7);
+    }
+
+    #[test]
+    fn padded_aggregation_returns_real_commitments() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 8);
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<Scalar> = (0..5).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"PaddedAggregationTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2, 3, 4, 5],
+            &blindings,
+            32,
+        )
+        .unwrap();
+        assert_eq!(commitments.len(), 5);
+        assert_eq!(proof.to_bytes().len(), RangeProof::serialized_size(32, 5));
+
+        // The proof does not verify against a prefix of the commitments.
+        let mut transcript = Transcript::new(b"PaddedAggregationTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments[..4], 32)
+            .is_err());
+    }
+
+    #[test]
+    fn padding_parties_cannot_be_replaced() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<Scalar> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"PaddingForgeryTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2, 3],
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        // The proof is bound to m = 3, so it does not verify as a proof
+        // for four values, whether the fourth commitment is the padding
+        // commitment or a commitment to another value.
+        let padding = RistrettoPoint::identity().compress();
+        let other = pc_gens
+            .commit(Scalar::from(1u64 << 40), Scalar::random(&mut rng))
+            .compress();
+        for fourth in [padding, other] {
+            let mut extended = commitments.clone();
+            extended.push(fourth);
+            let mut transcript = Transcript::new(b"PaddingForgeryTest");
+            assert!(proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &extended, 32)
+                .is_err());
+        }
+
+        let mut transcript = Transcript::new(b"PaddingForgeryTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+    }
+
+    #[test]
+    fn party_rngs_are_derived_from_chacha_blocks() {
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        // With an all-zero seed, the two party seeds are the first 64
+        // bytes of the RFC 8439 ChaCha20 keystream, and each party RNG
+        // then outputs the keystream for its own seed.  This holds with
+        // and without the `rayon` feature.
+        let expected = [
+            "b0fd14ff96a0bda154c329082c9c6533bb4c9473bf5dde138f82c9ac5553d958",
+            "18857cd7f14d7c81c9336373a0c112a55e0ce5c61087139b2a2779c02bfd195a",
+        ];
+        let rngs = party_rngs(&mut ChaChaRng::from_seed([0u8; 32]), 2);
+        assert_eq!(rngs.len(), 2);
+        for (mut rng, expected) in rngs.into_iter().zip(expected.iter()) {
+            let mut output = [0u8; 32];
+            rng.fill_bytes(&mut output);
+            assert_eq!(hex::encode(output), *expected);
+        }
+    }
+
+    #[test]
+    fn prove_multiple_matches_sequential_parties() {
+        use self::dealer::*;
+        use self::party::*;
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let values = [3u64, 1 << 20, 12345, u32::MAX as u64];
+        let blindings: Vec<_> = (1..5u64).map(Scalar::from).collect();
+
+        let mut transcript = Transcript::new(b"PartyRngsTest");
+        let (proof, commitments) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            32,
+            &mut ChaChaRng::from_seed([5u8; 32]),
+        )
+        .unwrap();
+
+        // Drive the parties one at a time, each with the RNG derived for
+        // it, which is what the prover does with or without `rayon`.
+        let mut rng = ChaChaRng::from_seed([5u8; 32]);
+        let mut transcript = Transcript::new(b"PartyRngsTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, 32, 4).unwrap();
+
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = values
+            .iter()
+            .zip(blindings.iter())
+            .zip(party_rngs(&mut rng, 4))
+            .enumerate()
+            .map(|(j, ((&v, &v_blinding), mut party_rng))| {
+                Party::new(&bp_gens, &pc_gens, v, v_blinding, 32)
+                    .unwrap()
+                    .assign_position_with_rng(j, &mut party_rng)
+                    .unwrap()
+            })
+            .unzip();
+        let expected_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .zip(party_rngs(&mut rng, 4))
+            .map(|(p, mut party_rng)| p.apply_challenge_with_rng(&bit_challenge, &mut party_rng))
+            .unzip();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+        let expected = dealer.receive_trusted_shares(&proof_shares).unwrap();
+
+        assert_eq!(proof, expected);
+        assert_eq!(commitments, expected_commitments);
+    }
+
+    #[test]
+    fn prove_from_iter_matches_prove_multiple() {
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let values = [3u64, 1 << 20, 12345];
+        let blindings = [Scalar::from(7u64), Scalar::from(8u64), Scalar::from(9u64)];
+
+        // Values generated lazily, without slices.
+        let mut transcript = Transcript::new(b"ProveFromIterTest");
+        let (proof, commitments) = RangeProof::prove_from_iter_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            (0..3).map(|i| (values[i], blindings[i])),
+            32,
+            &mut ChaChaRng::from_seed([1u8; 32]),
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"ProveFromIterTest");
+        let expected = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            32,
+            &mut ChaChaRng::from_seed([1u8; 32]),
+        )
+        .unwrap();
+        assert_eq!((proof.clone(), commitments.clone()), expected);
+
+        let mut transcript = Transcript::new(b"ProveFromIterTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+
+        // The aggregation size is checked before consuming the values.
+        let mut consumed = 0;
+        let mut transcript = Transcript::new(b"ProveFromIterTest");
+        assert_eq!(
+            RangeProof::prove_from_iter(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                (0..5).map(|v| {
+                    consumed += 1;
+                    (v, Scalar::ONE)
+                }),
+                32,
+            )
+            .unwrap_err(),
+            ProofError::InvalidAggregation
+        );
+        assert_eq!(consumed, 0);
+    }
+
+    #[test]
+    fn strict_mode_rejects_identity_commitments() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let mut rng = rand::thread_rng();
+
+        let blindings = [
+            Scalar::random(&mut rng),
+            Scalar::ZERO,
+            Scalar::random(&mut rng),
+        ];
+        let mut transcript = Transcript::new(b"StrictTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[5, 0, 7],
+            &blindings,
+            32,
+        )
+        .unwrap();
+        assert_eq!(commitments[1], CompressedRistretto::identity());
+
+        let mut transcript = Transcript::new(b"StrictTest");
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32),
+            Ok(())
+        );
+        let mut transcript = Transcript::new(b"StrictTest");
+        assert_eq!(
+            proof.verify_multiple_strict(&bp_gens, &pc_gens, &mut transcript, &commitments, 32),
+            Err(ProofError::InvalidCommitment { index: 1 })
+        );
+
+        // An undecodable commitment is reported at its index too.
+        let mut invalid = commitments.clone();
+        invalid[1] = commitments[0];
+        invalid[2] = CompressedRistretto([0xff; 32]);
+        let mut transcript = Transcript::new(b"StrictTest");
+        assert_eq!(
+            proof.verify_multiple_strict(&bp_gens, &pc_gens, &mut transcript, &invalid, 32),
+            Err(ProofError::InvalidCommitment { index: 2 })
+        );
+
+        // Strict views still accept valid proofs, alone or in batches.
+        let proofs: Vec<(RangeProof, Vec<CompressedRistretto>)> = (1..4u64)
+            .map(|v| {
+                let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+                let mut transcript = Transcript::new(b"StrictTest");
+                RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &[v, 0],
+                    &blindings,
+                    32,
+                )
+                .unwrap()
+            })
+            .collect();
+        let (first, Vs) = &proofs[0];
+        let mut transcript = Transcript::new(b"StrictTest");
+        assert_eq!(
+            first.verify_multiple_strict(&bp_gens, &pc_gens, &mut transcript, Vs, 32),
+            Ok(())
+        );
+        let mut transcripts = vec![Transcript::new(b"StrictTest"); proofs.len()];
+        let views = proofs
+            .iter()
+            .zip(transcripts.iter_mut())
+            .map(|((proof, Vs), t)| proof.verification_view(t, &Vs[..], 32).strict());
+        assert_eq!(RangeProof::verify_batch(views, &bp_gens, &pc_gens), Ok(()));
+
+        // A strict batch fails on the identity commitment.
+        let mut transcripts = vec![Transcript::new(b"StrictTest"); 2];
+        let batch = [(&proofs[0].0, &proofs[0].1), (&proof, &commitments)];
+        let views = batch
+            .iter()
+            .zip(transcripts.iter_mut())
+            .map(|((proof, Vs), t)| proof.verification_view(t, &Vs[..], 32).strict());
+        assert_eq!(
+            RangeProof::verify_batch(views, &bp_gens, &pc_gens),
+            Err(ProofError::InvalidCommitment { index: 1 })
+        );
+    }
+
+    #[test]
+    fn invalid_aggregation_sizes_are_rejected() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 8);
+        let mut transcript = Transcript::new(b"InvalidAggregationTest");
+
+        // No values, and more values than the party capacity.
+        for m in [0, 9] {
+            assert_eq!(
+                RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &vec![0; m],
+                    &vec![Scalar::ZERO; m],
+                    32,
+                )
+                .unwrap_err(),
+                ProofError::InvalidAggregation
+            );
+        }
+
+        let (proof, V) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 7, &Scalar::ONE, 32)
+                .unwrap();
+        for m in [0, 9] {
+            assert_eq!(
+                proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &vec![V; m], 32),
+                Err(ProofError::InvalidAggregation)
+            );
+        }
+    }
+
+    #[test]
+    fn verify_over_verifier_computed_combination() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+
+        let values = [100u64, 250, 7];
+        let blindings: Vec<Scalar> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+        let coefficients = [Scalar::from(3u64), Scalar::from(2u64), Scalar::from(10u64)];
+        let base_commitments: Vec<RistrettoPoint> = values
+            .iter()
+            .zip(&blindings)
+            .map(|(&v, &r)| pc_gens.commit(Scalar::from(v), r))
+            .collect();
+
+        // The prover knows the openings, so can open the combination.
+        let v = 3 * 100 + 2 * 250 + 10 * 7;
+        let v_blinding: Scalar = coefficients
+            .iter()
+            .zip(&blindings)
+            .map(|(c, r)| c * r)
+            .sum();
+        let mut transcript = Transcript::new(b"CombinationTest");
+        let (proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, v, &v_blinding, 32)
+                .unwrap();
+
+        let mut transcript = Transcript::new(b"CombinationTest");
+        assert!(proof
+            .verify_single_over_combination(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &base_commitments,
+                &coefficients,
+                32
+            )
+            .is_ok());
+
+        let mut transcript = Transcript::new(b"CombinationTest");
+        assert!(proof
+            .verify_single_over_combination(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &base_commitments,
+                &[Scalar::from(3u64), Scalar::from(2u64), Scalar::from(11u64)],
+                32
+            )
+            .is_err());
+
+        let mut transcript = Transcript::new(b"CombinationTest");
+        assert_eq!(
+            proof.verify_single_over_combination(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &base_commitments,
+                &coefficients[..2],
+                32
+            ),
+            Err(ProofError::InvalidInputLength)
+        );
+    }
+
+    /// Creates a proof for `values` of the given `bitsizes` without the
+    /// range checks of `Party::new`, as a dishonest prover would.
+    pub(super) fn prove_unchecked(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[Scalar],
+        bitsizes: &[usize],
+    ) -> (RangeProof, Vec<CompressedRistretto>) {
+        use self::dealer::*;
+        use self::party::*;
+
+        let mut rng = rand::thread_rng();
+        let padded_n = util::max_padded_bitsize(bitsizes).unwrap();
+        let dealer = if bitsizes.iter().all(|&n| n == bitsizes[0]) {
+            Dealer::new(bp_gens, pc_gens, transcript, bitsizes[0], values.len())
+        } else {
+            Dealer::new_with_bitsizes(bp_gens, pc_gens, transcript, bitsizes)
+        }
+        .unwrap();
+
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = values
+            .iter()
+            .zip(bitsizes.iter())
+            .enumerate()
+            .map(|(j, (&v, &n))| {
+                let blinding = Scalar::random(&mut rng);
+                Party::new_from_scalar_padded(bp_gens, pc_gens, v, blinding, n, padded_n)
+                    .unwrap()
+                    .assign_position_with_rng(j, &mut rng)
+                    .unwrap()
+            })
+            .unzip();
+        let Vs: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
+            .unzip();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+
+        (dealer.receive_trusted_shares(&proof_shares).unwrap(), Vs)
+    }
+
+    #[test]
+    fn u128_values_in_range() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 4);
+        let mut rng = rand::thread_rng();
+
+        let values = [0, 1 << 64, 1 << 127, u128::MAX];
+        let blindings: Vec<Scalar> = (0..4).map(|_| Scalar::random(&mut rng)).collect();
+        let mut transcript = Transcript::new(b"U128Test");
+        let (proof, commitments) = RangeProof::prove_multiple_u128(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            128,
+        )
+        .unwrap();
+        for (&v, (&V, blinding)) in values.iter().zip(commitments.iter().zip(&blindings)) {
+            assert_eq!(V, pc_gens.commit(Scalar::from(v), *blinding).compress());
+        }
+        let mut transcript = Transcript::new(b"U128Test");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 128)
+            .is_ok());
+
+        // A 128-bit value is out of range for n = 96: the prover rejects
+        // it, and a proof made without the check does not verify.
+        let mut transcript = Transcript::new(b"U128Test");
+        assert_eq!(
+            RangeProof::prove_single_u128(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                1 << 100,
+                &blindings[0],
+                96,
+            )
+            .unwrap_err(),
+            ProofError::ValueOutOfRange
+        );
+        let mut transcript = Transcript::new(b"U128Test");
+        let (proof, Vs) = prove_unchecked(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[Scalar::from(1u128 << 100)],
+            &[96],
+        );
+        let mut transcript = Transcript::new(b"U128Test");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &Vs[0], 96)
+            .is_err());
+
+        let mut transcript = Transcript::new(b"U128Test");
+        assert_eq!(
+            RangeProof::prove_single_u128(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                0,
+                &blindings[0],
+                129
+            )
+            .unwrap_err(),
+            ProofError::InvalidBitsize
+        );
+    }
+
+    #[test]
+    fn u128_proofs_batch_with_u64_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 2);
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<Scalar> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"U128BatchTest");
+        let (wide_proof, wide_Vs) = RangeProof::prove_multiple_u128(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[u128::MAX - 1, u128::MAX],
+            &blindings,
+            128,
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"U128BatchTest");
+        let (narrow_proof, narrow_Vs) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[u64::MAX, 0],
+            &blindings,
+            64,
+        )
+        .unwrap();
+
+        let mut transcripts = vec![Transcript::new(b"U128BatchTest"); 2];
+        let (t0, t1) = transcripts.split_at_mut(1);
+        let batch = vec![
+            wide_proof.verification_view(&mut t0[0], &wide_Vs, 128),
+            narrow_proof.verification_view(&mut t1[0], &narrow_Vs, 64),
+        ];
+        assert!(RangeProof::verify_batch(batch, &bp_gens, &pc_gens).is_ok());
+
+        // A 65-bit value is out of range for n = 64: the prover rejects
+        // it, and a proof made without the check does not verify.
+        let mut transcript = Transcript::new(b"U128BatchTest");
+        assert_eq!(
+            RangeProof::prove_single_u128(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                1 << 64,
+                &blindings[0],
+                64,
+            )
+            .unwrap_err(),
+            ProofError::ValueOutOfRange
+        );
+        let mut transcript = Transcript::new(b"U128BatchTest");
+        let (proof, Vs) = prove_unchecked(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[Scalar::from(1u128 << 64)],
+            &[64],
+        );
+        let mut transcript = Transcript::new(b"U128BatchTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &Vs[0], 64)
+            .is_err());
+    }
+
+    #[test]
+    fn proof_equality() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let prove = || {
+            let mut transcript = Transcript::new(b"EqualityTest");
+            RangeProof::prove_multiple_seeded(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &[17, 4000],
+                &[Scalar::from(5u64), Scalar::from(6u64)],
+                32,
+                &[9u8; 32],
+            )
+            .unwrap()
+            .0
+        };
+
+        let proof = prove();
+        assert_eq!(proof, prove());
+        assert_eq!(proof, RangeProof::from_bytes(&proof.to_bytes()).unwrap());
+
+        let mut modified = proof.clone();
+        modified.t_x += Scalar::ONE;
+        assert_ne!(proof, modified);
+
+        let mut modified = proof.clone();
+        modified.ipp_proof.b += Scalar::ONE;
+        assert_ne!(proof, modified);
+
+        // Equal proofs are deduplicated as map keys.
+        let mut seen = std::collections::HashSet::new();
+        assert!(seen.insert(proof.clone()));
+        assert!(!seen.insert(prove()));
+        assert!(!seen.insert(RangeProof::from_bytes(&proof.to_bytes()).unwrap()));
+        assert!(seen.insert(modified));
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn create_and_verify_every_bitsize() {
+        for n in 1..=64 {
+            singleparty_create_and_verify_helper(n, 2);
+        }
+    }
+
+    #[test]
+    fn padded_proof_is_bound_to_bitsize() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let mut transcript = Transcript::new(b"PaddedBitsizeTest");
+        let (proof, V) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 5000, &blinding, 13)
+                .unwrap();
+
+        let mut transcript = Transcript::new(b"PaddedBitsizeTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 13)
+            .is_ok());
+        for n in [12, 16] {
+            let mut transcript = Transcript::new(b"PaddedBitsizeTest");
+            assert!(proof
+                .verify_single(&bp_gens, &pc_gens, &mut transcript, &V, n)
+                .is_err());
+        }
+
+        // The padding bits cannot be used to prove a larger value.
+        let mut transcript = Transcript::new(b"PaddedBitsizeTest");
+        assert_eq!(
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 1 << 13, &blinding, 13)
+                .unwrap_err(),
+            ProofError::ValueOutOfRange
+        );
+        let mut transcript = Transcript::new(b"PaddedBitsizeTest");
+        let (proof, Vs) = prove_unchecked(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[Scalar::from(1u64 << 13)],
+            &[13],
+        );
+        let mut transcript = Transcript::new(b"PaddedBitsizeTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &Vs[0], 13)
+            .is_err());
+    }
+
+    #[test]
+    fn wrong_aggregation_split_is_rejected() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+        let mut transcript = Transcript::new(b"AggregationSplitTest");
+        let (proof, Vs) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[7, 11],
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"AggregationSplitTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, 32)
+            .is_ok());
+
+        // Same n*m = 64 as the proof, but a different (n, m) split,
+        // which the range proof domain separator binds separately.
+        let mut transcript = Transcript::new(b"AggregationSplitTest");
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs[..1], 64),
+            Err(ProofError::VerificationError)
+        );
+        let padded_Vs = [Vs[0], Vs[1], Vs[0], Vs[1]];
+        let mut transcript = Transcript::new(b"AggregationSplitTest");
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &padded_Vs, 16),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn create_and_verify_heterogeneous_bitsizes() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        let values = [u32::MAX as u64, u64::MAX, 200];
+        let bitsizes = [32, 64, 8];
+        let blindings: Vec<_> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+        let mut transcript = Transcript::new(b"HeterogeneousTest");
+        let (proof, Vs) = RangeProof::prove_multiple_heterogeneous(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            &bitsizes,
+        )
+        .unwrap();
+        // The proof has the size of a (64, 4) proof.
+        assert_eq!(proof.to_bytes().len(), RangeProof::serialized_size(64, 4));
+
+        let mut transcript = Transcript::new(b"HeterogeneousTest");
+        assert!(proof
+            .verify_multiple_heterogeneous(&bp_gens, &pc_gens, &mut transcript, &Vs, &bitsizes)
+            .is_ok());
+
+        // The proof is bound to each value's bitsize.
+        for bitsizes in [[64, 64, 8], [32, 64, 16], [64, 32, 8]] {
+            let mut transcript = Transcript::new(b"HeterogeneousTest");
+            assert!(proof
+                .verify_multiple_heterogeneous(&bp_gens, &pc_gens, &mut transcript, &Vs, &bitsizes)
+                .is_err());
+        }
+        let mut transcript = Transcript::new(b"HeterogeneousTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, 64)
+            .is_err());
+        let mut transcript = Transcript::new(b"HeterogeneousTest");
+        assert_eq!(
+            proof.verify_multiple_heterogeneous(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &Vs,
+                &bitsizes[..2]
+            ),
+            Err(ProofError::InvalidInputLength)
+        );
+    }
+
+    #[test]
+    fn heterogeneous_value_out_of_its_range_fails() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let mut rng = rand::thread_rng();
+
+        // 256 fits in the common padded bitsize, but not in 8 bits.
+        let values = [1 << 40, 256];
+        let bitsizes = [64, 8];
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+        let mut transcript = Transcript::new(b"HeterogeneousTest");
+        assert_eq!(
+            RangeProof::prove_multiple_heterogeneous(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &values,
+                &blindings,
+                &bitsizes,
+            )
+            .err(),
+            Some(ProofError::ValueOutOfRange)
+        );
+
+        // A proof made without the check does not verify.
+        let mut transcript = Transcript::new(b"HeterogeneousTest");
+        let scalars = [Scalar::from(values[0]), Scalar::from(values[1])];
+        let (proof, Vs) = prove_unchecked(&bp_gens, &pc_gens, &mut transcript, &scalars, &bitsizes);
+        let mut transcript = Transcript::new(b"HeterogeneousTest");
+        assert!(proof
+            .verify_multiple_heterogeneous(&bp_gens, &pc_gens, &mut transcript, &Vs, &bitsizes)
+            .is_err());
+
+        let mut transcript = Transcript::new(b"HeterogeneousTest");
+        assert_eq!(
+            RangeProof::prove_multiple_heterogeneous(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &values,
+                &blindings,
+                &[64],
+            )
+            .err(),
+            Some(ProofError::InvalidInputLength)
+        );
+    }
+
+    #[test]
+    fn prove_multiple_into_appends_encoding() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let mut rng = rand::thread_rng();
+
+        let values = [1u64, 2, 3];
+        let blindings: Vec<Scalar> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+
+        // The proof is appended after what the buffer already holds.
+        let mut buf = b"header".to_vec();
+        let mut transcript = Transcript::new(b"ProveIntoTest");
+        let commitments = RangeProof::prove_multiple_into(
+            &mut buf,
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            32,
+        )
+        .unwrap();
+        assert_eq!(&buf[..6], b"header");
+        assert_eq!(buf.len(), 6 + RangeProof::serialized_size(32, 3));
+
+        let proof = RangeProof::from_bytes(&buf[6..]).unwrap();
+        let mut transcript = Transcript::new(b"ProveIntoTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+
+        let mut transcript = Transcript::new(b"ProveIntoTest");
+        assert_eq!(
+            RangeProof::prove_multiple_into(
+                &mut buf,
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &values,
+                &blindings[..2],
+                32,
+            ),
+            Err(ProofError::WrongNumBlindingFactors)
+        );
+        assert_eq!(buf.len(), 6 + RangeProof::serialized_size(32, 3));
+    }
+
+    #[test]
+    fn serialized_size_matches_encoding() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 8);
+        let mut rng = rand::thread_rng();
+
+        for n in [1, 7, 8, 13, 16, 31, 32, 64] {
+            for m in [1, 2, 4, 8] {
+                let values = vec![0u64; m];
+                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+                let mut transcript = Transcript::new(b"SerializedSizeTest");
+                let (proof, _) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+
+                let bytes = proof.to_bytes();
+                assert_eq!(bytes.len(), RangeProof::serialized_size(n, m));
+                assert!(RangeProof::from_bytes(&bytes).is_ok());
+                assert_eq!(
+                    RangeProof::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+                    ProofError::FormatError
+                );
+                let mut longer = bytes.clone();
+                longer.push(0);
+                assert_eq!(
+                    RangeProof::from_bytes(&longer).unwrap_err(),
+                    ProofError::FormatError
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn is_aggregated_distinguishes_single_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        for n in [8, 13, 32, 64] {
+            for m in [1, 2, 3, 4] {
+                let values = vec![1u64; m];
+                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+                let mut transcript = Transcript::new(b"IsAggregatedTest");
+                let (proof, _) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+                assert_eq!(proof.is_aggregated(n), m > 1);
+            }
+        }
+
+        let mut transcript = Transcript::new(b"IsAggregatedTest");
+        let (proof, _) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1,
+            &Scalar::random(&mut rng),
+            32,
+        )
+        .unwrap();
+        // A single 32-bit proof has the size of two 16-bit values.
+        assert!(proof.is_aggregated(16));
+        assert!(!proof.is_aggregated(0));
+        assert!(!proof.is_aggregated(129));
+    }
+
+    #[test]
+    fn write_to_matches_to_bytes() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        for m in [1, 2, 4] {
+            let values = vec![1037578891u64; m];
+            let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+            let mut transcript = Transcript::new(b"WriteToTest");
+            let (proof, _) = RangeProof::prove_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &values,
+                &blindings,
+                64,
+            )
+            .unwrap();
+
+            // Writing appends to what is already in the buffer.
+            let mut buf = vec![0xaa; 3];
+            proof.write_to(&mut buf).unwrap();
+            assert_eq!(&buf[..3], &[0xaa; 3]);
+            assert_eq!(&buf[3..], &proof.to_bytes()[..]);
+
+            let mut exact = vec![0u8; proof.encoded_size()];
+            proof.write_to(&mut &mut exact[..]).unwrap();
+            assert_eq!(exact, proof.to_bytes());
+
+            let mut short = vec![0u8; proof.encoded_size() - 1];
+            assert_eq!(
+                proof.write_to(&mut &mut short[..]).unwrap_err().kind(),
+                std::io::ErrorKind::WriteZero
+            );
+        }
+    }
+
+    #[test]
+    fn proof_rebuilt_from_components_verifies() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+
+        let mut transcript = Transcript::new(b"ComponentsTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[5, 1 << 30],
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        let rebuilt = RangeProof::new_unchecked(
+            proof.A(),
+            proof.S(),
+            proof.T_1(),
+            proof.T_2(),
+            proof.t_x(),
+            proof.t_x_blinding(),
+            proof.e_blinding(),
+            proof.ipp_proof().clone(),
+        );
+        assert_eq!(rebuilt, proof);
+        assert_eq!(rebuilt.ipp_proof().n_rounds(), 6);
+        assert_eq!(rebuilt.ipp_proof().L_vec().len(), 6);
+        assert_eq!(rebuilt.ipp_proof().R_vec().len(), 6);
+
+        // The components sit at fixed offsets of the encoding.
+        let bytes = proof.to_bytes();
+        assert_eq!(&bytes[..32], proof.A().as_bytes());
+        assert_eq!(&bytes[3 * 32..4 * 32], proof.T_2().as_bytes());
+        assert_eq!(&bytes[4 * 32..5 * 32], proof.t_x().as_bytes());
+        assert_eq!(&bytes[7 * 32..], &proof.ipp_proof().to_bytes()[..]);
+
+        let mut transcript = Transcript::new(b"ComponentsTest");
+        assert!(rebuilt
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+
+        // Swapping two components breaks the proof.
+        let swapped = RangeProof::new_unchecked(
+            proof.T_1(),
+            proof.S(),
+            proof.A(),
+            proof.T_2(),
+            proof.t_x(),
+            proof.t_x_blinding(),
+            proof.e_blinding(),
+            proof.ipp_proof().clone(),
+        );
+        let mut transcript = Transcript::new(b"ComponentsTest");
+        assert_eq!(
+            swapped.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn batch_progress_is_reported_per_proof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut proofs: Vec<(RangeProof, CompressedRistretto)> = (0..5u64)
+            .map(|v| {
+                let mut transcript = Transcript::new(b"ProgressTest");
+                RangeProof::prove_single(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    v,
+                    &Scalar::random(&mut rng),
+                    16,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let verify = |proofs: &[(RangeProof, CompressedRistretto)]| {
+            let mut transcripts = vec![Transcript::new(b"ProgressTest"); proofs.len()];
+            let views: Vec<_> = proofs
+                .iter()
+                .zip(transcripts.iter_mut())
+                .map(|((proof, V), t)| proof.verification_view(t, core::slice::from_ref(V), 16))
+                .collect();
+            let mut calls = Vec::new();
+            let result = RangeProof::verify_batch_with_progress(
+                views,
+                &bp_gens,
+                &pc_gens,
+                |processed, total| calls.push((processed, total)),
+            );
+            (result, calls)
+        };
+
+        let (result, calls) = verify(&proofs);
+        assert_eq!(result, Ok(()));
+        assert_eq!(calls, [(1, 5), (2, 5), (3, 5), (4, 5), (5, 5)]);
+
+        // An invalid proof is only detected by the final check, after
+        // every proof has been reported.
+        let V = proofs[0].1;
+        proofs[1].1 = V;
+        let (result, calls) = verify(&proofs);
+        assert_eq!(result, Err(ProofError::VerificationError));
+        assert_eq!(calls.len(), 5);
+
+        let (result, calls) = verify(&[]);
+        assert_eq!(result, Ok(()));
+        assert!(calls.is_empty());
+    }
+
+    #[test]
+    fn commitment_forms_verify_alike() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+
+        let proofs: Vec<(RangeProof, Vec<CompressedRistretto>)> = (0..3u64)
+            .map(|i| {
+                let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+                let mut transcript = Transcript::new(b"CommitmentFormsTest");
+                RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &[i, 1000 + i],
+                    &blindings,
+                    32,
+                )
+                .unwrap()
+            })
+            .collect();
+        let mut swapped = proofs.clone();
+        swapped[2].1.swap(0, 1);
+
+        fn verify<V: ValueCommitment>(
+            proofs: &[(RangeProof, Vec<CompressedRistretto>)],
+            commitments: &[Vec<V>],
+            bp_gens: &BulletproofGens,
+            pc_gens: &PedersenGens,
+        ) -> Result<(), ProofError> {
+            let mut transcripts = vec![Transcript::new(b"CommitmentFormsTest"); proofs.len()];
+            let batch = proofs
+                .iter()
+                .zip(commitments)
+                .zip(transcripts.iter_mut())
+                .map(|(((proof, _), Vs), t)| proof.verification_view(t, Vs, 32));
+            RangeProof::verify_batch(batch, bp_gens, pc_gens)
+        }
+
+        for (proofs, expected) in [
+            (&proofs, Ok(())),
+            (&swapped, Err(ProofError::VerificationError)),
+        ] {
+            let compressed: Vec<Vec<CompressedRistretto>> =
+                proofs.iter().map(|(_, Vs)| Vs.clone()).collect();
+            let references: Vec<Vec<&CompressedRistretto>> =
+                proofs.iter().map(|(_, Vs)| Vs.iter().collect()).collect();
+            let points: Vec<Vec<RistrettoPoint>> = compressed
+                .iter()
+                .map(|Vs| Vs.iter().map(|V| V.decompress().unwrap()).collect())
+                .collect();
+            let point_references: Vec<Vec<&RistrettoPoint>> =
+                points.iter().map(|Vs| Vs.iter().collect()).collect();
+            let decompressed: Vec<Vec<DecompressedCommitment>> = compressed
+                .iter()
+                .map(|Vs| {
+                    Vs.iter()
+                        .map(|V| DecompressedCommitment::from_compressed(V).unwrap())
+                        .collect()
+                })
+                .collect();
+
+            assert_eq!(verify(proofs, &compressed, &bp_gens, &pc_gens), expected);
+            assert_eq!(verify(proofs, &references, &bp_gens, &pc_gens), expected);
+            assert_eq!(verify(proofs, &points, &bp_gens, &pc_gens), expected);
+            assert_eq!(
+                verify(proofs, &point_references, &bp_gens, &pc_gens),
+                expected
+            );
+            assert_eq!(verify(proofs, &decompressed, &bp_gens, &pc_gens), expected);
+        }
+
+        // A single proof verifies over a slice of references.
+        let (proof, Vs) = &proofs[0];
+        let references: Vec<&CompressedRistretto> = Vs.iter().collect();
+        let mut transcript = Transcript::new(b"CommitmentFormsTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &references, 32)
+            .is_ok());
+
+        let point = Vs[0].decompress().unwrap();
+        assert_eq!(
+            DecompressedCommitment::from(point),
+            DecompressedCommitment::from_compressed(&Vs[0]).unwrap()
+        );
+        assert_eq!(
+            DecompressedCommitment::from_compressed(&CompressedRistretto([0xff; 32])),
+            None
+        );
+    }
+
+    #[test]
+    fn from_bytes_rejects_malformed_lengths() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        let blindings: Vec<Scalar> = (0..4).map(|_| Scalar::random(&mut rng)).collect();
+        let mut transcript = Transcript::new(b"MalformedLengthTest");
+        let (proof, _) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2, 3, 4],
+            &blindings,
+            64,
+        )
+        .unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), proof.encoded_size());
+        assert_eq!(bytes.len(), RangeProof::serialized_size(64, 4));
+
+        // Dropping or adding whole elements breaks the L, R pairing,
+        // or leaves too few elements.
+        for elements in [0, 1, 6, 7, 8, 10, 24, 26] {
+            let mut malformed = bytes.clone();
+            malformed.resize(elements * 32, 0);
+            assert_eq!(
+                RangeProof::from_bytes(&malformed),
+                Err(ProofError::FormatError),
+                "{} elements",
+                elements
+            );
+        }
+
+        // Dimensions are bounded by the caller, or by the largest
+        // supported proof.
+        assert!(RangeProof::from_bytes_with_max_size(&bytes, 64, 4).is_ok());
+        assert!(RangeProof::from_bytes_with_max_size(&bytes, 32, 8).is_ok());
+        assert_eq!(
+            RangeProof::from_bytes_with_max_size(&bytes, 64, 2),
+            Err(ProofError::FormatError)
+        );
+        let max_m = BulletproofGens::MAX_PARTY_CAPACITY;
+        let largest = vec![0u8; RangeProof::serialized_size(128, max_m)];
+        assert!(RangeProof::from_bytes(&largest).is_ok());
+        let too_large = vec![0u8; RangeProof::serialized_size(128, 2 * max_m)];
+        assert_eq!(
+            RangeProof::from_bytes(&too_large),
+            Err(ProofError::FormatError)
+        );
+    }
+
+    #[test]
+    fn serialized_size_does_not_overflow() {
+        // The product of the padded dimensions does not fit in a usize,
+        // but the sum of their logarithms does.
+        assert_eq!(
+            RangeProof::serialized_size(usize::MAX, usize::MAX),
+            7 * 32 + (2 * 2 * usize::BITS as usize + 2) * 32
+        );
+        assert_eq!(
+            RangeProof::serialized_size(1 << 20, (1 << 20) + 1),
+            7 * 32 + (2 * 41 + 2) * 32
+        );
+
+        let mut transcript = Transcript::new(b"SerializedSizeTest");
+        let (proof, _) = RangeProof::prove_single(
+            &BulletproofGens::new(64, 1),
+            &PedersenGens::default(),
+            &mut transcript,
+            5,
+            &Scalar::ONE,
+            64,
+        )
+        .unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(
+            RangeProof::from_bytes_with_max_size(&bytes, usize::MAX, usize::MAX).unwrap(),
+            proof
+        );
+        assert_eq!(
+            RangeProof::from_bytes_with_max_size(&bytes, 0, 0),
+            Err(ProofError::FormatError)
+        );
+    }
+
+    #[test]
+    fn from_bytes_strict_rejects_invalid_points() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let mut transcript = Transcript::new(b"StrictDecodingTest");
+        let (proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 17, &blinding, 32)
+                .unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(RangeProof::from_bytes_strict(&bytes).unwrap(), proof);
+
+        // A, S, T_1, T_2, then the IPP's L and R points after the
+        // three scalars, followed by its two scalars.
+        let num_elements = bytes.len() / 32;
+        for i in (0..4).chain(7..num_elements - 2) {
+            let mut bad_point = bytes.clone();
+            bad_point[i * 32..(i + 1) * 32].copy_from_slice(&[0xff; 32]);
+            assert!(RangeProof::from_bytes(&bad_point).is_ok());
+            assert_eq!(
+                RangeProof::from_bytes_strict(&bad_point).unwrap_err(),
+                ProofError::FormatError
+            );
+        }
+    }
+
+    #[test]
+    fn accessors_match_encoding() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+        let mut transcript = Transcript::new(b"AccessorsTest");
+        let (proof, _) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[3, 5],
+            &blindings,
+            32,
+        )
+        .unwrap();
+        let bytes = proof.to_bytes();
+        let element = |i: usize| &bytes[i * 32..(i + 1) * 32];
+
+        assert_eq!(proof.A().as_bytes(), element(0));
+        assert_eq!(proof.S().as_bytes(), element(1));
+        assert_eq!(proof.T_1().as_bytes(), element(2));
+        assert_eq!(proof.T_2().as_bytes(), element(3));
+        assert_eq!(proof.t_x().as_bytes(), element(4));
+        assert_eq!(proof.t_x_blinding().as_bytes(), element(5));
+        assert_eq!(proof.e_blinding().as_bytes(), element(6));
+
+        let ipp_proof = proof.ipp_proof();
+        assert_eq!(ipp_proof.to_bytes(), &bytes[7 * 32..]);
+        // lg(32 * 2) rounds.
+        assert_eq!(ipp_proof.L_vec().len(), 6);
+        assert_eq!(ipp_proof.R_vec().len(), 6);
+        for (j, (L, R)) in ipp_proof.L_vec().iter().zip(ipp_proof.R_vec()).enumerate() {
+            assert_eq!(L.as_bytes(), element(7 + 2 * j));
+            assert_eq!(R.as_bytes(), element(8 + 2 * j));
+        }
+        let num_elements = bytes.len() / 32;
+        assert_eq!(ipp_proof.a().as_bytes(), element(num_elements - 2));
+        assert_eq!(ipp_proof.b().as_bytes(), element(num_elements - 1));
+    }
+
+    #[test]
+    fn verify_multiple_compressed_matches_generic() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        let blindings: Vec<_> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+        let mut transcript = Transcript::new(b"CompressedVerifyTest");
+        let (proof, Vs) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 1 << 40, u64::MAX],
+            &blindings,
+            64,
+        )
+        .unwrap();
+
+        let verify_both = |Vs: &[CompressedRistretto], n: usize| {
+            let mut transcript = Transcript::new(b"CompressedVerifyTest");
+            let generic = proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, Vs, n);
+            let mut transcript = Transcript::new(b"CompressedVerifyTest");
+            let compressed =
+                proof.verify_multiple_compressed(&bp_gens, &pc_gens, &mut transcript, Vs, n);
+            assert_eq!(generic, compressed);
+            compressed
+        };
+
+        assert_eq!(verify_both(&Vs, 64), Ok(()));
+        assert_eq!(verify_both(&Vs, 32), Err(ProofError::VerificationError));
+        assert_eq!(
+            verify_both(&[Vs[1], Vs[0], Vs[2]], 64),
+            Err(ProofError::VerificationError)
+        );
+        let mut bad_Vs = Vs.clone();
+        bad_Vs[1] = CompressedRistretto([0xff; 32]);
+        assert_eq!(
+            verify_both(&bad_Vs, 64),
+            Err(ProofError::PointDecompressionError {
+                proof_index: 0,
+                component: "V"
+            })
+        );
+    }
+
+    #[test]
+    fn verify_multiple_and_sum_returns_the_sum() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        let values = [3u64, 1 << 40, 17];
+        let blindings: Vec<_> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+        let mut transcript = Transcript::new(b"SumVerifyTest");
+        let (proof, Vs) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            64,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"SumVerifyTest");
+        let sum = proof
+            .verify_multiple_and_sum(&bp_gens, &pc_gens, &mut transcript, &Vs, 64)
+            .unwrap();
+        let value_sum: Scalar = values.iter().map(|&v| Scalar::from(v)).sum();
+        let blinding_sum: Scalar = blindings.iter().sum();
+        assert_eq!(sum, pc_gens.commit(value_sum, blinding_sum));
+
+        // The transcript ends in the same state as with verify_multiple.
+        let mut expected = Transcript::new(b"SumVerifyTest");
+        proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut expected, &Vs, 64)
+            .unwrap();
+        let mut expected_bytes = [0u8; 32];
+        expected.challenge_bytes(b"after", &mut expected_bytes);
+        let mut bytes = [0u8; 32];
+        transcript.challenge_bytes(b"after", &mut bytes);
+        assert_eq!(bytes, expected_bytes);
+
+        let mut transcript = Transcript::new(b"SumVerifyTest");
+        assert_eq!(
+            proof.verify_multiple_and_sum(&bp_gens, &pc_gens, &mut transcript, &Vs, 32),
+            Err(ProofError::VerificationError)
+        );
+        let mut bad_Vs = Vs.clone();
+        bad_Vs[1] = CompressedRistretto([0xff; 32]);
+        let mut transcript = Transcript::new(b"SumVerifyTest");
+        assert_eq!(
+            proof.verify_multiple_and_sum(&bp_gens, &pc_gens, &mut transcript, &bad_Vs, 64),
+            Err(ProofError::PointDecompressionError {
+                proof_index: 0,
+                component: "V"
+            })
+        );
+    }
+
+    #[test]
+    fn implied_aggregate_commits_to_the_value_sum() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let mut rng = rand::thread_rng();
+
+        let values = [10u64, 20, 30, 40];
+        let blindings: Vec<_> = (0..4).map(|_| Scalar::random(&mut rng)).collect();
+        let mut transcript = Transcript::new(b"AggregateTest");
+        let (_, Vs) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        let aggregate = RangeProof::implied_aggregate(&Vs).unwrap();
+        let manual: RistrettoPoint = Vs.iter().map(|V| V.decompress().unwrap()).sum();
+        assert_eq!(aggregate, manual);
+        let blinding_sum: Scalar = blindings.iter().sum();
+        assert_eq!(
+            aggregate,
+            pc_gens.commit(Scalar::from(100u64), blinding_sum)
+        );
+
+        let mut bad_Vs = Vs.clone();
+        bad_Vs[2] = CompressedRistretto([0xff; 32]);
+        assert_eq!(RangeProof::implied_aggregate(&bad_Vs), None);
+        assert_eq!(
+            RangeProof::implied_aggregate(&[] as &[CompressedRistretto]),
+            Some(RistrettoPoint::identity())
+        );
+    }
+
+    #[test]
+    fn v0_and_v1_proofs_do_not_cross_verify() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let mut rng = rand::thread_rng();
+
+        let values = [7u64, 1 << 20, 3];
+        let blindings: Vec<_> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"VersionTest");
+        let (proof_v1, Vs_v1) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            32,
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"VersionTest");
+        let (proof_v0, Vs_v0) = RangeProof::prove_multiple_v0(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            32,
+        )
+        .unwrap();
+        assert_eq!(Vs_v0, Vs_v1);
+        let Vs = Vs_v1;
+
+        let verify = |proof: &RangeProof, v0: bool| {
+            let mut transcript = Transcript::new(b"VersionTest");
+            if v0 {
+                proof.verify_multiple_v0(&bp_gens, &pc_gens, &mut transcript, &Vs, 32)
+            } else {
+                proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, 32)
+            }
+        };
+
+        assert_eq!(verify(&proof_v1, false), Ok(()));
+        assert_eq!(verify(&proof_v0, true), Ok(()));
+        assert_eq!(verify(&proof_v1, true), Err(ProofError::VerificationError));
+        assert_eq!(verify(&proof_v0, false), Err(ProofError::VerificationError));
+
+        // Both versions can be mixed in the same batch.
+        let mut transcripts = vec![Transcript::new(b"VersionTest"); 2];
+        let (t0, t1) = transcripts.split_at_mut(1);
+        let batch = vec![
+            proof_v0.verification_view_v0(&mut t0[0], &Vs, 32),
+            proof_v1.verification_view(&mut t1[0], &Vs, 32),
+        ];
+        assert!(RangeProof::verify_batch(batch, &bp_gens, &pc_gens).is_ok());
+    }
+
+    fn singleparty_create_and_verify_batch_helper(nm: &[(usize, usize)]) {
+        let max_bitsize = 64;
+        let max_parties = 8;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(max_bitsize, max_parties);
+
+        // Provers
+        let proofs: Vec<_> = nm
+            .iter()
+            .map(|&(n, m)| {
+                use self::rand::Rng;
+                let mut rng = rand::thread_rng();
+
+                // Truncating keeps the maximum in range for n > 64.
+                let (min, max) = (0u64, (u128::MAX >> (128 - n)) as u64);
+                let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
+                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+                let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+                let (proof, value_commitments) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+
+                (bincode::serialize(&proof).unwrap(), value_commitments, n)
+            })
+            .collect();
+
+        // Verifier
+        {
+            let mut rng = rand::thread_rng();
+
+            let proofs: Vec<(RangeProof, _, _)> = proofs
+                .into_iter()
+                .map(|(proof_bytes, commitments, n)| {
+                    (bincode::deserialize(&proof_bytes).unwrap(), commitments, n)
+                })
+                .collect();
+
+            let mut transcripts = proofs
+                .iter()
+                .map(|_| Transcript::new(b"AggregatedRangeProofTest"))
+                .collect::<Vec<_>>();
+
+            assert!(RangeProof::verify_batch_with_rng(
+                proofs
+                    .iter()
+                    .zip(&mut transcripts)
+                    .map(|((proof, commitments, n), transcript)| {
+                        proof.verification_view(transcript, commitments, *n)
+                    }),
+                &bp_gens,
+                &pc_gens,
+                &mut rng
+            )
+            .is_ok());
+        }
+    }
+
+    #[test]
+    fn create_and_verify_batch_64_2() {
+        singleparty_create_and_verify_batch_helper(&[(64, 2)]);
+    }
+
+    #[test]
+    fn create_and_verify_batch_64_2x2() {
+        singleparty_create_and_verify_batch_helper(&[(64, 2), (64, 2)]);
+    }
+
+    #[test]
+    fn create_and_verify_batch_32_1_64_4_64_2_64_1() {
+        singleparty_create_and_verify_batch_helper(&[(32, 1), (64, 4), (64, 2), (64, 1)]);
+    }
+
+    #[test]
+    fn create_and_verify_batch_8_1_64_8_32_2() {
+        singleparty_create_and_verify_batch_helper(&[(8, 1), (64, 8), (32, 2)]);
+    }
+
+    #[test]
+    fn create_and_verify_batch_non_power_of_two_aggregation() {
+        singleparty_create_and_verify_batch_helper(&[
+            (32, 3),
+            (64, 5),
+            (32, 7),
+            (64, 3),
+            (32, 5),
+            (64, 7),
+        ]);
+    }
+
+    #[test]
+    fn batch_with_growing_dimensions_rejects_corrupted_proof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 8);
+        let mut rng = rand::thread_rng();
+
+        let mut proofs: Vec<_> = [(8, 1), (64, 8), (32, 2)]
+            .iter()
+            .map(|&(n, m)| {
+                let values = vec![1u64; m];
+                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+                let mut transcript = Transcript::new(b"GrowingBatchTest");
+                let (proof, commitments) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+                (proof, commitments, n)
+            })
+            .collect();
+
+        // Corrupt the first proof, whose generator scalars are moved
+        // when the second proof grows the batch.
+        proofs[0].0.ipp_proof.a += Scalar::ONE;
+
+        let mut transcripts = vec![Transcript::new(b"GrowingBatchTest"); proofs.len()];
+        assert_eq!(
+            RangeProof::verify_batch(
+                proofs
+                    .iter()
+                    .zip(&mut transcripts)
+                    .map(|((proof, commitments, n), transcript)| {
+                        proof.verification_view(transcript, commitments, *n)
+                    }),
+                &bp_gens,
+                &pc_gens,
+            ),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_batch_verification() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 8);
+        let mut rng = rand::thread_rng();
+
+        let mut proofs: Vec<_> = [(8, 1), (64, 8), (32, 2), (16, 4)]
+            .iter()
+            .map(|&(n, m)| {
+                let values = vec![3u64; m];
+                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+                let mut transcript = Transcript::new(b"ParallelBatchTest");
+                let (proof, commitments) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+                (proof, commitments, n)
+            })
+            .collect();
+
+        let verify = |proofs: &[(RangeProof, Vec<CompressedRistretto>, usize)]| {
+            let mut transcripts = vec![Transcript::new(b"ParallelBatchTest"); proofs.len()];
+            RangeProof::verify_batch_par(
+                proofs
+                    .iter()
+                    .zip(&mut transcripts)
+                    .map(|((proof, commitments, n), transcript)| {
+                        proof.verification_view(transcript, commitments, *n)
+                    }),
+                &bp_gens,
+                &pc_gens,
+            )
+        };
+
+        assert!(verify(&proofs).is_ok());
+
+        proofs[2].0.ipp_proof.b += Scalar::ONE;
+        assert_eq!(verify(&proofs), Err(ProofError::VerificationError));
+    }
+
+    #[test]
+    fn batch_verifier_interleaved_bitsizes() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        let proofs: Vec<_> = [(8, 1), (64, 2), (32, 4), (8, 2), (64, 1)]
+            .iter()
+            .map(|&(n, m)| {
+                let values: Vec<u64> = (0..m).map(|i| i as u64).collect();
+                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+                let mut transcript = Transcript::new(b"BatchVerifierTest");
+                let (proof, commitments) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+                (proof, commitments, n)
+            })
+            .collect();
+
+        let mut verifier = BatchVerifier::new(&bp_gens, &pc_gens);
+        for (proof, commitments, n) in proofs.iter() {
+            // A failed add leaves the verifier usable.
+            let mut transcript = Transcript::new(b"BatchVerifierTest");
+            assert_eq!(
+                verifier.add(
+                    proof.verification_view(&mut transcript, commitments, 0),
+                    &mut rng
+                ),
+                Err(ProofError::InvalidBitsize)
+            );
+
+            let mut transcript = Transcript::new(b"BatchVerifierTest");
+            verifier
+                .add(
+                    proof.verification_view(&mut transcript, commitments, *n),
+                    &mut rng,
+                )
+                .unwrap();
+        }
+        assert!(verifier.finalize().is_ok());
+
+        // A proof verified under the wrong transcript fails the batch.
+        let mut verifier = BatchVerifier::new(&bp_gens, &pc_gens);
+        for (i, (proof, commitments, n)) in proofs.iter().enumerate() {
+            let label: &'static [u8] = if i == 2 {
+                b"Wrong"
+            } else {
+                b"BatchVerifierTest"
+            };
+            let mut transcript = Transcript::new(label);
+            verifier
+                .add(
+                    proof.verification_view(&mut transcript, commitments, *n),
+                    &mut rng,
+                )
+                .unwrap();
+        }
+        assert!(verifier.finalize().is_err());
+    }
+
+    #[test]
+    fn batch_collector_skips_rejected_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+        let mut transcript = Transcript::new(b"BatchCollectorTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[7, 8],
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        let invalid_point = CompressedRistretto([0xff; 32]);
+        let mut bad_L = proof.clone();
+        bad_L.ipp_proof.L_vec[1] = invalid_point;
+        let bad_commitments = [commitments[0], invalid_point];
+
+        let mut collector = BatchCollector::new(&bp_gens, &pc_gens);
+        let mut transcript = Transcript::new(b"BatchCollectorTest");
+        collector
+            .add_proof(
+                proof.verification_view(&mut transcript, &commitments, 32),
+                &mut rng,
+            )
+            .unwrap();
+
+        let mut transcript = Transcript::new(b"BatchCollectorTest");
+        assert_eq!(
+            collector.add_proof(
+                bad_L.verification_view(&mut transcript, &commitments, 32),
+                &mut rng
+            ),
+            Err(ProofError::PointDecompressionError {
+                proof_index: 1,
+                component: "L"
+            })
+        );
+        let mut transcript = Transcript::new(b"BatchCollectorTest");
+        assert_eq!(
+            collector.add_proof(
+                proof.verification_view(&mut transcript, &bad_commitments, 32),
+                &mut rng
+            ),
+            Err(ProofError::PointDecompressionError {
+                proof_index: 2,
+                component: "V"
+            })
+        );
+        let mut transcript = Transcript::new(b"BatchCollectorTest");
+        assert_eq!(
+            collector.add_proof(
+                proof.verification_view(&mut transcript, &commitments, 129),
+                &mut rng
+            ),
+            Err(ProofError::InvalidBitsize)
+        );
+
+        assert!(collector.verify().is_ok());
+    }
+
+    #[test]
+    fn reblinding_requires_a_new_proof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+        let (old_blinding, new_blinding) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+
+        let mut transcript = Transcript::new(b"ReblindTest");
+        let (proof, V) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 42, &old_blinding, 64)
+                .unwrap();
+
+        // Shifting the commitment by the blinding difference does not
+        // carry the proof over.
+        let delta = new_blinding - old_blinding;
+        let shifted = (V.decompress().unwrap() + delta * pc_gens.B_blinding).compress();
+        assert_eq!(
+            shifted,
+            pc_gens.commit(Scalar::from(42u64), new_blinding).compress()
+        );
+        let mut transcript = Transcript::new(b"ReblindTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &shifted, 64)
+            .is_err());
+
+        let mut transcript = Transcript::new(b"ReblindTest");
+        let (new_proof, new_V) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 42, &new_blinding, 64)
+                .unwrap();
+        assert_eq!(new_V, shifted);
+        assert_ne!(new_proof.A, proof.A);
+        for (proof, V) in [(&proof, &V), (&new_proof, &new_V)] {
+            let mut transcript = Transcript::new(b"ReblindTest");
+            assert_eq!(
+                proof.verify_single(&bp_gens, &pc_gens, &mut transcript, V, 64),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn commitments_disjoint_finds_reused_commitments() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+
+        let proofs: Vec<(RangeProof, Vec<CompressedRistretto>)> = (0..3u64)
+            .map(|v| {
+                let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+                let mut transcript = Transcript::new(b"DisjointTest");
+                RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &[v, v + 1],
+                    &blindings,
+                    32,
+                )
+                .unwrap()
+            })
+            .collect();
+        let check = |commitments: &[&[CompressedRistretto]]| {
+            let mut transcripts = vec![Transcript::new(b"DisjointTest"); commitments.len()];
+            let views: Vec<_> = proofs
+                .iter()
+                .zip(commitments.iter())
+                .zip(transcripts.iter_mut())
+                .map(|(((proof, _), Vs), transcript)| proof.verification_view(transcript, Vs, 32))
+                .collect();
+            RangeProof::commitments_disjoint(&views)
+        };
+
+        assert_eq!(
+            check(&[&proofs[0].1[..], &proofs[1].1[..], &proofs[2].1[..]]),
+            Ok(())
+        );
+        // Repeating a commitment within a proof is allowed.
+        let repeated = [proofs[1].1[0], proofs[1].1[0]];
+        assert_eq!(
+            check(&[&proofs[0].1[..], &repeated[..], &proofs[2].1[..]]),
+            Ok(())
+        );
+
+        let overlapping = [proofs[2].1[0], proofs[0].1[1]];
+        assert_eq!(
+            check(&[&proofs[0].1[..], &proofs[1].1[..], &overlapping[..]]),
+            Err(ProofError::DuplicateAcrossProofs {
+                proof_a: 0,
+                proof_b: 2,
+                commitment_index: 1
+            })
+        );
+    }
+
+    #[test]
+    fn batch_errors_identify_the_proof_and_point() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+
+        let proofs: Vec<(RangeProof, CompressedRistretto)> = (0..3u64)
+            .map(|v| {
+                let blinding = Scalar::random(&mut rng);
+                let mut transcript = Transcript::new(b"BatchErrorTest");
+                RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, v, &blinding, 32)
+                    .unwrap()
+            })
+            .collect();
+        let verify = |proofs: &[(RangeProof, CompressedRistretto)]| {
+            let mut transcripts = vec![Transcript::new(b"BatchErrorTest"); proofs.len()];
+            RangeProof::verify_batch(
+                proofs
+                    .iter()
+                    .zip(transcripts.iter_mut())
+                    .map(|((proof, V), transcript)| {
+                        proof.verification_view(transcript, core::slice::from_ref(V), 32)
+                    }),
+                &bp_gens,
+                &pc_gens,
+            )
+        };
+        assert_eq!(verify(&proofs), Ok(()));
+
+        let mut bad_A = proofs.clone();
+        bad_A[1].0.A = CompressedRistretto([0xff; 32]);
+        let error = verify(&bad_A).unwrap_err();
+        assert_eq!(
+            error,
+            ProofError::PointDecompressionError {
+                proof_index: 1,
+                component: "A"
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Point A of proof 1 could not be decompressed."
+        );
+
+        let mut bad_V = proofs.clone();
+        bad_V[2].1 = CompressedRistretto([0xff; 32]);
+        assert_eq!(
+            verify(&bad_V),
+            Err(ProofError::PointDecompressionError {
+                proof_index: 2,
+                component: "V"
+            })
         );
 
-        self.dynamic_points.extend(
-            iter::once(view.proof.A.decompress())
-                .chain(iter::once(view.proof.S.decompress()))
-                .chain(iter::once(view.proof.T_1.decompress()))
-                .chain(iter::once(view.proof.T_2.decompress()))
-                .chain(view.proof.ipp_proof.L_vec.iter().map(|L| L.decompress()))
-                .chain(view.proof.ipp_proof.R_vec.iter().map(|R| R.decompress()))
-                .chain(view.value_commitments.iter().map(|V| V.decompress())),
+        let mut identity_T_1 = proofs.clone();
+        identity_T_1[0].0.T_1 = CompressedRistretto::identity();
+        assert_eq!(
+            verify(&identity_T_1),
+            Err(ProofError::TranscriptPointValidation {
+                proof_index: 0,
+                label: "T_1"
+            })
+        );
+        let mut identity_R = proofs.clone();
+        identity_R[1].0.ipp_proof.R_vec[2] = CompressedRistretto::identity();
+        assert_eq!(
+            verify(&identity_R),
+            Err(ProofError::TranscriptPointValidation {
+                proof_index: 1,
+                label: "R"
+            })
         );
 
-        self.pedersen_B_blinding_scalar +=
-            (-view.proof.e_blinding - c * view.proof.t_x_blinding) * batch_factor;
-        self.pedersen_B_scalar += basepoint_scalar * batch_factor;
-
-        // Support (m,n) that are less than the bp_gens capacity.
-
-        self.party_capacity = self.party_capacity.max(m);
-        self.gens_capacity = self.gens_capacity.max(view.n);
+        // An invalid proof with valid points still fails the equation.
+        let mut swapped = proofs.clone();
+        swapped[0].1 = proofs[1].1;
+        assert_eq!(verify(&swapped), Err(ProofError::VerificationError));
+    }
 
-        self.g_scalars.resize_with(self.party_capacity, || vec![]);
-        for v in &mut self.g_scalars {
-            v.resize(self.gens_capacity, Scalar::ZERO);
-        }
-        self.h_scalars.resize_with(self.party_capacity, || vec![]);
-        for v in &mut self.h_scalars {
-            v.resize(self.gens_capacity, Scalar::ZERO);
-        }
+    #[test]
+    fn batch_collector_across_channel() {
+        use std::sync::mpsc;
+        use std::thread;
 
-        for cur_m in 0..m {
-            for cur_n in 0..view.n {
-                self.g_scalars[cur_m][cur_n] += g.next().unwrap() * batch_factor;
-                self.h_scalars[cur_m][cur_n] += h.next().unwrap() * batch_factor;
-            }
-        }
+        let (n, m) = (32, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
 
-        Ok(())
-    }
+        // The proofs arrive one at a time from another thread, while
+        // the collector is held in between.
+        let (sender, receiver) = mpsc::channel();
+        let producer = {
+            let (pc_gens, bp_gens) = (pc_gens, bp_gens.clone());
+            thread::spawn(move || {
+                let mut rng = rand::thread_rng();
+                for v in 0..4u64 {
+                    let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+                    let mut transcript = Transcript::new(b"BatchCollectorChannelTest");
+                    let proof = RangeProof::prove_multiple(
+                        &bp_gens,
+                        &pc_gens,
+                        &mut transcript,
+                        &[v, v + 1],
+                        &blindings,
+                        n,
+                    )
+                    .unwrap();
+                    sender.send(proof).unwrap();
+                }
+            })
+        };
 
-    fn verify(self) -> Result<(), ProofError> {
-        let mega_check = RistrettoPoint::optional_multiscalar_mul(
-            self.dynamic_scalars
-                .into_iter()
-                .chain(util::AssertSizeHint::new(
-                    self.g_scalars.into_iter().flatten(),
-                    self.gens_capacity * self.party_capacity,
-                ))
-                .chain(util::AssertSizeHint::new(
-                    self.h_scalars.into_iter().flatten(),
-                    self.gens_capacity * self.party_capacity,
-                ))
-                .chain(iter::once(self.pedersen_B_blinding_scalar))
-                .chain(iter::once(self.pedersen_B_scalar)),
-            self.dynamic_points
-                .into_iter()
-                .chain(
-                    self.bp_gens
-                        .G(self.gens_capacity, self.party_capacity)
-                        .copied()
-                        .map(Some),
-                )
-                .chain(
-                    self.bp_gens
-                        .H(self.gens_capacity, self.party_capacity)
-                        .copied()
-                        .map(Some),
+        let mut rng = rand::thread_rng();
+        let mut collector = BatchCollector::with_capacity(&bp_gens, &pc_gens, 4, n, m);
+        let capacity = collector.dynamic_points.capacity();
+        for (proof, commitments) in receiver {
+            let mut transcript = Transcript::new(b"BatchCollectorChannelTest");
+            collector
+                .add_proof(
+                    proof.verification_view(&mut transcript, &commitments, n),
+                    &mut rng,
                 )
-                .chain(iter::once(Some(self.pc_gens.B_blinding)))
-                .chain(iter::once(Some(self.pc_gens.B))),
-        )
-        .ok_or_else(|| ProofError::VerificationError)?;
-
-        use group::Group;
-        if mega_check.is_identity().into() {
-            Ok(())
-        } else {
-            Err(ProofError::VerificationError)
+                .unwrap();
         }
-    }
-}
-
-/// Compute
-/// \\[
-/// \delta(y,z) = (z - z^{2}) \langle \mathbf{1}, {\mathbf{y}}^{n \cdot m} \rangle - \sum_{j=0}^{m-1} z^{j+3} \cdot \langle \mathbf{1}, {\mathbf{2}}^{n \cdot m} \rangle
-/// \\]
-fn delta(n: usize, m: usize, y: &Scalar, z: &Scalar) -> Scalar {
-    let sum_y = util::sum_of_powers(y, n * m);
-    let sum_2 = util::sum_of_powers(&Scalar::from(2u64), n);
-    let sum_z = util::sum_of_powers(z, m);
-
-    (z - z * z) * sum_y - z * z * z * sum_2 * sum_z
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
+        producer.join().unwrap();
 
-    use crate::generators::PedersenGens;
+        assert_eq!(collector.dynamic_points.len(), capacity);
+        assert_eq!(collector.dynamic_scalars.capacity(), capacity);
+        assert!(collector.verify().is_ok());
+    }
 
     #[test]
-    fn test_delta() {
+    fn flat_generator_scalars_match_nested_layout() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
         let mut rng = rand::thread_rng();
-        let y = Scalar::random(&mut rng);
-        let z = Scalar::random(&mut rng);
 
-        // Choose n = 256 to ensure we overflow the group order during
-        // the computation, to check that that's done correctly
-        let n = 256;
+        let proofs: Vec<_> = [(8, 1), (64, 2), (32, 4), (16, 2)]
+            .iter()
+            .map(|&(n, m)| {
+                let values: Vec<u64> = (0..m).map(|i| i as u64).collect();
+                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+                let mut transcript = Transcript::new(b"FlatLayoutTest");
+                let (proof, commitments) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+                (proof, commitments, n)
+            })
+            .collect();
 
-        // code copied from previous implementation
-        let z2 = z * z;
-        let z3 = z2 * z;
-        let mut power_g = Scalar::ZERO;
-        let mut exp_y = Scalar::ONE; // start at y^0 = 1
-        let mut exp_2 = Scalar::ONE; // start at 2^0 = 1
-        for _ in 0..n {
-            power_g += (z - z2) * exp_y - z3 * exp_2;
+        // Accumulate each proof's generator scalars into one vector
+        // per party, and all proofs into the flat buffers.
+        let mut nested_g = vec![vec![Scalar::ZERO; 64]; 4];
+        let mut nested_h = vec![vec![Scalar::ZERO; 64]; 4];
+        let mut dynamic_scalars = Vec::new();
+        let mut factors = TranscriptBatchFactors::new();
+        for (proof, commitments, n) in proofs.iter() {
+            let mut transcript = Transcript::new(b"FlatLayoutTest");
+            factors.absorb(&proof.verification_view(&mut transcript, commitments, *n));
+        }
+        let mut collector = BatchCollector::new(&bp_gens, &pc_gens);
+        for (i, (proof, commitments, n)) in proofs.iter().enumerate() {
+            let mut transcript = Transcript::new(b"FlatLayoutTest");
+            collector
+                .add_proof_with_factors(
+                    proof.verification_view(&mut transcript, commitments, *n),
+                    &mut factors,
+                )
+                .unwrap();
 
-            exp_y = exp_y * y; // y^i -> y^(i+1)
-            exp_2 = exp_2 + exp_2; // 2^i -> 2^(i+1)
+            let mut single = BatchCollector::new(&bp_gens, &pc_gens);
+            let mut transcript = Transcript::new(b"FlatLayoutTest");
+            single
+                .add_proof_at(
+                    i,
+                    proof.verification_view(&mut transcript, commitments, *n),
+                    &mut factors,
+                )
+                .unwrap();
+            for j in 0..single.party_capacity {
+                for i in 0..single.gens_capacity {
+                    nested_g[j][i] += single.g_scalars[j * single.gens_capacity + i];
+                    nested_h[j][i] += single.h_scalars[j * single.gens_capacity + i];
+                }
+            }
+            dynamic_scalars.extend(single.dynamic_scalars.iter().copied());
         }
 
-        assert_eq!(power_g, delta(n, 1, &y, &z),);
-    }
+        assert_eq!((collector.gens_capacity, collector.party_capacity), (64, 4));
+        assert_eq!(collector.dynamic_scalars, dynamic_scalars);
+        assert_eq!(collector.g_scalars, nested_g.concat());
+        assert_eq!(collector.h_scalars, nested_h.concat());
 
-    /// Given a bitsize `n`, test the following:
-    ///
-    /// 1. Generate `m` random values and create a proof they are all in range;
-    /// 2. Serialize to wire format;
-    /// 3. Deserialize from wire format;
-    /// 4. Verify the proof.
-    fn singleparty_create_and_verify_helper(n: usize, m: usize) {
-        // Split the test into two scopes, so that it's explicit what
-        // data is shared between the prover and the verifier.
+        // The generators are laid out in the same order.
+        let nested_gens: Vec<_> = (0..4)
+            .flat_map(|j| bp_gens.share(j).G(64).copied().collect::<Vec<_>>())
+            .collect();
+        let flat_gens: Vec<_> = bp_gens.G(64, 4).copied().collect();
+        assert_eq!(flat_gens, nested_gens);
 
-        // Use bincode for serialization
-        //use bincode; // already present in lib.rs
+        assert!(collector.verify().is_ok());
+    }
 
-        // Both prover and verifier have access to the generators and the proof
-        let max_bitsize = 64;
-        let max_parties = 8;
+    #[test]
+    fn deterministic_batch_verification() {
         let pc_gens = PedersenGens::default();
-        let bp_gens = BulletproofGens::new(max_bitsize, max_parties);
-
-        // Prover's scope
-        let (proof_bytes, value_commitments) = {
-            use self::rand::Rng;
-            let mut rng = rand::thread_rng();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
 
-            // 0. Create witness data
-            let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
-            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
-            let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+        let mut proofs: Vec<_> = [(64, 1), (32, 4), (8, 2)]
+            .iter()
+            .map(|&(n, m)| {
+                let values = vec![42u64; m];
+                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+                let mut transcript = Transcript::new(b"DeterministicBatchTest");
+                let (proof, commitments) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+                (proof, commitments, n)
+            })
+            .collect();
 
-            // 1. Create the proof
-            let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
-            let (proof, value_commitments) = RangeProof::prove_multiple(
+        let verify = |proofs: &[(RangeProof, Vec<CompressedRistretto>, usize)]| {
+            let mut transcripts = vec![Transcript::new(b"DeterministicBatchTest"); proofs.len()];
+            let result = RangeProof::verify_batch_deterministic(
+                proofs
+                    .iter()
+                    .zip(&mut transcripts)
+                    .map(|((proof, commitments, n), transcript)| {
+                        proof.verification_view(transcript, commitments, *n)
+                    }),
                 &bp_gens,
                 &pc_gens,
-                &mut transcript,
-                &values,
-                &blindings,
-                n,
-            )
-            .unwrap();
-
-            // 2. Return serialized proof and value commitments
-            (bincode::serialize(&proof).unwrap(), value_commitments)
+            );
+            (result, transcripts)
         };
 
-        // Verifier's scope
-        {
-            // 3. Deserialize
-            let proof: RangeProof = bincode::deserialize(&proof_bytes).unwrap();
-
-            // 4. Verify with the same customization label as above
-            let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+        let (result, transcripts) = verify(&proofs);
+        assert!(result.is_ok());
 
-            assert!(proof
-                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
-                .is_ok());
+        // The transcripts are left as after a randomized verification.
+        let (_, again) = verify(&proofs);
+        let mut randomized = vec![Transcript::new(b"DeterministicBatchTest"); proofs.len()];
+        assert!(RangeProof::verify_batch(
+            proofs
+                .iter()
+                .zip(&mut randomized)
+                .map(|((proof, commitments, n), transcript)| {
+                    proof.verification_view(transcript, commitments, *n)
+                }),
+            &bp_gens,
+            &pc_gens,
+        )
+        .is_ok());
+        for ((mut t, mut u), mut r) in transcripts.into_iter().zip(again).zip(randomized) {
+            let mut t_bytes = [0u8; 32];
+            let mut u_bytes = [0u8; 32];
+            let mut r_bytes = [0u8; 32];
+            t.challenge_bytes(b"next", &mut t_bytes);
+            u.challenge_bytes(b"next", &mut u_bytes);
+            r.challenge_bytes(b"next", &mut r_bytes);
+            assert_eq!(t_bytes, u_bytes);
+            assert_eq!(t_bytes, r_bytes);
         }
-    }
 
-    #[test]
-    fn create_and_verify_n_32_m_1() {
-        singleparty_create_and_verify_helper(32, 1);
+        proofs[1].0.t_x += Scalar::ONE;
+        assert_eq!(verify(&proofs).0, Err(ProofError::VerificationError));
     }
 
     #[test]
-    fn create_and_verify_n_32_m_2() {
-        singleparty_create_and_verify_helper(32, 2);
-    }
+    fn deterministic_factors_depend_on_the_whole_batch() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
 
-    #[test]
-    fn create_and_verify_n_32_m_4() {
-        singleparty_create_and_verify_helper(32, 4);
-    }
+        let proofs: Vec<(RangeProof, CompressedRistretto)> = (0..3u64)
+            .map(|v| {
+                let blinding = Scalar::random(&mut rng);
+                let mut transcript = Transcript::new(b"BatchFactorsTest");
+                RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, v, &blinding, 32)
+                    .unwrap()
+            })
+            .collect();
+        let factors = |batch: &[&(RangeProof, CompressedRistretto)]| {
+            let mut factors = TranscriptBatchFactors::new();
+            for (proof, V) in batch.iter() {
+                let mut transcript = Transcript::new(b"BatchFactorsTest");
+                factors.absorb(&proof.verification_view(
+                    &mut transcript,
+                    core::slice::from_ref(V),
+                    32,
+                ));
+            }
+            factors.draw(0)
+        };
 
-    #[test]
-    fn create_and_verify_n_32_m_8() {
-        singleparty_create_and_verify_helper(32, 8);
+        // The factors of the first proof change with the other proofs
+        // of the batch and with their order.
+        let reference = factors(&[&proofs[0], &proofs[1]]);
+        assert_eq!(factors(&[&proofs[0], &proofs[1]]), reference);
+        assert_ne!(factors(&[&proofs[0], &proofs[2]]), reference);
+        assert_ne!(factors(&[&proofs[0]]), reference);
+        assert_ne!(factors(&[&proofs[1], &proofs[0]]), reference);
     }
 
     #[test]
-    fn create_and_verify_n_64_m_1() {
-        singleparty_create_and_verify_helper(64, 1);
+    fn batch_verifier_empty_batch() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        assert!(BatchVerifier::new(&bp_gens, &pc_gens).finalize().is_ok());
     }
 
     #[test]
-    fn create_and_verify_n_64_m_2() {
-        singleparty_create_and_verify_helper(64, 2);
+    fn verify_batch_identify_blames_bad_proof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut proofs: Vec<(RangeProof, CompressedRistretto)> = (0..10u64)
+            .map(|v| {
+                let mut transcript = Transcript::new(b"BatchIdentifyTest");
+                let blinding = Scalar::random(&mut rng);
+                RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, v, &blinding, 32)
+                    .unwrap()
+            })
+            .collect();
+
+        let verify = |proofs: &[(RangeProof, CompressedRistretto)]| {
+            let mut transcripts = vec![Transcript::new(b"BatchIdentifyTest"); proofs.len()];
+            let views =
+                proofs
+                    .iter()
+                    .zip(transcripts.iter_mut())
+                    .map(|((proof, V), transcript)| {
+                        proof.verification_view(transcript, core::slice::from_ref(V), 32)
+                    });
+            RangeProof::verify_batch_identify(views, &bp_gens, &pc_gens)
+        };
+
+        assert!(verify(&proofs).is_ok());
+
+        proofs[4].0.t_x += Scalar::ONE;
+        assert_eq!(
+            verify(&proofs),
+            Err(ProofError::BatchVerificationError {
+                bad_indices: vec![4]
+            })
+        );
     }
 
     #[test]
-    fn create_and_verify_n_64_m_4() {
-        singleparty_create_and_verify_helper(64, 4);
+    fn verify_batch_indexed_names_first_bad_proof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut proofs: Vec<(RangeProof, CompressedRistretto)> = (0..8u64)
+            .map(|v| {
+                let mut transcript = Transcript::new(b"BatchIndexedTest");
+                let blinding = Scalar::random(&mut rng);
+                RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, v, &blinding, 16)
+                    .unwrap()
+            })
+            .collect();
+
+        let verify = |proofs: &[(RangeProof, CompressedRistretto)]| {
+            let mut transcripts = vec![Transcript::new(b"BatchIndexedTest"); proofs.len()];
+            let views =
+                proofs
+                    .iter()
+                    .zip(transcripts.iter_mut())
+                    .map(|((proof, V), transcript)| {
+                        proof.verification_view(transcript, core::slice::from_ref(V), 16)
+                    });
+            RangeProof::verify_batch_indexed(views, &bp_gens, &pc_gens)
+        };
+
+        assert_eq!(verify(&proofs), Ok(()));
+
+        proofs[6].0.t_x += Scalar::ONE;
+        proofs[2].0.e_blinding += Scalar::ONE;
+        assert_eq!(verify(&proofs), Err((2, ProofError::VerificationError)));
     }
 
     #[test]
-    fn create_and_verify_n_64_m_8() {
-        singleparty_create_and_verify_helper(64, 8);
+    fn batch_soundness_error_grows_with_batch_size() {
+        assert_eq!(RangeProof::batch_soundness_error_bits(1), -252.0);
+        assert_eq!(RangeProof::batch_soundness_error_bits(2), -251.0);
+        assert_eq!(RangeProof::batch_soundness_error_bits(1024), -242.0);
+        assert_eq!(RangeProof::batch_soundness_error_bits(1 << 20), -232.0);
+        assert_eq!(RangeProof::batch_soundness_error_bits(0), f64::NEG_INFINITY);
+
+        let three = RangeProof::batch_soundness_error_bits(3);
+        assert!((three - (3f64.log2() - 252.0)).abs() < 1e-9);
+        assert!(three > -251.0 && three < -250.0);
     }
 
-    fn singleparty_create_and_verify_batch_helper(nm: &[(usize, usize)]) {
-        let max_bitsize = 64;
-        let max_parties = 8;
+    #[test]
+    fn verify_batch_identify_failures_reports_each_proof() {
         let pc_gens = PedersenGens::default();
-        let bp_gens = BulletproofGens::new(max_bitsize, max_parties);
-
-        // Provers
-        let proofs: Vec<_> = nm
-            .iter()
-            .map(|&(n, m)| {
-                use self::rand::Rng;
-                let mut rng = rand::thread_rng();
-
-                let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
-                let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
-                let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
 
-                let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
-                let (proof, value_commitments) = RangeProof::prove_multiple(
+        let mut proofs: Vec<(RangeProof, Vec<CompressedRistretto>)> = (0..6u64)
+            .map(|v| {
+                let mut transcript = Transcript::new(b"BatchFailuresTest");
+                let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+                RangeProof::prove_multiple(
                     &bp_gens,
                     &pc_gens,
                     &mut transcript,
-                    &values,
+                    &[v, v << 20],
                     &blindings,
-                    n,
+                    32,
                 )
-                .unwrap();
-
-                (bincode::serialize(&proof).unwrap(), value_commitments, n)
+                .unwrap()
             })
             .collect();
 
-        // Verifier
-        {
-            let mut rng = rand::thread_rng();
+        let verify = |proofs: &[(RangeProof, Vec<CompressedRistretto>)]| {
+            let mut transcripts = vec![Transcript::new(b"BatchFailuresTest"); proofs.len()];
+            let views = proofs
+                .iter()
+                .zip(transcripts.iter_mut())
+                .map(|((proof, Vs), transcript)| proof.verification_view(transcript, Vs, 32));
+            RangeProof::verify_batch_identify_failures(views, &bp_gens, &pc_gens)
+        };
 
-            let proofs: Vec<(RangeProof, _, _)> = proofs
-                .into_iter()
-                .map(|(proof_bytes, commitments, n)| {
-                    (bincode::deserialize(&proof_bytes).unwrap(), commitments, n)
-                })
-                .collect();
+        assert_eq!(verify(&proofs), vec![Ok(()); 6]);
+        assert_eq!(verify(&[]), vec![]);
 
-            let mut transcripts = proofs
-                .iter()
-                .map(|_| Transcript::new(b"AggregatedRangeProofTest"))
-                .collect::<Vec<_>>();
+        proofs[4].0.t_x_blinding += Scalar::ONE;
+        let mut expected = vec![Ok(()); 6];
+        expected[4] = Err(ProofError::VerificationError);
+        assert_eq!(verify(&proofs), expected);
+
+        // A proof rejected before the combined check is reported too:
+        // its length doesn't match a single commitment.
+        proofs[1].1.pop();
+        expected[1] = Err(ProofError::VerificationError);
+        assert_eq!(verify(&proofs), expected);
+    }
+
+    #[test]
+    fn verify_batch_partition_separates_bad_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut proofs: Vec<(RangeProof, CompressedRistretto)> = (0..11u64)
+            .map(|v| {
+                let mut transcript = Transcript::new(b"BatchPartitionTest");
+                let blinding = Scalar::random(&mut rng);
+                RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, v, &blinding, 16)
+                    .unwrap()
+            })
+            .collect();
+
+        let partition = |proofs: &[(RangeProof, CompressedRistretto)], n: &[usize]| {
+            let mut transcripts = vec![Transcript::new(b"BatchPartitionTest"); proofs.len()];
+            let views = proofs.iter().zip(transcripts.iter_mut()).zip(n).map(
+                |(((proof, V), transcript), &n)| {
+                    proof.verification_view(transcript, core::slice::from_ref(V), n)
+                },
+            );
+            RangeProof::verify_batch_partition(views, &bp_gens, &pc_gens)
+        };
 
-            assert!(RangeProof::verify_batch_with_rng(
-                proofs
-                    .iter()
-                    .zip(&mut transcripts)
-                    .map(|((proof, commitments, n), transcript)| {
-                        proof.verification_view(transcript, commitments, *n)
-                    }),
-                &bp_gens,
-                &pc_gens,
-                &mut rng
-            )
-            .is_ok());
+        let mut n = vec![16; proofs.len()];
+        assert_eq!(partition(&proofs, &n), ((0..11).collect(), vec![]));
+
+        // Proofs 1, 2 and 9 fail the batch check, proof 6 is rejected
+        // before it is added to the batch.
+        proofs[1].0.t_x += Scalar::ONE;
+        proofs[2].0.e_blinding += Scalar::ONE;
+        proofs[9].1 = proofs[8].1;
+        n[6] = 32;
+        assert_eq!(
+            partition(&proofs, &n),
+            (vec![0, 3, 4, 5, 7, 8, 10], vec![1, 2, 6, 9])
+        );
+
+        for proof in proofs.iter_mut() {
+            proof.0.t_x += Scalar::ONE;
         }
+        assert_eq!(partition(&proofs, &n), (vec![], (0..11).collect()));
     }
 
     #[test]
-    fn create_and_verify_batch_64_2() {
-        singleparty_create_and_verify_batch_helper(&[(64, 2)]);
+    fn prove_multiple_scalars_matches_u64() {
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let (n, m) = (32, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        let values = [0u64, 1, 1 << 31, (1 << 32) - 1];
+        let scalars: Vec<Scalar> = values.iter().map(|&v| Scalar::from(v)).collect();
+        let blindings: Vec<Scalar> = (1..=m as u64).map(Scalar::from).collect();
+
+        let mut transcript = Transcript::new(b"ScalarValuesTest");
+        let (proof, commitments) = RangeProof::prove_multiple_scalars_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &scalars,
+            &blindings,
+            n,
+            &mut ChaChaRng::from_seed([1u8; 32]),
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"ScalarValuesTest");
+        let (expected, expected_commitments) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            n,
+            &mut ChaChaRng::from_seed([1u8; 32]),
+        )
+        .unwrap();
+
+        assert_eq!(proof.to_bytes(), expected.to_bytes());
+        assert_eq!(commitments, expected_commitments);
+
+        let mut transcript = Transcript::new(b"ScalarValuesTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, n)
+            .is_ok());
     }
 
     #[test]
-    fn create_and_verify_batch_64_2x2() {
-        singleparty_create_and_verify_batch_helper(&[(64, 2), (64, 2)]);
+    #[should_panic(expected = "values must be less than 2^n")]
+    #[cfg(debug_assertions)]
+    fn prove_multiple_scalars_rejects_large_values_in_debug() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+
+        let mut transcript = Transcript::new(b"ScalarValuesTest");
+        let _ = RangeProof::prove_multiple_scalars(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[-Scalar::ONE],
+            &[Scalar::ONE],
+            8,
+        );
     }
 
     #[test]
-    fn create_and_verify_batch_32_1_64_4_64_2_64_1() {
-        singleparty_create_and_verify_batch_helper(&[(32, 1), (64, 4), (64, 2), (64, 1)]);
+    fn secret_states_zeroize_on_drop() {
+        use self::dealer::*;
+        use self::party::*;
+        use zeroize::ZeroizeOnDrop;
+
+        fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        assert_zeroize_on_drop::<PartyAwaitingPosition>();
+        assert_zeroize_on_drop::<PartyAwaitingBitChallenge>();
+        assert_zeroize_on_drop::<PartyAwaitingPolyChallenge>();
+        assert_zeroize_on_drop::<messages::ProofShare>();
+        assert_zeroize_on_drop::<util::VecPoly1>();
+        assert_zeroize_on_drop::<util::Poly2>();
+
+        let (n, m) = (32, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        // The dealer aborts after the polynomial commitments, dropping
+        // the parties' intermediate states.
+        let mut transcript = Transcript::new(b"ZeroizeTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let party0 = Party::new(&bp_gens, &pc_gens, 7, Scalar::random(&mut rng), n).unwrap();
+        let party1 = Party::new(&bp_gens, &pc_gens, 9, Scalar::random(&mut rng), n).unwrap();
+        let (party0, bit_com0) = party0.assign_position(0).unwrap();
+        let (party1, bit_com1) = party1.assign_position(1).unwrap();
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(vec![bit_com0, bit_com1])
+            .unwrap();
+        let (party0, poly_com0) = party0.apply_challenge(&bit_challenge);
+        let (party1, poly_com1) = party1.apply_challenge(&bit_challenge);
+        let _ = dealer
+            .receive_poly_commitments(vec![poly_com0, poly_com1])
+            .unwrap();
+        drop((party0, party1));
+
+        // A complete run still produces a valid proof.
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+        let mut transcript = Transcript::new(b"ZeroizeTest");
+        let (proof, commitments) =
+            RangeProof::prove_multiple(&bp_gens, &pc_gens, &mut transcript, &[7, 9], &blindings, n)
+                .unwrap();
+        let mut transcript = Transcript::new(b"ZeroizeTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, n)
+            .is_ok());
     }
 
     #[test]
     fn detect_dishonest_party_during_aggregation() {
         use self::dealer::*;
+        use self::messages::*;
         use self::party::*;
 
         use crate::errors::MPCError;
@@ -1006,14 +5670,23 @@ mod tests {
         let v2_blinding = Scalar::random(&mut rng);
         let party2 = Party::new(&bp_gens, &pc_gens, v2, v2_blinding, n).unwrap();
 
-        // Parties 1, 3 are dishonest and use a 64-bit value
-        let v1 = rng.gen::<u64>();
+        // Parties 1, 3 are dishonest and use a 64-bit value, skipping
+        // the range check of `Party::new` as a dishonest party would.
+        let v1 = (1 << 32) | rng.gen::<u64>();
         let v1_blinding = Scalar::random(&mut rng);
-        let party1 = Party::new(&bp_gens, &pc_gens, v1, v1_blinding, n).unwrap();
+        assert_eq!(
+            Party::new(&bp_gens, &pc_gens, v1, v1_blinding, n).err(),
+            Some(MPCError::ValueOutOfRange)
+        );
+        let party1 =
+            Party::new_from_scalar_unchecked(&bp_gens, &pc_gens, Scalar::from(v1), v1_blinding, n)
+                .unwrap();
 
-        let v3 = rng.gen::<u64>();
+        let v3 = (1 << 32) | rng.gen::<u64>();
         let v3_blinding = Scalar::random(&mut rng);
-        let party3 = Party::new(&bp_gens, &pc_gens, v3, v3_blinding, n).unwrap();
+        let party3 =
+            Party::new_from_scalar_unchecked(&bp_gens, &pc_gens, Scalar::from(v3), v3_blinding, n)
+                .unwrap();
 
         let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
 
@@ -1022,25 +5695,38 @@ mod tests {
         let (party2, bit_com2) = party2.assign_position(2).unwrap();
         let (party3, bit_com3) = party3.assign_position(3).unwrap();
 
-        let (dealer, bit_challenge) = dealer
-            .receive_bit_commitments(vec![bit_com0, bit_com1, bit_com2, bit_com3])
-            .unwrap();
+        // The messages cross a byte boundary between each step.
+        let bit_coms = [bit_com0, bit_com1, bit_com2, bit_com3]
+            .iter()
+            .map(|c| BitCommitment::from_bytes(&c.to_bytes()).unwrap())
+            .collect();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_coms).unwrap();
+        let bit_challenge = BitChallenge::from_bytes(&bit_challenge.to_bytes()).unwrap();
 
         let (party0, poly_com0) = party0.apply_challenge(&bit_challenge);
         let (party1, poly_com1) = party1.apply_challenge(&bit_challenge);
         let (party2, poly_com2) = party2.apply_challenge(&bit_challenge);
         let (party3, poly_com3) = party3.apply_challenge(&bit_challenge);
 
-        let (dealer, poly_challenge) = dealer
-            .receive_poly_commitments(vec![poly_com0, poly_com1, poly_com2, poly_com3])
-            .unwrap();
+        let poly_coms = [poly_com0, poly_com1, poly_com2, poly_com3]
+            .iter()
+            .map(|c| PolyCommitment::from_bytes(&c.to_bytes()).unwrap())
+            .collect();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_coms).unwrap();
+        let poly_challenge = PolyChallenge::from_bytes(&poly_challenge.to_bytes()).unwrap();
 
         let share0 = party0.apply_challenge(&poly_challenge).unwrap();
         let share1 = party1.apply_challenge(&poly_challenge).unwrap();
         let share2 = party2.apply_challenge(&poly_challenge).unwrap();
         let share3 = party3.apply_challenge(&poly_challenge).unwrap();
 
-        match dealer.receive_shares(&[share0, share1, share2, share3]) {
+        let shares: Vec<_> = [share0, share1, share2, share3]
+            .iter()
+            .map(|share| ProofShare::from_bytes(&share.to_bytes()).unwrap())
+            .collect();
+
+        // The dealer audits the decoded shares like local ones.
+        match dealer.receive_shares(&shares) {
             Err(MPCError::MalformedProofShares { bad_shares }) => {
                 assert_eq!(bad_shares, vec![1, 3]);
             }
@@ -1053,6 +5739,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn audit_single_proof_shares() {
+        use self::dealer::*;
+        use self::party::*;
+        use crate::errors::MPCError;
+
+        let (n, m) = (32, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        // Party 1 smuggles a 64-bit value into the 32-bit proof,
+        // skipping the range check of `Party::new`.
+        let party0 = Party::new(&bp_gens, &pc_gens, 7, Scalar::random(&mut rng), n).unwrap();
+        let party1 = Party::new_from_scalar_unchecked(
+            &bp_gens,
+            &pc_gens,
+            Scalar::from(1u64 << 40),
+            Scalar::random(&mut rng),
+            n,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"AuditTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let (party0, bit_com0) = party0.assign_position(0).unwrap();
+        let (party1, bit_com1) = party1.assign_position(1).unwrap();
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(vec![bit_com0, bit_com1])
+            .unwrap();
+        let (party0, poly_com0) = party0.apply_challenge(&bit_challenge);
+        let (party1, poly_com1) = party1.apply_challenge(&bit_challenge);
+        let (_dealer, poly_challenge) = dealer
+            .receive_poly_commitments(vec![poly_com0, poly_com1])
+            .unwrap();
+        let share0 = party0.apply_challenge(&poly_challenge).unwrap();
+        let share1 = party1.apply_challenge(&poly_challenge).unwrap();
+
+        // The recorded messages are enough to audit each share.
+        let audit = |share: &messages::ProofShare, j, bit_com, poly_com| {
+            share.audit(
+                &bp_gens,
+                &pc_gens,
+                j,
+                n,
+                bit_com,
+                &bit_challenge,
+                poly_com,
+                &poly_challenge,
+            )
+        };
+
+        assert_eq!(audit(&share0, 0, &bit_com0, &poly_com0), Ok(()));
+        assert_eq!(
+            audit(&share1, 1, &bit_com1, &poly_com1),
+            Err(MPCError::MalformedProofShares {
+                bad_shares: vec![1]
+            })
+        );
+
+        // An honest share checked against another party's messages fails.
+        assert!(audit(&share0, 1, &bit_com1, &poly_com1).is_err());
+
+        let mut tampered = share0.clone();
+        tampered.t_x_blinding += Scalar::ONE;
+        assert_eq!(
+            audit(&tampered, 0, &bit_com0, &poly_com0),
+            Err(MPCError::MalformedProofShares {
+                bad_shares: vec![0]
+            })
+        );
+    }
+
     #[test]
     fn detect_dishonest_dealer_during_aggregation() {
         use self::dealer::*;
@@ -1094,4 +5853,413 @@ mod tests {
 
         assert!(maybe_share0.unwrap_err() == MPCError::MaliciousDealer);
     }
+
+    #[test]
+    fn mpc_errors_propagate_as_proof_errors() {
+        use self::dealer::*;
+        use self::party::*;
+        use crate::errors::MPCError;
+
+        // Runs the protocol for one party, with the dealer choosing x = 0.
+        fn prove_with_zero_x(
+            bp_gens: &BulletproofGens,
+            pc_gens: &PedersenGens,
+        ) -> Result<RangeProof, ProofError> {
+            let mut transcript = Transcript::new(b"MPCErrorTest");
+            let party = Party::new(bp_gens, pc_gens, 7, Scalar::ONE, 32)?;
+            let dealer = Dealer::new(bp_gens, pc_gens, &mut transcript, 32, 1)?;
+            let (party, bit_commitment) = party.assign_position(0)?;
+            let (dealer, bit_challenge) = dealer.receive_bit_commitments(vec![bit_commitment])?;
+            let (party, poly_commitment) = party.apply_challenge(&bit_challenge);
+            let (dealer, mut poly_challenge) =
+                dealer.receive_poly_commitments(vec![poly_commitment])?;
+            poly_challenge.x = Scalar::ZERO;
+            let share = party.apply_challenge(&poly_challenge)?;
+            Ok(dealer.receive_shares(&[share])?)
+        }
+
+        let bp_gens = BulletproofGens::new(32, 1);
+        assert_eq!(
+            prove_with_zero_x(&bp_gens, &PedersenGens::default()).err(),
+            Some(ProofError::MaliciousDealer)
+        );
+
+        assert_eq!(
+            ProofError::from(MPCError::MalformedProofShares {
+                bad_shares: vec![1, 3]
+            }),
+            ProofError::MalformedShares {
+                bad_shares: vec![1, 3]
+            }
+        );
+
+        // Other MPC errors are kept as the source of the proof error.
+        let error = ProofError::from(MPCError::WrongNumProofShares);
+        assert_eq!(
+            error,
+            ProofError::ProvingError(MPCError::WrongNumProofShares)
+        );
+        let source = std::error::Error::source(&error).unwrap();
+        assert_eq!(
+            source.downcast_ref::<MPCError>(),
+            Some(&MPCError::WrongNumProofShares)
+        );
+    }
+
+    #[test]
+    fn aggregation_over_byte_messages() {
+        use self::dealer::*;
+        use self::messages::*;
+        use self::party::*;
+
+        let (n, m) = (32, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        let values = [1u64, 2, 3, 4];
+        let parties: Vec<_> = values
+            .iter()
+            .map(|&v| Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n).unwrap())
+            .collect();
+
+        let mut transcript = Transcript::new(b"ByteMessagesTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        // Every message is sent as bytes and decoded on the other side.
+        let (parties, bit_commitments): (Vec<_>, Vec<Vec<u8>>) = parties
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| {
+                let (p, bit_com) = p.assign_position(j).unwrap();
+                (p, bit_com.to_bytes().to_vec())
+            })
+            .unzip();
+        let bit_commitments: Vec<_> = bit_commitments
+            .iter()
+            .map(|bytes| BitCommitment::from_bytes(bytes).unwrap())
+            .collect();
+        let commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+        let bit_challenge = BitChallenge::from_bytes(&bit_challenge.to_bytes()).unwrap();
+
+        let (parties, poly_commitments): (Vec<_>, Vec<Vec<u8>>) = parties
+            .into_iter()
+            .map(|p| {
+                let (p, poly_com) = p.apply_challenge(&bit_challenge);
+                (p, poly_com.to_bytes().to_vec())
+            })
+            .unzip();
+        let poly_commitments = poly_commitments
+            .iter()
+            .map(|bytes| PolyCommitment::from_bytes(bytes).unwrap())
+            .collect();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+        let poly_challenge = PolyChallenge::from_bytes(&poly_challenge.to_bytes()).unwrap();
+
+        let shares: Vec<Vec<u8>> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap().to_bytes())
+            .collect();
+        let shares: Vec<_> = shares
+            .iter()
+            .map(|bytes| ProofShare::from_bytes(bytes).unwrap())
+            .collect();
+        let proof = dealer.receive_shares(&shares).unwrap();
+
+        let proof = RangeProof::from_bytes(&proof.to_bytes()).unwrap();
+        let mut transcript = Transcript::new(b"ByteMessagesTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, n)
+            .is_ok());
+    }
+
+    #[test]
+    fn messages_serde_roundtrip() {
+        use self::dealer::*;
+        use self::messages::*;
+        use self::party::*;
+
+        let (n, m) = (16, 1);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        let party = Party::new(&bp_gens, &pc_gens, 5, Scalar::random(&mut rng), n).unwrap();
+        let mut transcript = Transcript::new(b"MessagesSerdeTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        let (party, bit_com) = party.assign_position(0).unwrap();
+        let decoded: BitCommitment =
+            bincode::deserialize(&bincode::serialize(&bit_com).unwrap()).unwrap();
+        assert_eq!(decoded.to_bytes(), bit_com.to_bytes());
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(vec![decoded]).unwrap();
+        let decoded: BitChallenge =
+            bincode::deserialize(&bincode::serialize(&bit_challenge).unwrap()).unwrap();
+        assert_eq!(decoded.to_bytes(), bit_challenge.to_bytes());
+
+        let (party, poly_com) = party.apply_challenge(&decoded);
+        let decoded: PolyCommitment =
+            bincode::deserialize(&bincode::serialize(&poly_com).unwrap()).unwrap();
+        assert_eq!(decoded.to_bytes(), poly_com.to_bytes());
+
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(vec![decoded]).unwrap();
+        let decoded: PolyChallenge =
+            bincode::deserialize(&bincode::serialize(&poly_challenge).unwrap()).unwrap();
+        assert_eq!(decoded.to_bytes(), poly_challenge.to_bytes());
+
+        let share = party.apply_challenge(&decoded).unwrap();
+        let decoded: ProofShare =
+            bincode::deserialize(&bincode::serialize(&share).unwrap()).unwrap();
+        assert_eq!(decoded.to_bytes(), share.to_bytes());
+
+        assert!(dealer.receive_shares(&[decoded]).is_ok());
+    }
+
+    #[test]
+    fn malformed_messages_are_rejected() {
+        use self::messages::*;
+
+        let invalid_point = [0xffu8; 32];
+        let non_canonical = [0xffu8; 32];
+        let point = PedersenGens::default().B.compress().to_bytes();
+        let scalar = Scalar::ONE.to_bytes();
+
+        assert!(BitCommitment::from_bytes(&[point, point, point].concat()).is_ok());
+        assert_eq!(
+            BitCommitment::from_bytes(&[point, invalid_point, point].concat()).unwrap_err(),
+            ProofError::FormatError
+        );
+        assert_eq!(
+            BitCommitment::from_bytes(&[invalid_point, point, point].concat()).unwrap_err(),
+            ProofError::FormatError
+        );
+        assert_eq!(
+            BitCommitment::from_bytes(&[point, point].concat()).unwrap_err(),
+            ProofError::FormatError
+        );
+
+        assert!(BitChallenge::from_bytes(&[scalar, scalar].concat()).is_ok());
+        assert_eq!(
+            BitChallenge::from_bytes(&[scalar, non_canonical].concat()).unwrap_err(),
+            ProofError::FormatError
+        );
+
+        assert!(PolyCommitment::from_bytes(&[point, point].concat()).is_ok());
+        assert_eq!(
+            PolyCommitment::from_bytes(&[invalid_point, point].concat()).unwrap_err(),
+            ProofError::FormatError
+        );
+        assert_eq!(
+            PolyCommitment::from_bytes(&[point, point, point].concat()).unwrap_err(),
+            ProofError::FormatError
+        );
+
+        assert!(PolyChallenge::from_bytes(&scalar).is_ok());
+        assert_eq!(
+            PolyChallenge::from_bytes(&non_canonical).unwrap_err(),
+            ProofError::FormatError
+        );
+        assert_eq!(
+            PolyChallenge::from_bytes(&scalar[..31]).unwrap_err(),
+            ProofError::FormatError
+        );
+
+        let share = [scalar; 7].concat();
+        assert!(ProofShare::from_bytes(&share).is_ok());
+        let mut bad_share = share.clone();
+        bad_share[5 * 32..6 * 32].copy_from_slice(&non_canonical);
+        assert_eq!(
+            ProofShare::from_bytes(&bad_share).unwrap_err(),
+            ProofError::FormatError
+        );
+        // The l and r vectors must have the same length.
+        assert_eq!(
+            ProofShare::from_bytes(&share[..6 * 32]).unwrap_err(),
+            ProofError::FormatError
+        );
+        assert_eq!(
+            ProofShare::from_bytes(&share[..2 * 32]).unwrap_err(),
+            ProofError::FormatError
+        );
+        assert_eq!(
+            ProofShare::from_bytes(&share[1..]).unwrap_err(),
+            ProofError::FormatError
+        );
+    }
+
+    fn participants_helper(
+        dealer_list: &[[u8; 32]],
+        party_list: &[[u8; 32]],
+    ) -> Result<(), crate::errors::MPCError> {
+        use self::dealer::*;
+        use self::party::*;
+
+        let (n, m) = (16, party_list.len());
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        let mut transcript = Transcript::new(b"ParticipantsTest");
+        let dealer =
+            Dealer::new_with_participants(&bp_gens, &pc_gens, &mut transcript, n, m, dealer_list)?;
+
+        let mut parties = Vec::new();
+        let mut bit_commitments = Vec::new();
+        for (j, identity) in party_list.iter().enumerate() {
+            let party = Party::expect_participants(
+                &bp_gens,
+                &pc_gens,
+                j as u64,
+                Scalar::random(&mut rng),
+                n,
+                *identity,
+                party_list,
+            )?
+            .receive_participants(dealer.participants())?;
+            let (party, bit_commitment) = party.assign_position(j)?;
+            parties.push(party);
+            bit_commitments.push(bit_commitment);
+        }
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&bit_challenge))
+            .unzip();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+        let shares = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge))
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = dealer.receive_shares(&shares)?;
+
+        let mut transcript = Transcript::new(b"ParticipantsTest");
+        bind_participants(&mut transcript, dealer_list);
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+            .is_ok());
+
+        // The proof does not verify without the participant binding.
+        let mut transcript = Transcript::new(b"ParticipantsTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn participants_matching_lists() {
+        let list = [[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]];
+        assert_eq!(participants_helper(&list, &list), Ok(()));
+    }
+
+    #[test]
+    fn participants_reordered_list() {
+        use crate::errors::MPCError;
+
+        let list = [[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]];
+        let reordered = [[2u8; 32], [1u8; 32], [3u8; 32], [4u8; 32]];
+        assert_eq!(
+            participants_helper(&reordered, &list),
+            Err(MPCError::ParticipantMismatch)
+        );
+    }
+
+    #[test]
+    fn participants_omitted_participant() {
+        use crate::errors::MPCError;
+
+        let list = [[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]];
+        let omitted = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        assert_eq!(
+            participants_helper(&omitted, &list),
+            Err(MPCError::WrongNumParticipants)
+        );
+
+        let substituted = [[1u8; 32], [2u8; 32], [3u8; 32], [5u8; 32]];
+        assert_eq!(
+            participants_helper(&substituted, &list),
+            Err(MPCError::ParticipantMismatch)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "prover-introspection")]
+    fn trace_recomputes_t_x() {
+        use crate::inner_product_proof::inner_product;
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let (n, m) = (32, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        let values = [7u64, 1 << 20, 0, u32::MAX as u64];
+        let mut rng = ChaChaRng::from_seed([7u8; 32]);
+        let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"ProverTraceTest");
+        let (proof, _, trace) = RangeProof::prove_multiple_traced(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            n,
+            &mut ChaChaRng::from_seed([24u8; 32]),
+        )
+        .unwrap();
+
+        // Recompute t(x) independently from l(x) and r(x).
+        let x = trace.x.unwrap();
+        let t_x: Scalar = trace
+            .parties
+            .iter()
+            .map(|p| {
+                let poly = p.poly.as_ref().unwrap();
+                let l: Vec<Scalar> = poly
+                    .l_poly
+                    .0
+                    .iter()
+                    .zip(&poly.l_poly.1)
+                    .map(|(a, b)| a + b * x)
+                    .collect();
+                let r: Vec<Scalar> = poly
+                    .r_poly
+                    .0
+                    .iter()
+                    .zip(&poly.r_poly.1)
+                    .map(|(a, b)| a + b * x)
+                    .collect();
+                let t = inner_product(&l, &r);
+                assert_eq!(t, poly.t_poly.0 + x * (poly.t_poly.1 + x * poly.t_poly.2));
+                t
+            })
+            .sum();
+        assert_eq!(t_x, proof.t_x);
+
+        for (j, v) in values.iter().enumerate() {
+            let bits = trace.party(j).unwrap().bits.as_ref().unwrap();
+            assert_eq!(bits.a_L.len(), n);
+            assert_eq!(bits.a_L[0], Scalar::from(v & 1));
+        }
+
+        // Tracing has no effect on the emitted proof.
+        let mut transcript = Transcript::new(b"ProverTraceTest");
+        let (untraced, _) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            n,
+            &mut ChaChaRng::from_seed([24u8; 32]),
+        )
+        .unwrap();
+        assert_eq!(proof.to_bytes(), untraced.to_bytes());
+    }
 }
@@ -13,11 +13,11 @@
 extern crate alloc;
 
 use alloc::vec::Vec;
-use zeroize::ZeroizeOnDrop;
 use core::iter;
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use rand_core::{CryptoRng, RngCore};
+use zeroize::ZeroizeOnDrop;
 
 use crate::errors::MPCError;
 use crate::generators::{BulletproofGens, PedersenGens};
@@ -27,12 +27,23 @@ use crate::util;
 use rand::thread_rng;
 
 use super::messages::*;
+#[cfg(feature = "prover-introspection")]
+use super::trace::{BitTrace, PolyTrace};
+
+/// Returns the `i`-th bit of the little-endian encoding of `v`.
+fn bit(v: &Scalar, i: usize) -> u8 {
+    (v.as_bytes()[i >> 3] >> (i & 7)) & 1
+}
 
 /// Used to construct a party for the aggregated rangeproof MPC protocol.
 pub struct Party {}
 
 impl Party {
     /// Constructs a `PartyAwaitingPosition` with the given rangeproof parameters.
+    ///
+    /// Returns [`MPCError::ValueOutOfRange`] if `v` does not fit in
+    /// `n` bits, since the party's share would make the aggregated
+    /// proof fail.
     pub fn new<'a>(
         bp_gens: &'a BulletproofGens,
         pc_gens: &'a PedersenGens,
@@ -40,26 +51,172 @@ impl Party {
         v_blinding: Scalar,
         n: usize,
     ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
-        if !(n == 8 || n == 16 || n == 32 || n == 64) {
-            return Err(MPCError::InvalidBitsize);
+        Party::new_from_scalar(bp_gens, pc_gens, Scalar::from(v), v_blinding, n)
+    }
+
+    /// Constructs a `PartyAwaitingPosition` with the given rangeproof
+    /// parameters, for a value `v` given as a scalar, such as a
+    /// witness of another protocol.
+    ///
+    /// `v` is read as a little-endian integer, and must be less than
+    /// \\(2\^n\\), otherwise this returns
+    /// [`MPCError::ValueOutOfRange`].  The party then commits to the
+    /// same bits as a party created with [`Party::new`] for the
+    /// equivalent `u64`.
+    pub fn new_from_scalar<'a>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        v: Scalar,
+        v_blinding: Scalar,
+        n: usize,
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        let party = Party::new_from_scalar_unchecked(bp_gens, pc_gens, v, v_blinding, n)?;
+        if !util::scalar_fits_bits(&v, n) {
+            return Err(MPCError::ValueOutOfRange);
+        }
+        Ok(party)
+    }
+
+    /// Constructs a `PartyAwaitingPosition` for a value `v` given as a
+    /// scalar, without checking that it fits in `n` bits.
+    ///
+    /// Only the low `n` bits of `v` are committed to bit by bit, so
+    /// the resulting proof is invalid unless `v` is less than
+    /// \\(2\^n\\).
+    pub(crate) fn new_from_scalar_unchecked<'a>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        v: Scalar,
+        v_blinding: Scalar,
+        n: usize,
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        let padded_n = util::padded_bitsize(n).ok_or(MPCError::InvalidBitsize)?;
+        Party::new_from_scalar_padded(bp_gens, pc_gens, v, v_blinding, n, padded_n)
+    }
+
+    /// Constructs a `PartyAwaitingPosition` proving an `n`-bit range
+    /// padded to `padded_n` bits, for a dealer created with
+    /// [`Dealer::new_with_bitsizes`](super::dealer::Dealer::new_with_bitsizes).
+    ///
+    /// The bits past `n` have zero weight, so that parties with
+    /// different bitsizes can share the common `padded_n`.  Returns
+    /// [`MPCError::ValueOutOfRange`] if `v` does not fit in `n` bits.
+    pub fn new_padded<'a>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        v: u64,
+        v_blinding: Scalar,
+        n: usize,
+        padded_n: usize,
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        let v = Scalar::from(v);
+        let party = Party::new_from_scalar_padded(bp_gens, pc_gens, v, v_blinding, n, padded_n)?;
+        if !util::scalar_fits_bits(&v, n) {
+            return Err(MPCError::ValueOutOfRange);
         }
-        if bp_gens.gens_capacity < n {
+        Ok(party)
+    }
+
+    /// Constructs a `PartyAwaitingPosition` proving an `n`-bit range
+    /// padded to `padded_n` bits, like [`Party::new_padded`], without
+    /// checking that `v` fits in `n` bits.
+    pub(crate) fn new_from_scalar_padded<'a>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        v: Scalar,
+        v_blinding: Scalar,
+        n: usize,
+        padded_n: usize,
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        match util::padded_bitsize(n) {
+            Some(min_n)
+                if min_n <= padded_n && util::padded_bitsize(padded_n) == Some(padded_n) => {}
+            _ => return Err(MPCError::InvalidBitsize),
+        }
+        if bp_gens.gens_capacity < padded_n {
             return Err(MPCError::InvalidGeneratorsLength);
         }
 
-        let V = pc_gens.commit(v.into(), v_blinding).compress();
+        let V = pc_gens.commit(v, v_blinding).compress();
 
         Ok(PartyAwaitingPosition {
             bp_gens,
             pc_gens,
             n,
+            padded_n,
             v,
             v_blinding,
             V,
+            expected_position: None,
+        })
+    }
+
+    /// Constructs a `PartyAwaitingParticipants` with the given
+    /// rangeproof parameters, for a dealer created with
+    /// [`Dealer::new_with_participants`](super::dealer::Dealer::new_with_participants).
+    ///
+    /// The party expects the dealer's participant list to be exactly
+    /// `participants`, and `identity` to appear in it exactly once.
+    pub fn expect_participants<'a>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        v: u64,
+        v_blinding: Scalar,
+        n: usize,
+        identity: [u8; 32],
+        participants: &[[u8; 32]],
+    ) -> Result<PartyAwaitingParticipants<'a>, MPCError> {
+        let mut positions = participants
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| **p == identity);
+        let position = match (positions.next(), positions.next()) {
+            (Some((j, _)), None) => j,
+            _ => return Err(MPCError::ParticipantMismatch),
+        };
+
+        let party = Party::new(bp_gens, pc_gens, v, v_blinding, n)?;
+
+        Ok(PartyAwaitingParticipants {
+            party,
+            position,
+            participants: participants.to_vec(),
         })
     }
 }
 
+/// A party waiting for the dealer's list of participant identities.
+pub struct PartyAwaitingParticipants<'a> {
+    party: PartyAwaitingPosition<'a>,
+    /// The position of the party's identity in the expected list.
+    position: usize,
+    /// The ordered participant identities the party expects.
+    participants: Vec<[u8; 32]>,
+}
+
+impl<'a> PartyAwaitingParticipants<'a> {
+    /// Receive the ordered participant identities from the dealer
+    /// (see [`DealerAwaitingBitCommitments::participants`](super::dealer::DealerAwaitingBitCommitments::participants))
+    /// and check them against the expected list.
+    ///
+    /// Returns an error before any commitment is made if the lists
+    /// differ, for instance if a participant was omitted or reordered.
+    /// The returned party only accepts its claimed position in
+    /// [`assign_position`](PartyAwaitingPosition::assign_position).
+    pub fn receive_participants(
+        self,
+        participants: &[[u8; 32]],
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        if participants != &self.participants[..] {
+            return Err(MPCError::ParticipantMismatch);
+        }
+
+        let mut party = self.party;
+        party.expected_position = Some(self.position);
+        Ok(party)
+    }
+}
+
 /// A party waiting for the dealer to assign their position in the aggregation.
 #[derive(ZeroizeOnDrop)]
 pub struct PartyAwaitingPosition<'a> {
@@ -68,9 +225,14 @@ pub struct PartyAwaitingPosition<'a> {
     #[zeroize(skip)]
     pc_gens: &'a PedersenGens,
     n: usize,
-    v: u64,
+    /// The bitsize `n` rounded up to 8, 16, 32, 64 or 128.  The padding
+    /// bits are zero for any value in range and carry no weight.
+    padded_n: usize,
+    v: Scalar,
     v_blinding: Scalar,
     V: CompressedRistretto,
+    /// The position the party claimed in the participant list, if any.
+    expected_position: Option<usize>,
 }
 
 impl<'a> PartyAwaitingPosition<'a> {
@@ -90,41 +252,61 @@ impl<'a> PartyAwaitingPosition<'a> {
         self,
         j: usize,
         rng: &mut T,
+    ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
+        let a_blinding = Scalar::random(rng);
+        let s_blinding = Scalar::random(rng);
+        self.assign_position_with_blindings(j, a_blinding, s_blinding, rng)
+    }
+
+    /// Assigns a position in the aggregated proof to this party, with
+    /// the given blinding factors of the bit commitments, drawing the
+    /// blinding vectors from `rng`.
+    pub(crate) fn assign_position_with_blindings<T: RngCore + CryptoRng>(
+        self,
+        j: usize,
+        a_blinding: Scalar,
+        s_blinding: Scalar,
+        rng: &mut T,
     ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
         if self.bp_gens.party_capacity <= j {
             return Err(MPCError::InvalidGeneratorsLength);
         }
+        if self
+            .expected_position
+            .map_or(false, |position| position != j)
+        {
+            return Err(MPCError::ParticipantMismatch);
+        }
 
         let bp_share = self.bp_gens.share(j);
+        let padded_n = self.padded_n;
 
-        let a_blinding = Scalar::random(rng);
         // Compute A = <a_L, G> + <a_R, H> + a_blinding * B_blinding
         let mut A = self.pc_gens.B_blinding * a_blinding;
 
         use subtle::Choice;
         use subtle::ConditionallySelectable;
         let mut i = 0;
-        for (G_i, H_i) in bp_share.G(self.n).zip(bp_share.H(self.n)) {
+        for (G_i, H_i) in bp_share.G(padded_n).zip(bp_share.H(padded_n)) {
             // If v_i = 0, we add a_L[i] * G[i] + a_R[i] * H[i] = - H[i]
             // If v_i = 1, we add a_L[i] * G[i] + a_R[i] * H[i] =   G[i]
-            let v_i = Choice::from(((self.v >> i) & 1) as u8);
+            let v_i = Choice::from(bit(&self.v, i));
             let mut point = -H_i;
             point = RistrettoPoint::conditional_select(&point, G_i, v_i);
             A += point;
             i += 1;
         }
 
-        let s_blinding = Scalar::random(rng);
-        let s_L: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(rng)).collect();
-        let s_R: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(rng)).collect();
+        let s_L: Vec<Scalar> = (0..padded_n).map(|_| Scalar::random(rng)).collect();
+        let s_R: Vec<Scalar> = (0..padded_n).map(|_| Scalar::random(rng)).collect();
 
         // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
         use curve25519_dalek::traits::MultiscalarMul;
         let S = RistrettoPoint::multiscalar_mul(
             iter::once(&s_blinding).chain(s_L.iter()).chain(s_R.iter()),
             iter::once(&self.pc_gens.B_blinding)
-                .chain(bp_share.G(self.n))
-                .chain(bp_share.H(self.n)),
+                .chain(bp_share.G(padded_n))
+                .chain(bp_share.H(padded_n)),
         );
 
         // Return next state and all commitments
@@ -135,6 +317,7 @@ impl<'a> PartyAwaitingPosition<'a> {
         };
         let next_state = PartyAwaitingBitChallenge {
             n: self.n,
+            padded_n,
             v: self.v,
             v_blinding: self.v_blinding,
             pc_gens: self.pc_gens,
@@ -152,8 +335,9 @@ impl<'a> PartyAwaitingPosition<'a> {
 /// and is waiting for the aggregated value challenge from the dealer.
 #[derive(ZeroizeOnDrop)]
 pub struct PartyAwaitingBitChallenge<'a> {
-    n: usize, // bitsize of the range
-    v: u64,
+    n: usize,        // bitsize of the range
+    padded_n: usize, // bitsize of the bit vectors
+    v: Scalar,
     v_blinding: Scalar,
     j: usize,
     #[zeroize(skip)]
@@ -182,7 +366,21 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
         vc: &BitChallenge,
         rng: &mut T,
     ) -> (PartyAwaitingPolyChallenge, PolyCommitment) {
-        let n = self.n;
+        let t_1_blinding = Scalar::random(rng);
+        let t_2_blinding = Scalar::random(rng);
+        self.apply_challenge_with_blindings(vc, t_1_blinding, t_2_blinding)
+    }
+
+    /// Receive a [`BitChallenge`] from the dealer and use it to
+    /// compute commitments to the party's polynomial coefficients, with
+    /// the given blinding factors.
+    pub(crate) fn apply_challenge_with_blindings(
+        self,
+        vc: &BitChallenge,
+        t_1_blinding: Scalar,
+        t_2_blinding: Scalar,
+    ) -> (PartyAwaitingPolyChallenge, PolyCommitment) {
+        let n = self.padded_n;
         let offset_y = util::scalar_exp_vartime(&vc.y, (self.j * n) as u64);
         let offset_z = util::scalar_exp_vartime(&vc.z, self.j as u64);
 
@@ -192,9 +390,9 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
 
         let offset_zz = vc.z * vc.z * offset_z;
         let mut exp_y = offset_y; // start at y^j
-        let mut exp_2 = Scalar::ONE; // start at 2^0 = 1
-        for i in 0..n {
-            let a_L_i = Scalar::from((self.v >> i) & 1);
+        let powers_of_2 = util::padded_powers_of_2(self.n, n);
+        for (i, exp_2) in powers_of_2.enumerate() {
+            let a_L_i = Scalar::from(bit(&self.v, i));
             let a_R_i = a_L_i - Scalar::ONE;
 
             l_poly.0[i] = a_L_i - vc.z;
@@ -203,14 +401,11 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
             r_poly.1[i] = exp_y * self.s_R[i];
 
             exp_y *= vc.y; // y^i -> y^(i+1)
-            exp_2 = exp_2 + exp_2; // 2^i -> 2^(i+1)
         }
 
         let t_poly = l_poly.inner_product(&r_poly);
 
         // Generate x by committing to T_1, T_2 (line 49-54)
-        let t_1_blinding = Scalar::random(rng);
-        let t_2_blinding = Scalar::random(rng);
         let T_1 = self.pc_gens.commit(t_poly.1, t_1_blinding);
         let T_2 = self.pc_gens.commit(t_poly.2, t_2_blinding);
 
@@ -220,6 +415,7 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
         };
 
         let papc = PartyAwaitingPolyChallenge {
+            j: self.j,
             v_blinding: self.v_blinding,
             a_blinding: self.a_blinding,
             s_blinding: self.s_blinding,
@@ -233,12 +429,30 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
 
         (papc, poly_commitment)
     }
+
+    /// Returns the party's position in the aggregation.
+    pub(crate) fn position(&self) -> usize {
+        self.j
+    }
+
+    /// Records the party's bit vectors \\(\mathbf{a}\_L, \mathbf{a}\_R\\).
+    ///
+    /// This leaks the witness, see the [`trace`](super::trace) module.
+    #[cfg(feature = "prover-introspection")]
+    pub(crate) fn trace(&self) -> BitTrace {
+        let a_L: Vec<Scalar> = (0..self.padded_n)
+            .map(|i| Scalar::from(bit(&self.v, i)))
+            .collect();
+        let a_R = a_L.iter().map(|a_L_i| a_L_i - Scalar::ONE).collect();
+        BitTrace { a_L, a_R }
+    }
 }
 
 /// A party which has committed to their polynomial coefficents
 /// and is waiting for the polynomial challenge from the dealer.
 #[derive(ZeroizeOnDrop)]
 pub struct PartyAwaitingPolyChallenge {
+    j: usize,
     offset_zz: Scalar,
     l_poly: util::VecPoly1,
     r_poly: util::VecPoly1,
@@ -251,6 +465,11 @@ pub struct PartyAwaitingPolyChallenge {
 }
 
 impl PartyAwaitingPolyChallenge {
+    /// Returns the party's position in the aggregation.
+    pub(crate) fn position(&self) -> usize {
+        self.j
+    }
+
     /// Receive a [`PolyChallenge`] from the dealer and compute the
     /// party's proof share.
     pub fn apply_challenge(self, pc: &PolyChallenge) -> Result<ProofShare, MPCError> {
@@ -280,4 +499,132 @@ impl PartyAwaitingPolyChallenge {
             r_vec,
         })
     }
-}
\ No newline at end of file
+
+    /// Records the party's polynomials and the openings of its
+    /// \\(T\_1, T\_2\\) commitments.
+    ///
+    /// This leaks the witness, see the [`trace`](super::trace) module.
+    #[cfg(feature = "prover-introspection")]
+    pub(crate) fn trace(&self) -> PolyTrace {
+        PolyTrace {
+            l_poly: (self.l_poly.0.clone(), self.l_poly.1.clone()),
+            r_poly: (self.r_poly.0.clone(), self.r_poly.1.clone()),
+            t_poly: (self.t_poly.0, self.t_poly.1, self.t_poly.2),
+            t_1_blinding: self.t_1_blinding,
+            t_2_blinding: self.t_2_blinding,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::mem::ManuallyDrop;
+    use core::ptr;
+
+    /// Runs the destructor of `state` in place, keeping its storage alive,
+    /// and returns the values read back from `secrets` afterwards.
+    fn drop_and_read<T>(state: &mut ManuallyDrop<T>, secrets: &[*const Scalar]) -> Vec<Scalar> {
+        // The secrets point into `state`, which is only dropped in
+        // place, so they remain valid to read.
+        unsafe {
+            ptr::drop_in_place(&mut **state);
+            secrets.iter().map(|&s| ptr::read_volatile(s)).collect()
+        }
+    }
+
+    #[test]
+    fn dropped_states_clear_their_secrets() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+        let v_blinding = Scalar::random(&mut rng);
+
+        let mut party =
+            ManuallyDrop::new(Party::new(&bp_gens, &pc_gens, 7, v_blinding, 32).unwrap());
+        assert_eq!(party.v, Scalar::from(7u64));
+        let secrets: [*const Scalar; 2] = [&party.v, &party.v_blinding];
+        assert_eq!(drop_and_read(&mut party, &secrets), [Scalar::ZERO; 2]);
+
+        let party = Party::new(&bp_gens, &pc_gens, 7, v_blinding, 32).unwrap();
+        let (party, _) = party.assign_position_with_rng(0, &mut rng).unwrap();
+        let mut party = ManuallyDrop::new(party);
+        let secrets: [*const Scalar; 4] = [
+            &party.v,
+            &party.v_blinding,
+            &party.a_blinding,
+            &party.s_blinding,
+        ];
+        assert_eq!(drop_and_read(&mut party, &secrets), [Scalar::ZERO; 4]);
+
+        let party = Party::new(&bp_gens, &pc_gens, 7, v_blinding, 32).unwrap();
+        let (party, _) = party.assign_position_with_rng(0, &mut rng).unwrap();
+        let bit_challenge = BitChallenge {
+            y: Scalar::random(&mut rng),
+            z: Scalar::random(&mut rng),
+        };
+        let (party, _) = party.apply_challenge_with_rng(&bit_challenge, &mut rng);
+        let mut party = ManuallyDrop::new(party);
+        let secrets: [*const Scalar; 5] = [
+            &party.v_blinding,
+            &party.a_blinding,
+            &party.s_blinding,
+            &party.t_1_blinding,
+            &party.t_2_blinding,
+        ];
+        assert_eq!(drop_and_read(&mut party, &secrets), [Scalar::ZERO; 5]);
+    }
+
+    #[test]
+    fn scalar_values_commit_like_u64_values() {
+        use crate::errors::ProofError;
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 1);
+        let v_blinding = Scalar::from(5u64);
+
+        for (v, n) in [(0u64, 8), (255, 8), (1 << 40, 64), (u64::MAX, 64)] {
+            let party = Party::new(&bp_gens, &pc_gens, v, v_blinding, n).unwrap();
+            let (_, expected) = party
+                .assign_position_with_rng(0, &mut ChaChaRng::from_seed([1u8; 32]))
+                .unwrap();
+            let party =
+                Party::new_from_scalar(&bp_gens, &pc_gens, Scalar::from(v), v_blinding, n).unwrap();
+            let (_, commitment) = party
+                .assign_position_with_rng(0, &mut ChaChaRng::from_seed([1u8; 32]))
+                .unwrap();
+            assert_eq!(commitment.to_bytes(), expected.to_bytes());
+        }
+
+        // Values past u64 are accepted as long as they fit in n bits.
+        let big = Scalar::from(u64::MAX) + Scalar::ONE;
+        assert!(Party::new_from_scalar(&bp_gens, &pc_gens, big, v_blinding, 128).is_ok());
+
+        for (v, n) in [(Scalar::from(256u64), 8), (big, 64), (-Scalar::ONE, 128)] {
+            let result = Party::new_from_scalar(&bp_gens, &pc_gens, v, v_blinding, n);
+            assert_eq!(result.err(), Some(MPCError::ValueOutOfRange));
+        }
+
+        // The u64 constructors are checked the same way.
+        for (v, n) in [(256u64, 8), (1 << 13, 13), (1 << 32, 32)] {
+            let result = Party::new(&bp_gens, &pc_gens, v, v_blinding, n);
+            assert_eq!(result.err(), Some(MPCError::ValueOutOfRange));
+        }
+        assert_eq!(
+            Party::new_padded(&bp_gens, &pc_gens, 256, v_blinding, 8, 64).err(),
+            Some(MPCError::ValueOutOfRange)
+        );
+        assert!(Party::new_padded(&bp_gens, &pc_gens, 255, v_blinding, 8, 64).is_ok());
+        assert_eq!(
+            ProofError::from(MPCError::ValueOutOfRange),
+            ProofError::ValueOutOfRange
+        );
+        assert_eq!(
+            Party::new_from_scalar(&bp_gens, &pc_gens, Scalar::ONE, v_blinding, 0).err(),
+            Some(MPCError::InvalidBitsize)
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Rewindable range proofs, from which the holder of a rewind key can
+//! recover the value and its blinding factor.
+//!
+//! The prover derives all of its randomness from a
+//! [`TranscriptRng`](merlin::TranscriptRng) keyed by the transcript
+//! state, the rewind key, the value commitment \\(V\\), the bitsize
+//! and the label of the generators, and adds
+//! the value \\(v\\) to the derived blinding factor \\(\alpha'\\) of
+//! \\(A\\), so that \\(\alpha = \alpha' + v\\).  The proof contains
+//! $$
+//! \begin{aligned}
+//! \mu &= \alpha + \rho x, \\\\
+//! \tau\_x &= \tau\_2 x^2 + \tau\_1 x + z^2 \gamma,
+//! \end{aligned}
+//! $$
+//! and the key holder, who can derive \\(\alpha', \rho, \tau\_1,
+//! \tau\_2\\) and replay the transcript to get \\(x\\) and \\(z\\),
+//! solves for \\(v\\) and the blinding factor \\(\gamma\\).
+//!
+//! Without the key, the derived scalars are indistinguishable from
+//! random ones, so rewindable proofs look like any other proof and
+//! verify with [`RangeProof::verify_single_with_rng`].  The same
+//! inputs always produce the same proof, and every input which the
+//! challenges depend on is bound into the nonces, so proving twice
+//! never reuses the nonces with different challenges.
+
+#![allow(non_snake_case)]
+
+extern crate alloc;
+
+use alloc::vec;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::{Transcript, TranscriptRng};
+use zeroize::ZeroizeOnDrop;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::transcript::TranscriptProtocol;
+use crate::util;
+
+use super::dealer::Dealer;
+use super::party::Party;
+use super::seeded::NullRng;
+use super::RangeProof;
+
+/// The blinding factors derived from a rewind key.
+#[derive(ZeroizeOnDrop)]
+struct RewindNonces {
+    /// The blinding factor \\(\alpha'\\) of \\(A\\), before adding
+    /// the value.
+    alpha: Scalar,
+    rho: Scalar,
+    tau_1: Scalar,
+    tau_2: Scalar,
+}
+
+/// Returns the RNG of a rewindable proof of bitsize `n` for the
+/// commitment `V`, and the blinding factors drawn first from it.
+///
+/// Besides the transcript and `V`, the challenges of the proof depend
+/// on `n`, the aggregation size and the generators, so they are all
+/// bound into the RNG: otherwise proofs of the same `V` for different
+/// bitsizes or generators would reuse the nonces with different
+/// challenges, and reveal the blinding factor.
+fn rewind_rng(
+    bp_gens: &BulletproofGens,
+    transcript: &Transcript,
+    V: &CompressedRistretto,
+    n: usize,
+    rewind_key: &[u8; 32],
+) -> (RewindNonces, TranscriptRng) {
+    let builder = transcript
+        .build_rng()
+        .rekey_with_witness_bytes(b"rewind_key", rewind_key)
+        .rekey_with_witness_bytes(b"V", V.as_bytes())
+        .rekey_with_witness_bytes(b"n", &(n as u64).to_le_bytes())
+        .rekey_with_witness_bytes(b"m", &1u64.to_le_bytes());
+    let builder = match bp_gens.label() {
+        None => builder.rekey_with_witness_bytes(b"default_gens", &[]),
+        Some(label) => builder.rekey_with_witness_bytes(b"gens_label", label),
+    };
+    let mut rng = builder.finalize(&mut NullRng);
+    let nonces = RewindNonces {
+        alpha: Scalar::random(&mut rng),
+        rho: Scalar::random(&mut rng),
+        tau_1: Scalar::random(&mut rng),
+        tau_2: Scalar::random(&mut rng),
+    };
+    (nonces, rng)
+}
+
+impl RangeProof {
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, from which the holder of `rewind_key` can
+    /// recover them with [`RangeProof::rewind_single`].
+    ///
+    /// The proof verifies like one created by
+    /// [`RangeProof::prove_single_with_rng`].  All of the prover's
+    /// randomness is derived from `rewind_key`, the commitment and the
+    /// state of `transcript`, so the key must be kept secret.
+    pub fn prove_single_rewindable(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+        rewind_key: &[u8; 32],
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let V = pc_gens.commit(Scalar::from(v), *v_blinding).compress();
+        let (nonces, mut rng) = rewind_rng(bp_gens, transcript, &V, n, rewind_key);
+
+        let party = Party::new(bp_gens, pc_gens, v, *v_blinding, n)?;
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, 1)?;
+
+        let (party, bit_commitment) = party.assign_position_with_blindings(
+            0,
+            nonces.alpha + Scalar::from(v),
+            nonces.rho,
+            &mut rng,
+        )?;
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(vec![bit_commitment])?;
+
+        let (party, poly_commitment) =
+            party.apply_challenge_with_blindings(&bit_challenge, nonces.tau_1, nonces.tau_2);
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(vec![poly_commitment])?;
+
+        let proof_share = party.apply_challenge(&poly_challenge)?;
+        let proof = dealer.receive_trusted_shares(&[proof_share])?;
+
+        Ok((proof, V))
+    }
+
+    /// Recovers the value and blinding factor of the commitment `V`
+    /// from a proof created by [`RangeProof::prove_single_rewindable`]
+    /// with `rewind_key`.
+    ///
+    /// `transcript` must be in the state the prover's transcript was
+    /// in, and is not modified.  This does not verify the proof.
+    /// Returns [`ProofError::RewindError`] if the proof was not created
+    /// with `rewind_key` for `V`.
+    pub fn rewind_single(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+        rewind_key: &[u8; 32],
+    ) -> Result<(u64, Scalar), ProofError> {
+        let padded_n = util::padded_bitsize(n).ok_or(ProofError::InvalidBitsize)?;
+        if bp_gens.gens_capacity < padded_n {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let (nonces, _) = rewind_rng(bp_gens, transcript, V, n, rewind_key);
+
+        // Replay the transcript of the proof to recompute z and x.
+        let mut transcript = transcript.clone();
+        transcript.rangeproof_domain_sep(n as u64, 1);
+        transcript.append_point(b"V", V);
+        transcript.validate_and_append_point(b"A", &self.A)?;
+        transcript.validate_and_append_point(b"S", &self.S)?;
+        let _y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        transcript.validate_and_append_point(b"T_1", &self.T_1)?;
+        transcript.validate_and_append_point(b"T_2", &self.T_2)?;
+        let x = transcript.challenge_scalar(b"x");
+
+        let v = self.e_blinding - nonces.rho * x - nonces.alpha;
+        let v_blinding =
+            (self.t_x_blinding - (nonces.tau_2 * x + nonces.tau_1) * x) * (z * z).invert();
+
+        // With the wrong key, v is a random scalar, which almost never
+        // fits in 64 bits, and otherwise fails to open V.
+        let bytes = v.to_bytes();
+        if bytes[8..].iter().any(|&b| b != 0) {
+            return Err(ProofError::RewindError);
+        }
+        let mut v_bytes = [0u8; 8];
+        v_bytes.copy_from_slice(&bytes[..8]);
+        let v = u64::from_le_bytes(v_bytes);
+        if pc_gens.commit(Scalar::from(v), v_blinding).compress() != *V {
+            return Err(ProofError::RewindError);
+        }
+        Ok((v, v_blinding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prove(v: u64, v_blinding: &Scalar, key: &[u8; 32]) -> (RangeProof, CompressedRistretto) {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut transcript = Transcript::new(b"RewindTest");
+        RangeProof::prove_single_rewindable(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            v,
+            v_blinding,
+            64,
+            key,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rewindable_proofs_rewind_and_verify() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let key = [7u8; 32];
+
+        for v in [0, 1, 1037578891, u64::MAX] {
+            let v_blinding = Scalar::random(&mut rand::thread_rng());
+            let (proof, V) = prove(v, &v_blinding, &key);
+
+            let transcript = Transcript::new(b"RewindTest");
+            assert_eq!(
+                proof.rewind_single(&bp_gens, &pc_gens, &transcript, &V, 64, &key),
+                Ok((v, v_blinding))
+            );
+
+            let mut transcript = Transcript::new(b"RewindTest");
+            assert_eq!(
+                proof.verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 64),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn rewinding_needs_the_key_and_transcript() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let key = [7u8; 32];
+        let v_blinding = Scalar::random(&mut rand::thread_rng());
+        let (proof, V) = prove(42, &v_blinding, &key);
+
+        let transcript = Transcript::new(b"RewindTest");
+        assert_eq!(
+            proof.rewind_single(&bp_gens, &pc_gens, &transcript, &V, 64, &[8u8; 32]),
+            Err(ProofError::RewindError)
+        );
+        let transcript = Transcript::new(b"OtherTranscript");
+        assert_eq!(
+            proof.rewind_single(&bp_gens, &pc_gens, &transcript, &V, 64, &key),
+            Err(ProofError::RewindError)
+        );
+
+        // Ordinary proofs cannot be rewound.
+        let mut transcript = Transcript::new(b"RewindTest");
+        let (proof, V) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 42, &v_blinding, 64)
+                .unwrap();
+        let transcript = Transcript::new(b"RewindTest");
+        assert_eq!(
+            proof.rewind_single(&bp_gens, &pc_gens, &transcript, &V, 64, &key),
+            Err(ProofError::RewindError)
+        );
+    }
+
+    #[test]
+    fn rewindable_proofs_are_deterministic() {
+        let v_blinding = Scalar::from(1234u64);
+        let (proof, V) = prove(42, &v_blinding, &[7u8; 32]);
+        let (same_proof, same_V) = prove(42, &v_blinding, &[7u8; 32]);
+        assert_eq!(proof.to_bytes(), same_proof.to_bytes());
+        assert_eq!(V, same_V);
+
+        let (other_proof, _) = prove(42, &v_blinding, &[8u8; 32]);
+        assert_ne!(proof.to_bytes(), other_proof.to_bytes());
+    }
+
+    #[test]
+    fn nonces_depend_on_bitsize_and_generators() {
+        let pc_gens = PedersenGens::default();
+        let key = [7u8; 32];
+        let v_blinding = Scalar::from(1234u64);
+        let prove_with = |bp_gens: &BulletproofGens, n: usize| {
+            let mut transcript = Transcript::new(b"RewindTest");
+            let (proof, V) = RangeProof::prove_single_rewindable(
+                bp_gens,
+                &pc_gens,
+                &mut transcript,
+                42,
+                &v_blinding,
+                n,
+                &key,
+            )
+            .unwrap();
+            let transcript = Transcript::new(b"RewindTest");
+            assert_eq!(
+                proof.rewind_single(bp_gens, &pc_gens, &transcript, &V, n, &key),
+                Ok((42, v_blinding))
+            );
+            (proof, V)
+        };
+
+        let bp_gens = BulletproofGens::new(64, 1);
+        let (proof_32, V_32) = prove_with(&bp_gens, 32);
+        let (proof_64, V_64) = prove_with(&bp_gens, 64);
+        assert_eq!(V_32, V_64);
+        assert_ne!(proof_32.A, proof_64.A);
+
+        let labelled = BulletproofGens::new_with_label(b"RewindTest", 64, 1);
+        let (labelled_proof, _) = prove_with(&labelled, 64);
+        assert_ne!(labelled_proof.A, proof_64.A);
+    }
+}
@@ -0,0 +1,467 @@
+//! Range proofs whose randomness is derived from a caller-supplied
+//! seed or from the witness instead of an RNG.
+//!
+//! The prover's blinding factors are drawn from a
+//! [`TranscriptRng`](merlin::TranscriptRng), keyed by the transcript
+//! state and either the seed or the values and their blindings, or
+//! from a [`ChaChaRng`] seeded with a fixed 32-byte seed.  These are
+//! the only source of entropy, so proving is fully deterministic and
+//! needs no `RngCore`.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::{Transcript, TranscriptRngBuilder};
+use rand_chacha::ChaChaRng;
+use rand_core::{CryptoRng, RngCore, SeedableRng};
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+
+use super::RangeProof;
+
+/// The minimum length of a seed, in bytes.
+const MIN_SEED_LEN: usize = 32;
+
+/// An RNG which contributes no entropy, used to finalize the
+/// [`TranscriptRng`](merlin::TranscriptRng) when the seed is the sole
+/// source of entropy.
+pub(super) struct NullRng;
+
+impl RngCore for NullRng {
+    fn next_u32(&mut self) -> u32 {
+        0
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        0
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.iter_mut().for_each(|b| *b = 0);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// The output is only ever mixed into a transcript keyed by the seed.
+impl CryptoRng for NullRng {}
+
+/// Returns a [`TranscriptRngBuilder`] keyed by the state of
+/// `transcript`, the witness, and every other input which the
+/// challenges of the proof depend on: the bitsize, the number of
+/// values, the value commitments and the label of the generators.
+///
+/// Leaving any of them out would let two proofs of the same witness
+/// reuse the nonces with different challenges, revealing the values
+/// and their blinding factors.
+fn witness_rng_builder(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &Transcript,
+    values: &[u64],
+    blindings: &[Scalar],
+    n: usize,
+) -> TranscriptRngBuilder {
+    let mut builder = transcript
+        .build_rng()
+        .rekey_with_witness_bytes(b"n", &(n as u64).to_le_bytes())
+        .rekey_with_witness_bytes(b"m", &(values.len() as u64).to_le_bytes());
+    builder = match bp_gens.label() {
+        None => builder.rekey_with_witness_bytes(b"default_gens", &[]),
+        Some(label) => builder.rekey_with_witness_bytes(b"gens_label", label),
+    };
+    for (v, v_blinding) in values.iter().zip(blindings) {
+        let commitment = pc_gens.commit(Scalar::from(*v), *v_blinding).compress();
+        builder = builder
+            .rekey_with_witness_bytes(b"V", commitment.as_bytes())
+            .rekey_with_witness_bytes(b"v", &v.to_le_bytes())
+            .rekey_with_witness_bytes(b"v_blinding", v_blinding.as_bytes());
+    }
+    builder
+}
+
+impl RangeProof {
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, deriving all of the prover's randomness
+    /// from `seed`.
+    ///
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_seeded`].
+    pub fn prove_single_seeded(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+        seed: &[u8],
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let (p, Vs) = RangeProof::prove_multiple_seeded(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v],
+            &[*v_blinding],
+            n,
+            seed,
+        )?;
+        Ok((p, Vs[0]))
+    }
+
+    /// Create a rangeproof for a set of values, deriving all of the
+    /// prover's randomness from `seed` and the state of `transcript`,
+    /// without using an RNG.
+    ///
+    /// The same inputs always produce the same proof.  Returns
+    /// [`ProofError::InsufficientSeed`] if `seed` is shorter than 32
+    /// bytes.
+    ///
+    /// # Warning
+    ///
+    /// The seed must be uniformly random and **unique to each proof**.
+    /// Reusing a seed with the same transcript state for different
+    /// values reuses the blinding factors of the bit commitments and
+    /// polynomials, which lets anyone seeing both proofs solve for the
+    /// values and their blinding factors.
+    pub fn prove_multiple_seeded(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        seed: &[u8],
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        if seed.len() < MIN_SEED_LEN {
+            return Err(ProofError::InsufficientSeed);
+        }
+
+        let mut rng = transcript
+            .build_rng()
+            .rekey_with_witness_bytes(b"seed", seed)
+            .finalize(&mut NullRng);
+
+        RangeProof::prove_multiple_with_rng(
+            bp_gens, pc_gens, transcript, values, blindings, n, &mut rng,
+        )
+    }
+
+    /// Create a rangeproof for a set of values, drawing all of the
+    /// prover's randomness from a [`ChaChaRng`] seeded with `seed`.
+    ///
+    /// This is [`RangeProof::prove_multiple_with_rng`] with a fixed RNG,
+    /// so the same inputs produce byte-for-byte the same proof on every
+    /// platform, and another implementation can reproduce it from the
+    /// seed alone.  It is meant for generating test vectors.
+    ///
+    /// The seeded RNG is not used by the parties directly: party `j`
+    /// draws from a [`ChaChaRng`] seeded with the `j`-th 32-byte block
+    /// of its output, once when committing to its bits and once more
+    /// when committing to its polynomial.  This is the same with and
+    /// without the `rayon` feature.
+    ///
+    /// # Warning
+    ///
+    /// Unlike [`RangeProof::prove_multiple_seeded`], the randomness
+    /// does not depend on the transcript or the values: reusing a seed
+    /// for different values lets anyone seeing both proofs solve for
+    /// the values and their blinding factors.
+    pub fn prove_multiple_from_seed(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        seed: [u8; 32],
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut ChaChaRng::from_seed(seed),
+        )
+    }
+
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, drawing the prover's randomness from a
+    /// [`TranscriptRng`](merlin::TranscriptRng) finalized with `seed`.
+    ///
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_with_transcript_rng`].
+    pub fn prove_single_with_transcript_rng(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+        seed: &[u8; 32],
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let (p, Vs) = RangeProof::prove_multiple_with_transcript_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v],
+            &[*v_blinding],
+            n,
+            seed,
+        )?;
+        Ok((p, Vs[0]))
+    }
+
+    /// Create a rangeproof for a set of values, drawing the prover's
+    /// randomness from a [`TranscriptRng`](merlin::TranscriptRng) keyed
+    /// by the state of `transcript`, the bitsize, the generators, the
+    /// values, their commitments and their blinding factors, and
+    /// finalized with a [`ChaChaRng`] seeded with `seed`.
+    ///
+    /// This needs no external RNG at call time: two calls with the
+    /// same seed, transcript state and witness produce bit-for-bit
+    /// identical proofs.
+    ///
+    /// # Warning
+    ///
+    /// The seed must be uniformly random and kept secret.  Anyone who
+    /// knows it can recompute the prover's blinding factors from the
+    /// witness and the transcript.
+    pub fn prove_multiple_with_transcript_rng(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        seed: &[u8; 32],
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+
+        let mut rng = witness_rng_builder(bp_gens, pc_gens, transcript, values, blindings, n)
+            .finalize(&mut ChaChaRng::from_seed(*seed));
+
+        RangeProof::prove_multiple_with_rng(
+            bp_gens, pc_gens, transcript, values, blindings, n, &mut rng,
+        )
+    }
+
+    /// Create a rangeproof for a set of values, deriving all of the
+    /// prover's randomness from the state of `transcript`, the bitsize,
+    /// the generators, the values, their commitments and their blinding
+    /// factors, without using an RNG.
+    ///
+    /// The same inputs always produce the same proof, which verifies
+    /// with [`RangeProof::verify_multiple`].  The secret blinding
+    /// factors provide the entropy, so they must be uniformly random.
+    pub fn prove_multiple_deterministic(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+
+        let mut rng = witness_rng_builder(bp_gens, pc_gens, transcript, values, blindings, n)
+            .finalize(&mut NullRng);
+
+        RangeProof::prove_multiple_with_rng(
+            bp_gens, pc_gens, transcript, values, blindings, n, &mut rng,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prove(seed: &[u8]) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut transcript = Transcript::new(b"SeededRangeProofTest");
+        RangeProof::prove_single_seeded(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1037578891,
+            &Scalar::from(7u64),
+            32,
+            seed,
+        )
+    }
+
+    #[test]
+    fn seeded_proofs_are_deterministic() {
+        let (proof, V) = prove(&[1u8; 32]).unwrap();
+        let (again, _) = prove(&[1u8; 32]).unwrap();
+        assert_eq!(proof.to_bytes(), again.to_bytes());
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut transcript = Transcript::new(b"SeededRangeProofTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 32)
+            .is_ok());
+    }
+
+    #[test]
+    fn distinct_seeds_give_distinct_proofs() {
+        let (proof, _) = prove(&[1u8; 32]).unwrap();
+        let (other, _) = prove(&[2u8; 32]).unwrap();
+        assert_ne!(proof.to_bytes(), other.to_bytes());
+
+        let (longer, _) = prove(&[1u8; 33]).unwrap();
+        assert_ne!(proof.to_bytes(), longer.to_bytes());
+    }
+
+    #[test]
+    fn short_seed_is_rejected() {
+        assert_eq!(prove(&[1u8; 31]).unwrap_err(), ProofError::InsufficientSeed);
+    }
+
+    #[test]
+    fn transcript_rng_proofs_are_deterministic() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let prove = |v: u64, seed: &[u8; 32]| {
+            let mut transcript = Transcript::new(b"TranscriptRngTest");
+            RangeProof::prove_single_with_transcript_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                v,
+                &Scalar::from(9u64),
+                32,
+                seed,
+            )
+            .unwrap()
+        };
+
+        let (proof, V) = prove(1037578891, &[5u8; 32]);
+        assert_eq!(proof.to_bytes(), prove(1037578891, &[5u8; 32]).0.to_bytes());
+        assert_ne!(proof.to_bytes(), prove(1037578891, &[6u8; 32]).0.to_bytes());
+        assert_ne!(proof.to_bytes(), prove(1037578890, &[5u8; 32]).0.to_bytes());
+
+        let mut transcript = Transcript::new(b"TranscriptRngTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 32)
+            .is_ok());
+    }
+
+    #[test]
+    fn proofs_from_seed_match_chacha_prover() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let blindings = [Scalar::from(11u64), Scalar::from(12u64)];
+        let prove = |seed: [u8; 32]| {
+            let mut transcript = Transcript::new(b"SeedRangeProofTest");
+            RangeProof::prove_multiple_from_seed(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &[5, 1037578891],
+                &blindings,
+                32,
+                seed,
+            )
+            .unwrap()
+        };
+
+        let (proof, commitments) = prove([3u8; 32]);
+        assert_eq!(proof.to_bytes(), prove([3u8; 32]).0.to_bytes());
+        assert_ne!(proof.to_bytes(), prove([4u8; 32]).0.to_bytes());
+
+        let mut transcript = Transcript::new(b"SeedRangeProofTest");
+        let (expected, _) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[5, 1037578891],
+            &blindings,
+            32,
+            &mut ChaChaRng::from_seed([3u8; 32]),
+        )
+        .unwrap();
+        assert_eq!(proof.to_bytes(), expected.to_bytes());
+
+        let mut transcript = Transcript::new(b"SeedRangeProofTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+    }
+
+    fn prove_deterministic(blindings: &[Scalar]) -> (RangeProof, Vec<CompressedRistretto>) {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut transcript = Transcript::new(b"DeterministicRangeProofTest");
+        RangeProof::prove_multiple_deterministic(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[5, 1037578891],
+            blindings,
+            32,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn deterministic_proofs_are_reproducible() {
+        let blindings = [Scalar::from(11u64), Scalar::from(12u64)];
+        let (proof, commitments) = prove_deterministic(&blindings);
+        let (again, _) = prove_deterministic(&blindings);
+        assert_eq!(proof.to_bytes(), again.to_bytes());
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut transcript = Transcript::new(b"DeterministicRangeProofTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+    }
+
+    #[test]
+    fn different_blindings_give_different_proofs() {
+        let (proof, _) = prove_deterministic(&[Scalar::from(11u64), Scalar::from(12u64)]);
+        let (other, _) = prove_deterministic(&[Scalar::from(11u64), Scalar::from(13u64)]);
+        assert_ne!(proof.to_bytes(), other.to_bytes());
+    }
+
+    #[test]
+    fn nonces_depend_on_bitsize_and_generators() {
+        let pc_gens = PedersenGens::default();
+        let blindings = [Scalar::from(11u64), Scalar::from(12u64)];
+        let prove = |bp_gens: &BulletproofGens, n: usize| {
+            let mut transcript = Transcript::new(b"DeterministicRangeProofTest");
+            RangeProof::prove_multiple_deterministic(
+                bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &[5, 7],
+                &blindings,
+                n,
+            )
+            .unwrap()
+            .0
+        };
+
+        let bp_gens = BulletproofGens::new(64, 2);
+        let proof_32 = prove(&bp_gens, 32);
+        let proof_64 = prove(&bp_gens, 64);
+        assert_ne!(proof_32.A, proof_64.A);
+
+        let labelled = BulletproofGens::new_with_label(b"DeterministicTest", 64, 2);
+        assert_ne!(prove(&labelled, 64).A, proof_64.A);
+    }
+}
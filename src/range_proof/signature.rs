@@ -0,0 +1,367 @@
+//! The `signature` module contains a Schnorr [`Signature`] over
+//! Ristretto which shares its Fiat-Shamir transcript with a range
+//! proof, as in transaction formats which bind both into one
+//! transcript.
+//!
+//! The prover and the verifier must absorb the range proof and the
+//! signature in the same [`SignatureOrder`].  With
+//! [`SignatureOrder::ProofFirst`], the prover proves, then signs with
+//! the same transcript:
+//!
+//! ```
+//! extern crate rand;
+//! use rand::thread_rng;
+//!
+//! extern crate curve25519_dalek;
+//! use curve25519_dalek::scalar::Scalar;
+//!
+//! extern crate merlin;
+//! use merlin::Transcript;
+//!
+//! extern crate bulletproofs;
+//! use bulletproofs::{BulletproofGens, PedersenGens, RangeProof, Signature, SignatureOrder};
+//!
+//! # fn main() {
+//! let pc_gens = PedersenGens::default();
+//! let bp_gens = BulletproofGens::new(64, 1);
+//!
+//! let secret_key = Scalar::random(&mut thread_rng());
+//! let public_key = (secret_key * pc_gens.B).compress();
+//! let blinding = Scalar::random(&mut thread_rng());
+//!
+//! // The prover absorbs the proof, then derives the signature
+//! // challenge from the same transcript.
+//! let mut transcript = Transcript::new(b"signed transaction");
+//! let (proof, commitment) =
+//!     RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 1037578891, &blinding, 64)
+//!         .expect("A real program could handle errors");
+//! let signature = Signature::sign(&mut transcript, &pc_gens, &secret_key);
+//!
+//! // The verifier replays both in the same order.
+//! let mut transcript = Transcript::new(b"signed transaction");
+//! assert!(proof
+//!     .verify_with_signature(
+//!         &bp_gens,
+//!         &pc_gens,
+//!         &mut transcript,
+//!         &[commitment],
+//!         64,
+//!         &signature,
+//!         &public_key,
+//!         SignatureOrder::ProofFirst,
+//!     )
+//!     .is_ok());
+//! # }
+//! ```
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::transcript::TranscriptProtocol;
+use crate::util;
+
+use super::{RangeProof, ValueCommitment};
+
+/// A Schnorr signature over Ristretto, whose challenge is derived from
+/// a shared transcript.
+///
+/// The public key of a secret key \\(x\\) is \\(P = x \cdot B\\),
+/// where \\(B\\) is the value base of the [`PedersenGens`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// The commitment \\(R = r \cdot B\\) to the nonce.
+    pub R: CompressedRistretto,
+    /// The response \\(s = r + c \cdot x\\).
+    pub s: Scalar,
+}
+
+/// The order in which a range proof and a [`Signature`] are absorbed
+/// into their shared transcript.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SignatureOrder {
+    /// The range proof is absorbed first, so the signature challenge
+    /// binds the proof.
+    ProofFirst,
+    /// The signature is absorbed first, so the range proof challenges
+    /// bind the signature.
+    SignatureFirst,
+}
+
+impl Signature {
+    /// Signs the current state of `transcript` with `secret_key`,
+    /// appending the public key and the nonce commitment to it.
+    ///
+    /// The nonce is drawn from a [`TranscriptRng`](merlin::TranscriptRng)
+    /// keyed by the transcript state, the secret key, and `rng`.
+    pub fn sign_with_rng<T: RngCore + CryptoRng>(
+        transcript: &mut Transcript,
+        pc_gens: &PedersenGens,
+        secret_key: &Scalar,
+        rng: &mut T,
+    ) -> Signature {
+        let P = (secret_key * pc_gens.B).compress();
+        transcript.signature_domain_sep();
+        transcript.append_point(b"P", &P);
+
+        let mut nonce_rng = transcript
+            .build_rng()
+            .rekey_with_witness_bytes(b"secret_key", secret_key.as_bytes())
+            .finalize(rng);
+        let mut r = Scalar::random(&mut nonce_rng);
+        let R = (r * pc_gens.B).compress();
+        transcript.append_point(b"R", &R);
+
+        let c = transcript.challenge_scalar(b"c");
+        let s = r + c * secret_key;
+        // Anyone learning the nonce can solve for the secret key.
+        r.zeroize();
+        Signature { R, s }
+    }
+
+    /// Signs the current state of `transcript` with `secret_key`.
+    /// This is a convenience wrapper around [`Signature::sign_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn sign(transcript: &mut Transcript, pc_gens: &PedersenGens, secret_key: &Scalar) -> Self {
+        Signature::sign_with_rng(transcript, pc_gens, secret_key, &mut thread_rng())
+    }
+
+    /// Verifies the signature of `public_key` over the current state of
+    /// `transcript`, appending the same data as the signer.
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        pc_gens: &PedersenGens,
+        public_key: &CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        transcript.signature_domain_sep();
+        transcript.validate_and_append_point(b"P", public_key)?;
+        transcript.append_point(b"R", &self.R);
+        let c = transcript.challenge_scalar(b"c");
+
+        let P = public_key.decompress().ok_or(ProofError::FormatError)?;
+        let R = self.R.decompress().ok_or(ProofError::FormatError)?;
+
+        // s * B - c * P == R
+        let check = RistrettoPoint::vartime_multiscalar_mul(&[self.s, -c], &[pc_gens.B, P]);
+        if check == R {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Serializes the signature into a 64-byte array, the point
+    /// \\(R\\) followed by the scalar \\(s\\).
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(self.R.as_bytes());
+        bytes[32..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    /// Deserializes the signature from a byte slice, see
+    /// [`Signature::to_bytes`] for the layout.
+    ///
+    /// Returns [`ProofError::FormatError`] if the slice is not 64
+    /// bytes long or the scalar is not canonical.
+    pub fn from_bytes(slice: &[u8]) -> Result<Signature, ProofError> {
+        if slice.len() != 64 {
+            return Err(ProofError::FormatError);
+        }
+        let R = CompressedRistretto(util::read32(&slice[..32]));
+        let s = Option::from(Scalar::from_canonical_bytes(util::read32(&slice[32..])))
+            .ok_or(ProofError::FormatError)?;
+        Ok(Signature { R, s })
+    }
+}
+
+impl RangeProof {
+    /// Verifies an aggregated rangeproof and a [`Signature`] of
+    /// `public_key` which share `transcript`, absorbing them in the
+    /// given `order`.
+    ///
+    /// Fails if either the proof or the signature fails to verify.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_with_signature_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+        signature: &Signature,
+        public_key: &CompressedRistretto,
+        order: SignatureOrder,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        match order {
+            SignatureOrder::ProofFirst => {
+                self.verify_multiple_with_rng(
+                    bp_gens,
+                    pc_gens,
+                    transcript,
+                    value_commitments,
+                    n,
+                    rng,
+                )?;
+                signature.verify(transcript, pc_gens, public_key)
+            }
+            SignatureOrder::SignatureFirst => {
+                signature.verify(transcript, pc_gens, public_key)?;
+                self.verify_multiple_with_rng(
+                    bp_gens,
+                    pc_gens,
+                    transcript,
+                    value_commitments,
+                    n,
+                    rng,
+                )
+            }
+        }
+    }
+
+    /// Verifies an aggregated rangeproof and a [`Signature`] sharing a transcript.
+    /// This is a convenience wrapper around [`RangeProof::verify_with_signature_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_with_signature(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[impl ValueCommitment],
+        n: usize,
+        signature: &Signature,
+        public_key: &CompressedRistretto,
+        order: SignatureOrder,
+    ) -> Result<(), ProofError> {
+        self.verify_with_signature_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            signature,
+            public_key,
+            order,
+            &mut thread_rng(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_proof(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        secret_key: &Scalar,
+        order: SignatureOrder,
+    ) -> (RangeProof, Vec<CompressedRistretto>, Signature) {
+        let mut rng = rand::thread_rng();
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+        let mut transcript = Transcript::new(b"SignatureTest");
+
+        let prove = |transcript: &mut Transcript| {
+            RangeProof::prove_multiple(bp_gens, pc_gens, transcript, &[5, 1 << 30], &blindings, 32)
+                .unwrap()
+        };
+        match order {
+            SignatureOrder::ProofFirst => {
+                let (proof, Vs) = prove(&mut transcript);
+                let signature = Signature::sign(&mut transcript, pc_gens, secret_key);
+                (proof, Vs, signature)
+            }
+            SignatureOrder::SignatureFirst => {
+                let signature = Signature::sign(&mut transcript, pc_gens, secret_key);
+                let (proof, Vs) = prove(&mut transcript);
+                (proof, Vs, signature)
+            }
+        }
+    }
+
+    #[test]
+    fn tampering_with_proof_or_signature_fails() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+        let secret_key = Scalar::random(&mut rng);
+        let public_key = (secret_key * pc_gens.B).compress();
+        let other_key = (Scalar::random(&mut rng) * pc_gens.B).compress();
+
+        for &order in &[SignatureOrder::ProofFirst, SignatureOrder::SignatureFirst] {
+            let (proof, Vs, signature) = signed_proof(&bp_gens, &pc_gens, &secret_key, order);
+
+            let verify = |proof: &RangeProof,
+                          Vs: &[CompressedRistretto],
+                          signature: &Signature,
+                          public_key: &CompressedRistretto,
+                          order: SignatureOrder| {
+                let mut transcript = Transcript::new(b"SignatureTest");
+                proof.verify_with_signature(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    Vs,
+                    32,
+                    signature,
+                    public_key,
+                    order,
+                )
+            };
+
+            assert_eq!(verify(&proof, &Vs, &signature, &public_key, order), Ok(()));
+
+            let mut bad_proof = proof.clone();
+            bad_proof.t_x += Scalar::ONE;
+            assert!(verify(&bad_proof, &Vs, &signature, &public_key, order).is_err());
+            assert!(verify(&proof, &[Vs[1], Vs[0]], &signature, &public_key, order).is_err());
+
+            let mut bad_signature = signature;
+            bad_signature.s += Scalar::ONE;
+            assert!(verify(&proof, &Vs, &bad_signature, &public_key, order).is_err());
+            assert!(verify(&proof, &Vs, &signature, &other_key, order).is_err());
+
+            let other_order = match order {
+                SignatureOrder::ProofFirst => SignatureOrder::SignatureFirst,
+                SignatureOrder::SignatureFirst => SignatureOrder::ProofFirst,
+            };
+            assert!(verify(&proof, &Vs, &signature, &public_key, other_order).is_err());
+        }
+    }
+
+    #[test]
+    fn signature_bytes_round_trip() {
+        let pc_gens = PedersenGens::default();
+        let secret_key = Scalar::from(42u64);
+        let mut transcript = Transcript::new(b"SignatureBytesTest");
+        let signature = Signature::sign(&mut transcript, &pc_gens, &secret_key);
+
+        let bytes = signature.to_bytes();
+        assert_eq!(Signature::from_bytes(&bytes), Ok(signature));
+        assert_eq!(
+            Signature::from_bytes(&bytes[..63]),
+            Err(ProofError::FormatError)
+        );
+        let mut non_canonical = bytes;
+        non_canonical[32..].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            Signature::from_bytes(&non_canonical),
+            Err(ProofError::FormatError)
+        );
+    }
+}
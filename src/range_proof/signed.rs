@@ -0,0 +1,225 @@
+//! Range proofs for signed values.
+//!
+//! To prove \\(v \in [-2^{n-1}, 2^{n-1})\\) for a signed value \\(v\\),
+//! the prover shifts it by \\(2^{n-1}\\) and proves that
+//! \\(v + 2^{n-1} \in [0, 2^n)\\) with an ordinary range proof.  The
+//! returned commitment is to the shifted value, so that
+//! \\(V - 2^{n-1} \cdot B\\) is the commitment to \\(v\\) itself.
+//!
+//! Both sides bind the shift to the transcript before the range proof,
+//! as the exponent \\(n - 1\\) under the `signed-rangeproof v1` domain
+//! separator, so a signed proof never verifies as an unsigned one.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::transcript::TranscriptProtocol;
+
+use super::{RangeProof, ValueCommitment};
+
+/// Returns `v` shifted by \\(2^{n-1}\\) into \\([0, 2^n)\\), checking
+/// that \\(2 \leq n \leq 128\\) and \\(-2^{n-1} \leq v < 2^{n-1}\\).
+fn shifted_value(v: i64, n: usize) -> Result<u128, ProofError> {
+    if !(2..=128).contains(&n) {
+        return Err(ProofError::InvalidBitsize);
+    }
+    if n <= 64 {
+        let half = 1i128 << (n - 1);
+        if (v as i128) < -half || (v as i128) >= half {
+            return Err(ProofError::ValueOutOfRange);
+        }
+    }
+    // Sign-extending to u128 makes the sum wrap to v + 2^(n-1).
+    Ok((1u128 << (n - 1)).wrapping_add(v as u128))
+}
+
+impl RangeProof {
+    /// Create a rangeproof that the signed value `v` lies in
+    /// \\([-2^{n-1}, 2^{n-1})\\), with the blinding factor
+    /// `v_blinding`.
+    ///
+    /// Returns the proof and the commitment to the shifted value
+    /// \\(v + 2^{n-1}\\).  Returns [`ProofError::InvalidBitsize`] if
+    /// `n < 2` or `n > 128`, and [`ProofError::ValueOutOfRange`] if `v` does not
+    /// fit in `n` signed bits.
+    pub fn prove_single_signed_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: i64,
+        v_blinding: &Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let u = shifted_value(v, n)?;
+
+        transcript.signed_rangeproof_domain_sep((n - 1) as u64);
+        RangeProof::prove_single_u128_with_rng(bp_gens, pc_gens, transcript, u, v_blinding, n, rng)
+    }
+
+    /// Create a rangeproof that the signed value `v` lies in
+    /// \\([-2^{n-1}, 2^{n-1})\\).
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::prove_single_signed_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_single_signed(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: i64,
+        v_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_single_signed_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof that the value committed to in `V`,
+    /// shifted back by \\(2^{n-1}\\), lies in \\([-2^{n-1}, 2^{n-1})\\).
+    ///
+    /// `V` is the commitment returned by
+    /// [`RangeProof::prove_single_signed_with_rng`].
+    pub fn verify_single_signed_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        if !(2..=128).contains(&n) {
+            return Err(ProofError::InvalidBitsize);
+        }
+
+        transcript.signed_rangeproof_domain_sep((n - 1) as u64);
+        self.verify_single_with_rng(bp_gens, pc_gens, transcript, V, n, rng)
+    }
+
+    /// Verifies a rangeproof for a signed value.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_single_signed_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_single_signed(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &impl ValueCommitment,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_single_signed_with_rng(bp_gens, pc_gens, transcript, V, n, &mut thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_helper(v: i64, n: usize) -> Result<(), ProofError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let mut transcript = Transcript::new(b"SignedTest");
+        let (proof, V) =
+            RangeProof::prove_single_signed(&bp_gens, &pc_gens, &mut transcript, v, &blinding, n)?;
+
+        // Shifting back gives the commitment to the signed value.
+        let signed_v = if v < 0 {
+            -Scalar::from(v.unsigned_abs())
+        } else {
+            Scalar::from(v as u64)
+        };
+        let shift = Scalar::from(1u128 << (n - 1));
+        assert_eq!(
+            (V.decompress().unwrap() - shift * pc_gens.B).compress(),
+            pc_gens.commit(signed_v, blinding).compress()
+        );
+
+        let mut transcript = Transcript::new(b"SignedTest");
+        proof.verify_single_signed(&bp_gens, &pc_gens, &mut transcript, &V, n)
+    }
+
+    #[test]
+    fn signed_boundaries() {
+        for v in [i64::MIN, -1, 0, 1, i64::MAX] {
+            assert_eq!(signed_helper(v, 64), Ok(()));
+            assert_eq!(signed_helper(v, 128), Ok(()));
+        }
+        assert_eq!(signed_helper(-128, 8), Ok(()));
+        assert_eq!(signed_helper(127, 8), Ok(()));
+        assert_eq!(signed_helper(-1, 2), Ok(()));
+        assert_eq!(signed_helper(1, 2), Ok(()));
+    }
+
+    #[test]
+    fn signed_values_out_of_range() {
+        assert_eq!(signed_helper(-129, 8), Err(ProofError::ValueOutOfRange));
+        assert_eq!(signed_helper(128, 8), Err(ProofError::ValueOutOfRange));
+        assert_eq!(
+            signed_helper(i64::MIN, 63),
+            Err(ProofError::ValueOutOfRange)
+        );
+        assert_eq!(signed_helper(0, 1), Err(ProofError::InvalidBitsize));
+        assert_eq!(signed_helper(0, 0), Err(ProofError::InvalidBitsize));
+    }
+
+    #[test]
+    fn signed_bitsizes_past_128_are_rejected() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 1);
+        let blinding = Scalar::from(5u64);
+
+        let mut transcript = Transcript::new(b"SignedTest");
+        let (proof, V) =
+            RangeProof::prove_single_signed(&bp_gens, &pc_gens, &mut transcript, -7, &blinding, 8)
+                .unwrap();
+
+        for n in [129, 200] {
+            assert_eq!(signed_helper(-7, n), Err(ProofError::InvalidBitsize));
+            let mut transcript = Transcript::new(b"SignedTest");
+            assert_eq!(
+                proof.verify_single_signed(&bp_gens, &pc_gens, &mut transcript, &V, n),
+                Err(ProofError::InvalidBitsize)
+            );
+        }
+    }
+
+    #[test]
+    fn signed_proof_does_not_verify_as_unsigned() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::from(5u64);
+
+        let mut transcript = Transcript::new(b"SignedTest");
+        let (proof, V) =
+            RangeProof::prove_single_signed(&bp_gens, &pc_gens, &mut transcript, -7, &blinding, 32)
+                .unwrap();
+
+        let mut transcript = Transcript::new(b"SignedTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 32)
+            .is_err());
+    }
+}
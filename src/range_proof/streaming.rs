@@ -0,0 +1,397 @@
+//! The [`StreamingBatchProver`], which runs the aggregation protocol
+//! between a local dealer and parties submitted one at a time.
+//!
+//! [`RangeProof::prove_multiple_with_rng`] creates every party up
+//! front and keeps the parties of each round in a vector.  The
+//! streaming prover instead only keeps the parties' messages: each
+//! round, the caller submits every party state, which is consumed and
+//! replaced by the party's next state, so the caller decides when each
+//! party is created and where its state lives between the rounds.
+//!
+//! The rounds are typestates, like the ones of the
+//! [`dealer`](super::dealer) and [`party`](super::party) modules:
+//! [`StreamingBatchProver::finalize_bit_round`] and
+//! [`StreamingBatchProverAwaitingPolyCommitments::finalize_poly_round`]
+//! fail if a party of their round is missing.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::iter;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::{MPCError, ProofError};
+use crate::generators::{BulletproofGens, PedersenGens};
+
+use super::dealer::{
+    Dealer, DealerAwaitingBitCommitments, DealerAwaitingPolyCommitments, DealerAwaitingProofShares,
+};
+use super::messages::{BitChallenge, BitCommitment, PolyChallenge, PolyCommitment, ProofShare};
+use super::party::{PartyAwaitingBitChallenge, PartyAwaitingPolyChallenge, PartyAwaitingPosition};
+use super::RangeProof;
+
+/// Returns `m` empty slots for the parties' messages.
+fn slots<T>(m: usize) -> Vec<Option<T>> {
+    iter::repeat_with(|| None).take(m).collect()
+}
+
+/// Returns the empty slot `j` of `slots`, or
+/// [`MPCError::ParticipantMismatch`] if the position is out of range
+/// or was already submitted.
+fn slot<T>(slots: &mut [Option<T>], j: usize) -> Result<&mut Option<T>, ProofError> {
+    match slots.get_mut(j) {
+        Some(slot) if slot.is_none() => Ok(slot),
+        _ => Err(MPCError::ParticipantMismatch.into()),
+    }
+}
+
+/// A prover waiting for the parties' [`BitCommitment`]s, the first
+/// round of the aggregation protocol.
+pub struct StreamingBatchProver<'a, 'b> {
+    dealer: DealerAwaitingBitCommitments<'a, 'b>,
+    bit_commitments: Vec<Option<BitCommitment>>,
+}
+
+impl<'a, 'b> StreamingBatchProver<'a, 'b> {
+    /// Creates a prover for an aggregated proof of `m` values of
+    /// bitsize `n`, where `m` is a power of two.
+    pub fn new(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        n: usize,
+        m: usize,
+    ) -> Result<Self, ProofError> {
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, m)?;
+        Ok(StreamingBatchProver {
+            dealer,
+            bit_commitments: slots(m),
+        })
+    }
+
+    /// Assigns `party` the position `j` and records its bit
+    /// commitment, returning the party's next state.
+    ///
+    /// Returns [`MPCError::ParticipantMismatch`] if `j` is out of range
+    /// or was already submitted.
+    pub fn add_party_bit_commitment_with_rng<T: RngCore + CryptoRng>(
+        &mut self,
+        j: usize,
+        party: PartyAwaitingPosition<'b>,
+        rng: &mut T,
+    ) -> Result<PartyAwaitingBitChallenge<'b>, ProofError> {
+        let slot = slot(&mut self.bit_commitments, j)?;
+        let (party, bit_commitment) = party.assign_position_with_rng(j, rng)?;
+        *slot = Some(bit_commitment);
+        Ok(party)
+    }
+
+    /// Assigns `party` the position `j` and records its bit commitment.
+    /// This is a convenience wrapper around [`StreamingBatchProver::add_party_bit_commitment_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn add_party_bit_commitment(
+        &mut self,
+        j: usize,
+        party: PartyAwaitingPosition<'b>,
+    ) -> Result<PartyAwaitingBitChallenge<'b>, ProofError> {
+        self.add_party_bit_commitment_with_rng(j, party, &mut thread_rng())
+    }
+
+    /// Computes the [`BitChallenge`] from the bit commitments of all
+    /// the parties.
+    ///
+    /// Returns [`MPCError::WrongNumBitCommitments`] if a party is
+    /// missing.
+    pub fn finalize_bit_round(
+        self,
+    ) -> Result<StreamingBatchProverAwaitingPolyCommitments<'a, 'b>, ProofError> {
+        let bit_commitments = self
+            .bit_commitments
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MPCError::WrongNumBitCommitments)?;
+        let value_commitments = bit_commitments.iter().map(|c| c.V_j).collect();
+        let m = bit_commitments.len();
+
+        let (dealer, bit_challenge) = self.dealer.receive_bit_commitments(bit_commitments)?;
+        Ok(StreamingBatchProverAwaitingPolyCommitments {
+            dealer,
+            bit_challenge,
+            value_commitments,
+            poly_commitments: slots(m),
+        })
+    }
+}
+
+/// A prover waiting for the parties' [`PolyCommitment`]s, the second
+/// round of the aggregation protocol.
+pub struct StreamingBatchProverAwaitingPolyCommitments<'a, 'b> {
+    dealer: DealerAwaitingPolyCommitments<'a, 'b>,
+    bit_challenge: BitChallenge,
+    value_commitments: Vec<CompressedRistretto>,
+    poly_commitments: Vec<Option<PolyCommitment>>,
+}
+
+impl<'a, 'b> StreamingBatchProverAwaitingPolyCommitments<'a, 'b> {
+    /// Applies the [`BitChallenge`] to the party at position `j` and
+    /// records its polynomial commitment, returning the party's next
+    /// state.
+    ///
+    /// Returns [`MPCError::ParticipantMismatch`] if `party` is not at
+    /// position `j`, or `j` was already submitted.
+    pub fn add_party_poly_commitment_with_rng<T: RngCore + CryptoRng>(
+        &mut self,
+        j: usize,
+        party: PartyAwaitingBitChallenge<'b>,
+        rng: &mut T,
+    ) -> Result<PartyAwaitingPolyChallenge, ProofError> {
+        if party.position() != j {
+            return Err(MPCError::ParticipantMismatch.into());
+        }
+        let slot = slot(&mut self.poly_commitments, j)?;
+        let (party, poly_commitment) = party.apply_challenge_with_rng(&self.bit_challenge, rng);
+        *slot = Some(poly_commitment);
+        Ok(party)
+    }
+
+    /// Applies the [`BitChallenge`] to the party at position `j` and
+    /// records its polynomial commitment.
+    /// This is a convenience wrapper around [`StreamingBatchProverAwaitingPolyCommitments::add_party_poly_commitment_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn add_party_poly_commitment(
+        &mut self,
+        j: usize,
+        party: PartyAwaitingBitChallenge<'b>,
+    ) -> Result<PartyAwaitingPolyChallenge, ProofError> {
+        self.add_party_poly_commitment_with_rng(j, party, &mut thread_rng())
+    }
+
+    /// Computes the [`PolyChallenge`] from the polynomial commitments
+    /// of all the parties.
+    ///
+    /// Returns [`MPCError::WrongNumPolyCommitments`] if a party is
+    /// missing.
+    pub fn finalize_poly_round(
+        self,
+    ) -> Result<StreamingBatchProverAwaitingProofShares<'a, 'b>, ProofError> {
+        let poly_commitments = self
+            .poly_commitments
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MPCError::WrongNumPolyCommitments)?;
+        let m = poly_commitments.len();
+
+        let (dealer, poly_challenge) = self.dealer.receive_poly_commitments(poly_commitments)?;
+        Ok(StreamingBatchProverAwaitingProofShares {
+            dealer,
+            poly_challenge,
+            value_commitments: self.value_commitments,
+            proof_shares: slots(m),
+        })
+    }
+}
+
+/// A prover waiting for the parties' [`ProofShare`]s, the last round
+/// of the aggregation protocol.
+pub struct StreamingBatchProverAwaitingProofShares<'a, 'b> {
+    dealer: DealerAwaitingProofShares<'a, 'b>,
+    poly_challenge: PolyChallenge,
+    value_commitments: Vec<CompressedRistretto>,
+    proof_shares: Vec<Option<ProofShare>>,
+}
+
+impl<'a, 'b> StreamingBatchProverAwaitingProofShares<'a, 'b> {
+    /// Applies the [`PolyChallenge`] to the party at position `j` and
+    /// records its proof share, consuming the party.
+    ///
+    /// Returns [`MPCError::ParticipantMismatch`] if `party` is not at
+    /// position `j`, or `j` was already submitted.
+    pub fn add_party_share(
+        &mut self,
+        j: usize,
+        party: PartyAwaitingPolyChallenge,
+    ) -> Result<(), ProofError> {
+        if party.position() != j {
+            return Err(MPCError::ParticipantMismatch.into());
+        }
+        let slot = slot(&mut self.proof_shares, j)?;
+        *slot = Some(party.apply_challenge(&self.poly_challenge)?);
+        Ok(())
+    }
+
+    /// Assembles the proof shares of all the parties into the
+    /// aggregated proof, and returns it with the parties' value
+    /// commitments, in order.
+    ///
+    /// The proof is verified before it is returned, since the shares
+    /// come from party states the caller submitted.  Returns
+    /// [`MPCError::WrongNumProofShares`] if a party is missing, and
+    /// [`ProofError::MalformedShares`] with the positions of the bad
+    /// shares, from [`MPCError::MalformedProofShares`], if the proof
+    /// does not verify, for instance if a party was taken from another
+    /// prover.
+    pub fn build_with_rng<T: RngCore + CryptoRng>(
+        self,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        let proof_shares = self
+            .proof_shares
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MPCError::WrongNumProofShares)?;
+
+        let proof = self.dealer.receive_shares_with_rng(&proof_shares, rng)?;
+        Ok((proof, self.value_commitments))
+    }
+
+    /// Assembles and verifies the aggregated proof.
+    /// This is a convenience wrapper around [`StreamingBatchProverAwaitingProofShares::build_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn build(self) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        self.build_with_rng(&mut thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use crate::range_proof::party::Party;
+
+    #[test]
+    fn streaming_prover_matches_aggregated_proofs() {
+        let (n, m) = (32, 16);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        let values: Vec<u64> = (0..m as u64).map(|j| j * 1000 + 7).collect();
+        let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"StreamingTest");
+        let mut prover =
+            StreamingBatchProver::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        // Submit the parties out of order, creating each one just in time.
+        let parties: Vec<_> = (0..m)
+            .rev()
+            .map(|j| {
+                let party = Party::new(&bp_gens, &pc_gens, values[j], blindings[j], n).unwrap();
+                (j, prover.add_party_bit_commitment(j, party).unwrap())
+            })
+            .collect();
+        let mut prover = prover.finalize_bit_round().unwrap();
+
+        let parties: Vec<_> = parties
+            .into_iter()
+            .map(|(j, party)| (j, prover.add_party_poly_commitment(j, party).unwrap()))
+            .collect();
+        let mut prover = prover.finalize_poly_round().unwrap();
+
+        for (j, party) in parties {
+            prover.add_party_share(j, party).unwrap();
+        }
+        let (proof, commitments) = prover.build().unwrap();
+
+        for (j, V) in commitments.iter().enumerate() {
+            assert_eq!(
+                *V,
+                pc_gens
+                    .commit(Scalar::from(values[j]), blindings[j])
+                    .compress()
+            );
+        }
+        let mut transcript = Transcript::new(b"StreamingTest");
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, n),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn streaming_prover_rejects_parties_of_another_prover() {
+        let (n, m) = (16, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        // Run two provers up to their last round with the same values.
+        let mut transcripts = vec![Transcript::new(b"StreamingTest"); 2];
+        let mut rounds = Vec::new();
+        for transcript in transcripts.iter_mut() {
+            let mut prover =
+                StreamingBatchProver::new(&bp_gens, &pc_gens, transcript, n, m).unwrap();
+            let parties: Vec<_> = (0..m)
+                .map(|j| {
+                    let party = Party::new(&bp_gens, &pc_gens, 7, Scalar::ONE, n).unwrap();
+                    prover.add_party_bit_commitment(j, party).unwrap()
+                })
+                .collect();
+            let mut prover = prover.finalize_bit_round().unwrap();
+            let parties: Vec<_> = parties
+                .into_iter()
+                .enumerate()
+                .map(|(j, party)| prover.add_party_poly_commitment(j, party).unwrap())
+                .collect();
+            rounds.push((prover.finalize_poly_round().unwrap(), parties));
+        }
+        let (mut other, mut other_parties) = rounds.pop().unwrap();
+        let (mut prover, mut parties) = rounds.pop().unwrap();
+
+        // Swap the parties at position 1, whose shares then answer the
+        // other prover's challenges.
+        core::mem::swap(&mut parties[1], &mut other_parties[1]);
+        for (j, (party, other_party)) in parties.into_iter().zip(other_parties).enumerate() {
+            prover.add_party_share(j, party).unwrap();
+            other.add_party_share(j, other_party).unwrap();
+        }
+        let malformed = Err(ProofError::MalformedShares {
+            bad_shares: vec![1],
+        });
+        assert_eq!(prover.build().map(|_| ()), malformed);
+        assert_eq!(other.build().map(|_| ()), malformed);
+    }
+
+    #[test]
+    fn streaming_prover_rejects_misplaced_parties() {
+        let (n, m) = (8, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let party = || Party::new(&bp_gens, &pc_gens, 1, Scalar::ONE, n).unwrap();
+        let mismatch = Err(ProofError::ProvingError(MPCError::ParticipantMismatch));
+
+        let mut transcript = Transcript::new(b"StreamingTest");
+        let mut prover =
+            StreamingBatchProver::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let party0 = prover.add_party_bit_commitment(0, party()).unwrap();
+        assert_eq!(
+            prover.add_party_bit_commitment(0, party()).map(|_| ()),
+            mismatch.clone()
+        );
+        assert_eq!(
+            prover.add_party_bit_commitment(2, party()).map(|_| ()),
+            mismatch.clone()
+        );
+        let party1 = prover.add_party_bit_commitment(1, party()).unwrap();
+        let mut prover = prover.finalize_bit_round().unwrap();
+
+        assert_eq!(
+            prover.add_party_poly_commitment(1, party0).map(|_| ()),
+            mismatch
+        );
+        assert!(prover.add_party_poly_commitment(1, party1).is_ok());
+        assert_eq!(
+            prover.finalize_poly_round().map(|_| ()),
+            Err(ProofError::ProvingError(MPCError::WrongNumPolyCommitments))
+        );
+    }
+}
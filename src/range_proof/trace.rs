@@ -0,0 +1,95 @@
+//! The `trace` module contains the [`ProverTrace`] type, which
+//! records the prover's intermediate values during proof creation.
+//!
+//! ## WARNING
+//!
+//! A `ProverTrace` contains the bits of the secret values, the
+//! blinding factors and every intermediate polynomial.  It **leaks
+//! the witness** and must never be enabled in production.  It exists
+//! only for research and for debugging witness-construction bugs,
+//! and is only available with the `prover-introspection` feature.
+
+#![allow(non_snake_case)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use curve25519_dalek::scalar::Scalar;
+
+/// The intermediate values recorded while the parties commit to the
+/// bits of their values.
+#[derive(Clone, Debug)]
+pub struct BitTrace {
+    /// The bit vector \\(\mathbf{a}\_L\\) of the party's value.
+    pub a_L: Vec<Scalar>,
+    /// The vector \\(\mathbf{a}\_R = \mathbf{a}\_L - \mathbf{1}\\).
+    pub a_R: Vec<Scalar>,
+}
+
+/// The intermediate values recorded while the parties commit to
+/// their polynomial coefficients.
+#[derive(Clone, Debug)]
+pub struct PolyTrace {
+    /// The coefficients \\((\mathbf{l}\_0, \mathbf{l}\_1)\\) of \\(\mathbf{l}(x)\\).
+    pub l_poly: (Vec<Scalar>, Vec<Scalar>),
+    /// The coefficients \\((\mathbf{r}\_0, \mathbf{r}\_1)\\) of \\(\mathbf{r}(x)\\).
+    pub r_poly: (Vec<Scalar>, Vec<Scalar>),
+    /// The coefficients \\((t\_0, t\_1, t\_2)\\) of \\(t(x)\\).
+    pub t_poly: (Scalar, Scalar, Scalar),
+    /// The blinding factor opening the party's \\(T\_1\\) commitment.
+    pub t_1_blinding: Scalar,
+    /// The blinding factor opening the party's \\(T\_2\\) commitment.
+    pub t_2_blinding: Scalar,
+}
+
+/// The intermediate values recorded for one party, per protocol phase.
+#[derive(Clone, Debug)]
+pub struct PartyTrace {
+    /// The position of the party in the aggregated proof.
+    pub j: usize,
+    /// Values recorded during the bit commitment phase.
+    pub bits: Option<BitTrace>,
+    /// Values recorded during the polynomial commitment phase.
+    pub poly: Option<PolyTrace>,
+}
+
+/// A record of the prover's intermediate values, keyed by party
+/// index and protocol phase.
+///
+/// Produced by [`RangeProof::prove_multiple_traced`](crate::RangeProof::prove_multiple_traced).
+/// Recording the trace has no effect on the emitted proof.
+///
+/// ## WARNING
+///
+/// This leaks the witness.  Never enable the `prover-introspection`
+/// feature in production.
+#[derive(Clone, Debug, Default)]
+pub struct ProverTrace {
+    /// The polynomial challenge \\(x\\) issued by the dealer.
+    pub x: Option<Scalar>,
+    /// The per-party traces, indexed by party position.
+    pub parties: Vec<PartyTrace>,
+}
+
+impl ProverTrace {
+    /// Returns the trace recorded for the party at position `j`.
+    pub fn party(&self, j: usize) -> Option<&PartyTrace> {
+        self.parties.iter().find(|p| p.j == j)
+    }
+
+    /// Returns a mutable trace for the party at position `j`,
+    /// inserting an empty one if none was recorded yet.
+    pub(crate) fn party_mut(&mut self, j: usize) -> &mut PartyTrace {
+        match self.parties.iter().position(|p| p.j == j) {
+            Some(idx) => &mut self.parties[idx],
+            None => {
+                self.parties.push(PartyTrace {
+                    j,
+                    bits: None,
+                    poly: None,
+                });
+                self.parties.last_mut().unwrap()
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! The `verification_key` module contains the
+//! [`RangeProofVerificationKey`] type, which precomputes the static
+//! generators of batch verification.
+
+#![allow(non_snake_case)]
+
+use core::iter;
+
+use curve25519_dalek::ristretto::VartimeRistrettoPrecomputation;
+use curve25519_dalek::traits::VartimePrecomputedMultiscalarMul;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::util;
+
+use super::{padded_aggregation_size, BatchCollector, RangeProof, RangeProofView, ValueCommitment};
+
+/// Precomputed tables for the generators which appear in every batch
+/// verification: the \\(\mathbf G\\) and \\(\mathbf H\\) generators of
+/// `bp_gens` up to a fixed `(n, m)`, and the Pedersen generators.
+///
+/// Building the key is expensive, so it is meant to be built once and
+/// reused for many batches with
+/// [`RangeProof::verify_batch_precomputed_with_rng`].  Only the points
+/// of the proofs themselves are then multiplied without
+/// precomputation.
+pub struct RangeProofVerificationKey<'a> {
+    bp_gens: &'a BulletproofGens,
+    pc_gens: &'a PedersenGens,
+    /// The padded bitsize covered by the tables.
+    gens_capacity: usize,
+    /// The padded aggregation size covered by the tables.
+    party_capacity: usize,
+    precomputation: VartimeRistrettoPrecomputation,
+}
+
+impl<'a> RangeProofVerificationKey<'a> {
+    /// Builds the verification key for proofs of up to `m` values of
+    /// bitsize up to `n`, created with `bp_gens` and `pc_gens`.
+    pub fn new(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        n: usize,
+        m: usize,
+    ) -> Result<Self, ProofError> {
+        let gens_capacity = util::padded_bitsize(n).ok_or(ProofError::InvalidBitsize)?;
+        if bp_gens.gens_capacity < gens_capacity {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        let party_capacity = padded_aggregation_size(bp_gens, m)?;
+
+        // The same order as the generator scalars in `BatchCollector`.
+        let precomputation = VartimeRistrettoPrecomputation::new(
+            bp_gens
+                .G(gens_capacity, party_capacity)
+                .chain(bp_gens.H(gens_capacity, party_capacity))
+                .chain(iter::once(&pc_gens.B_blinding))
+                .chain(iter::once(&pc_gens.B)),
+        );
+
+        Ok(RangeProofVerificationKey {
+            bp_gens,
+            pc_gens,
+            gens_capacity,
+            party_capacity,
+            precomputation,
+        })
+    }
+}
+
+impl<'a> BatchCollector<'a> {
+    /// Verifies all the proofs added to the batch at once, like
+    /// [`BatchCollector::verify`], using the precomputed tables of
+    /// `key` for the generators.
+    ///
+    /// The collector must have been created with the generators of
+    /// `key`.  Returns [`ProofError::InvalidGeneratorsLength`] if a
+    /// proof in the batch is larger than the key supports.
+    pub fn verify_precomputed(mut self, key: &RangeProofVerificationKey) -> Result<(), ProofError> {
+        if self.gens_capacity > key.gens_capacity || self.party_capacity > key.party_capacity {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        // Lay the scalars out exactly as the precomputed generators.
+        self.grow(key.gens_capacity, key.party_capacity);
+
+        let mega_check = key
+            .precomputation
+            .optional_mixed_multiscalar_mul(
+                self.g_scalars
+                    .iter()
+                    .chain(self.h_scalars.iter())
+                    .chain(iter::once(&self.pedersen_B_blinding_scalar))
+                    .chain(iter::once(&self.pedersen_B_scalar)),
+                self.dynamic_scalars.iter(),
+                self.dynamic_points.iter().copied(),
+            )
+            .ok_or(ProofError::VerificationError)?;
+
+        use group::Group;
+        if mega_check.is_identity().into() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+impl RangeProof {
+    /// Verifies a batch of proofs like [`RangeProof::verify_batch_with_rng`],
+    /// using the precomputed generators of `key`.
+    ///
+    /// Accepts exactly the batches accepted by
+    /// [`RangeProof::verify_batch_with_rng`] with the generators of
+    /// `key`, provided no proof is larger than the key supports.
+    pub fn verify_batch_precomputed_with_rng<
+        'a,
+        T: RngCore + CryptoRng,
+        V: ValueCommitment + 'a,
+    >(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        key: &RangeProofVerificationKey,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let mut collector = BatchCollector::new(key.bp_gens, key.pc_gens);
+        for view in batch {
+            collector.add_proof(view, rng)?;
+        }
+
+        collector.verify_precomputed(key)
+    }
+
+    /// Verifies a batch of proofs using the precomputed generators of `key`.
+    /// This is a convenience wrapper around [`RangeProof::verify_batch_precomputed_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_batch_precomputed<'a, V: ValueCommitment + 'a>(
+        batch: impl IntoIterator<Item = RangeProofView<'a, V>>,
+        key: &RangeProofVerificationKey,
+    ) -> Result<(), ProofError> {
+        Self::verify_batch_precomputed_with_rng(batch, key, &mut thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use curve25519_dalek::scalar::Scalar;
+    use merlin::Transcript;
+
+    #[test]
+    fn precomputed_batch_matches_generic_batch() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let key = RangeProofVerificationKey::new(&bp_gens, &pc_gens, 64, 4).unwrap();
+        let mut rng = rand::thread_rng();
+
+        let nm = [(8, 1), (32, 2), (64, 4), (64, 3)];
+        let proofs: Vec<_> = nm
+            .iter()
+            .map(|&(n, m)| {
+                let values: Vec<u64> = (0..m as u64).collect();
+                let blindings: Vec<_> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+                let mut transcript = Transcript::new(b"PrecomputedBatchTest");
+                let (proof, Vs) = RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap();
+                (proof, Vs, n)
+            })
+            .collect();
+
+        let verify_both = |proofs: &[(RangeProof, Vec<CompressedRistretto>, usize)]| {
+            let mut transcripts = vec![Transcript::new(b"PrecomputedBatchTest"); proofs.len()];
+            let views = proofs
+                .iter()
+                .zip(transcripts.iter_mut())
+                .map(|((proof, Vs, n), t)| proof.verification_view(t, Vs, *n));
+            let generic = RangeProof::verify_batch(views, &bp_gens, &pc_gens);
+
+            let mut transcripts = vec![Transcript::new(b"PrecomputedBatchTest"); proofs.len()];
+            let views = proofs
+                .iter()
+                .zip(transcripts.iter_mut())
+                .map(|((proof, Vs, n), t)| proof.verification_view(t, Vs, *n));
+            let precomputed = RangeProof::verify_batch_precomputed(views, &key);
+
+            assert_eq!(generic, precomputed);
+            precomputed
+        };
+
+        assert!(verify_both(&proofs).is_ok());
+        assert!(verify_both(&proofs[..1]).is_ok());
+        assert!(verify_both(&[]).is_ok());
+
+        // A proof checked against the wrong commitments fails both.
+        let mut bad = proofs.clone();
+        bad[2].1.swap(0, 1);
+        assert!(verify_both(&bad).is_err());
+    }
+
+    #[test]
+    fn precomputed_batch_rejects_proofs_larger_than_key() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let key = RangeProofVerificationKey::new(&bp_gens, &pc_gens, 32, 2).unwrap();
+        let mut rng = rand::thread_rng();
+
+        let blindings: Vec<_> = (0..4).map(|_| Scalar::random(&mut rng)).collect();
+        let mut transcript = Transcript::new(b"PrecomputedBatchTest");
+        let (proof, Vs) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2, 3, 4],
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"PrecomputedBatchTest");
+        assert_eq!(
+            RangeProof::verify_batch_precomputed(
+                core::iter::once(proof.verification_view(&mut transcript, &Vs, 32)),
+                &key
+            ),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+
+        assert_eq!(
+            RangeProofVerificationKey::new(&bp_gens, &pc_gens, 128, 1).err(),
+            Some(ProofError::InvalidGeneratorsLength)
+        );
+        assert_eq!(
+            RangeProofVerificationKey::new(&bp_gens, &pc_gens, 64, 8).err(),
+            Some(ProofError::InvalidAggregation)
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Range proofs on the Hamming weight of a committed bitmask.
+//!
+//! Each bit \\(b\_i\\) is committed separately as
+//! \\(C\_i = b\_i \cdot B + r\_i \cdot \widetilde{B}\\).  The sum of
+//! the bit commitments is a commitment to the weight
+//! \\(w = \sum\_i b\_i\\) with blinding \\(\sum\_i r\_i\\), so proving
+//! \\(w \leq k\\) reduces to range-proving \\(k - w\\) against the
+//! commitment \\(k \cdot B - \sum\_i C\_i\\).
+//!
+//! # Assumption
+//!
+//! These proofs do **not** show that each \\(C\_i\\) commits to a
+//! bit.  Booleanity of every \\(b\_i\\) must be established by a
+//! separate proof: an aggregated \\(n = 1\\) range proof of the bits
+//! with the same blindings, created with
+//! [`RangeProof::prove_multiple_with_rng`], commits to the same
+//! \\(C\_i\\) and proves that each of them opens to 0 or 1.  Without
+//! it, a prover could commit to arbitrary values.
+
+#![allow(non_snake_case)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+
+use super::RangeProof;
+
+/// Returns the bit length of `max_weight`, the smallest bitsize able to
+/// represent it, and at least 1.
+fn weight_bitsize(max_weight: u64) -> usize {
+    (u64::BITS - max_weight.leading_zeros()).max(1) as usize
+}
+
+impl RangeProof {
+    /// Commits to each of the `bits` with the matching `blindings`
+    /// and proves that the number of set bits is at most `max_weight`.
+    ///
+    /// Returns the proof and the per-bit commitments.
+    ///
+    /// # Assumption
+    ///
+    /// The proof does **not** show that each commitment opens to a
+    /// bit.  Booleanity must be proven separately, for instance with
+    /// [`RangeProof::prove_multiple_with_rng`] for the same bits and
+    /// blindings and \\(n = 1\\), which gives the same commitments.
+    pub fn prove_weighted_bits_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        bits: &[bool],
+        blindings: &[Scalar],
+        max_weight: u64,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        if bits.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+
+        let weight = bits.iter().filter(|&&b| b).count() as u64;
+        if weight > max_weight {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        let bit_commitments = bits
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&b, &r)| pc_gens.commit(Scalar::from(b as u64), r).compress())
+            .collect();
+
+        // k*B - sum(C_i) commits to k - w with blinding -sum(r_i).
+        let blinding: Scalar = blindings.iter().sum();
+        let (proof, _) = RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            max_weight - weight,
+            &-blinding,
+            weight_bitsize(max_weight),
+            rng,
+        )?;
+
+        Ok((proof, bit_commitments))
+    }
+
+    /// Commits to each of the `bits` with the matching `blindings`
+    /// and proves that the number of set bits is at most `max_weight`.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::prove_weighted_bits_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_weighted_bits(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        bits: &[bool],
+        blindings: &[Scalar],
+        max_weight: u64,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_weighted_bits_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            bits,
+            blindings,
+            max_weight,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies that the bits committed to in `bit_commitments` have
+    /// a Hamming weight of at most `max_weight`.
+    ///
+    /// Returns [`ProofError::PointDecompressionError`] for the component
+    /// `"V"` if a bit commitment is not a valid encoding.
+    pub fn verify_weighted_bits_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        bit_commitments: &[CompressedRistretto],
+        max_weight: u64,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let sum = bit_commitments
+            .iter()
+            .map(|C| {
+                C.decompress().ok_or(ProofError::PointDecompressionError {
+                    proof_index: 0,
+                    component: "V",
+                })
+            })
+            .sum::<Result<RistrettoPoint, _>>()?;
+        let V = pc_gens.B * Scalar::from(max_weight) - sum;
+
+        self.verify_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &V,
+            weight_bitsize(max_weight),
+            rng,
+        )
+    }
+
+    /// Verifies that the bits committed to in `bit_commitments` have
+    /// a Hamming weight of at most `max_weight`.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProof::verify_weighted_bits_with_rng`], passing in a
+    /// threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn verify_weighted_bits(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        bit_commitments: &[CompressedRistretto],
+        max_weight: u64,
+    ) -> Result<(), ProofError> {
+        self.verify_weighted_bits_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            bit_commitments,
+            max_weight,
+            &mut thread_rng(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weighted_bits_helper(bits: &[bool], prove_bound: u64, verify_bound: u64) -> bool {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<Scalar> = bits.iter().map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"WeightedBitsTest");
+        let (proof, commitments) = RangeProof::prove_weighted_bits(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            bits,
+            &blindings,
+            prove_bound,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"WeightedBitsTest");
+        proof
+            .verify_weighted_bits(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &commitments,
+                verify_bound,
+            )
+            .is_ok()
+    }
+
+    #[test]
+    fn weight_within_bound() {
+        let bits = [true, false, true, true, false, false, true, false];
+        assert!(weighted_bits_helper(&bits, 4, 4));
+        assert!(weighted_bits_helper(&bits, 5, 5));
+        assert!(weighted_bits_helper(&bits, 1000, 1000));
+    }
+
+    #[test]
+    fn weight_over_bound() {
+        let bits = [true, false, true, true, false, false, true, false];
+        let blindings: Vec<Scalar> = bits.iter().map(|_| Scalar::ONE).collect();
+
+        let mut transcript = Transcript::new(b"WeightedBitsTest");
+        assert_eq!(
+            RangeProof::prove_weighted_bits(
+                &BulletproofGens::new(64, 1),
+                &PedersenGens::default(),
+                &mut transcript,
+                &bits,
+                &blindings,
+                3,
+            )
+            .unwrap_err(),
+            ProofError::ValueOutOfRange
+        );
+
+        // A proof for a larger bound does not verify against a smaller one.
+        assert!(!weighted_bits_helper(&bits, 4, 3));
+    }
+
+    #[test]
+    fn weight_bitsize_is_exact() {
+        assert_eq!(weight_bitsize(0), 1);
+        assert_eq!(weight_bitsize(1), 1);
+        assert_eq!(weight_bitsize(2), 2);
+        assert_eq!(weight_bitsize(255), 8);
+        assert_eq!(weight_bitsize(256), 9);
+        assert_eq!(weight_bitsize(1000), 10);
+        assert_eq!(weight_bitsize(u64::MAX), 64);
+    }
+
+    #[test]
+    fn booleanity_is_proven_over_the_same_commitments() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 4);
+        let mut rng = rand::thread_rng();
+        let bits = [true, false, true, true];
+        let blindings: Vec<Scalar> = bits.iter().map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"WeightedBitsTest");
+        let (proof, commitments) = RangeProof::prove_weighted_bits(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &bits,
+            &blindings,
+            3,
+        )
+        .unwrap();
+
+        let values: Vec<u64> = bits.iter().map(|&b| b as u64).collect();
+        let mut transcript = Transcript::new(b"BooleanityTest");
+        let (bits_proof, bit_commitments) =
+            RangeProof::prove_multiple(&bp_gens, &pc_gens, &mut transcript, &values, &blindings, 1)
+                .unwrap();
+        assert_eq!(bit_commitments, commitments);
+
+        let mut transcript = Transcript::new(b"BooleanityTest");
+        assert!(bits_proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 1)
+            .is_ok());
+        let mut transcript = Transcript::new(b"WeightedBitsTest");
+        assert!(proof
+            .verify_weighted_bits(&bp_gens, &pc_gens, &mut transcript, &commitments, 3)
+            .is_ok());
+    }
+
+    #[test]
+    fn invalid_commitments_are_attributed() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let bits = [true, false];
+        let blindings = [Scalar::from(3u64), Scalar::from(4u64)];
+        let mut transcript = Transcript::new(b"WeightedBitsTest");
+        let (proof, mut commitments) = RangeProof::prove_weighted_bits(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &bits,
+            &blindings,
+            1,
+        )
+        .unwrap();
+
+        let invalid = CompressedRistretto([0xff; 32]);
+        let error = Err(ProofError::PointDecompressionError {
+            proof_index: 0,
+            component: "V",
+        });
+        commitments[1] = invalid;
+        let mut transcript = Transcript::new(b"WeightedBitsTest");
+        assert_eq!(
+            proof.verify_weighted_bits(&bp_gens, &pc_gens, &mut transcript, &commitments, 1),
+            error
+        );
+    }
+}
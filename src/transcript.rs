@@ -6,13 +6,48 @@ use merlin::Transcript;
 
 use crate::errors::ProofError;
 
+/// The version of the range proof protocol bound by
+/// [`TranscriptProtocol::rangeproof_domain_sep`].  Proofs created
+/// before the version was bound are version 0.
+pub const RANGEPROOF_VERSION: u64 = 1;
+
 pub trait TranscriptProtocol {
-    /// Append a domain separator for an `n`-bit, `m`-party range proof.
+    /// Append a domain separator for an `n`-bit, `m`-party range proof,
+    /// preceded by the range proof protocol version.
     fn rangeproof_domain_sep(&mut self, n: u64, m: u64);
 
+    /// Append the domain separator of version 0 range proofs, which
+    /// did not bind the protocol version.
+    fn rangeproof_domain_sep_v0(&mut self, n: u64, m: u64);
+
+    /// Append a domain separator for the `index`-th of `num_chunks`
+    /// chunks of a range proof of `m` values, split with the chunk
+    /// capacity `capacity`.
+    fn rangeproof_chunk_domain_sep(&mut self, index: u64, num_chunks: u64, m: u64, capacity: u64);
+
+    /// Append a domain separator for a range proof whose \\(j\\)-th
+    /// party proves a `bitsizes[j]`-bit value, padded to `n` bits.
+    fn rangeproof_bitsizes_domain_sep(&mut self, n: u64, bitsizes: &[usize]);
+
+    /// Append a domain separator for a range proof of a signed value,
+    /// shifted by \\(2^k\\) into the unsigned range.
+    fn signed_rangeproof_domain_sep(&mut self, k: u64);
+
+    /// Append a domain separator for a range proof that one committed
+    /// value is strictly less than another.
+    fn less_than_domain_sep(&mut self);
+
+    /// Append the ordered list of participant identities of an
+    /// aggregated range proof.
+    fn participants_domain_sep(&mut self, participants: &[[u8; 32]]);
+
     /// Append a domain separator for a length-`n` inner product proof.
     fn innerproduct_domain_sep(&mut self, n: u64);
 
+    /// Append a domain separator for a Schnorr signature sharing the
+    /// transcript of a range proof.
+    fn signature_domain_sep(&mut self);
+
     /// Append a domain separator for a constraint system.
     fn r1cs_domain_sep(&mut self);
 
@@ -42,16 +77,59 @@ pub trait TranscriptProtocol {
 
 impl TranscriptProtocol for Transcript {
     fn rangeproof_domain_sep(&mut self, n: u64, m: u64) {
+        self.append_u64(b"version", RANGEPROOF_VERSION);
+        self.rangeproof_domain_sep_v0(n, m);
+    }
+
+    fn rangeproof_domain_sep_v0(&mut self, n: u64, m: u64) {
         self.append_message(b"dom-sep", b"rangeproof v1");
         self.append_u64(b"n", n);
         self.append_u64(b"m", m);
     }
 
+    fn rangeproof_chunk_domain_sep(&mut self, index: u64, num_chunks: u64, m: u64, capacity: u64) {
+        self.append_message(b"dom-sep", b"rangeproof-chunk v1");
+        self.append_u64(b"capacity", capacity);
+        self.append_u64(b"m", m);
+        self.append_u64(b"chunks", num_chunks);
+        self.append_u64(b"chunk", index);
+    }
+
+    fn rangeproof_bitsizes_domain_sep(&mut self, n: u64, bitsizes: &[usize]) {
+        self.append_message(b"dom-sep", b"rangeproof-bitsizes v1");
+        self.append_u64(b"n", n);
+        self.append_u64(b"m", bitsizes.len() as u64);
+        for &n_j in bitsizes {
+            self.append_u64(b"n_j", n_j as u64);
+        }
+    }
+
+    fn signed_rangeproof_domain_sep(&mut self, k: u64) {
+        self.append_message(b"dom-sep", b"signed-rangeproof v1");
+        self.append_u64(b"k", k);
+    }
+
+    fn less_than_domain_sep(&mut self) {
+        self.append_message(b"dom-sep", b"less-than-rangeproof v1");
+    }
+
+    fn participants_domain_sep(&mut self, participants: &[[u8; 32]]) {
+        self.append_message(b"dom-sep", b"participants v1");
+        self.append_u64(b"k", participants.len() as u64);
+        for identity in participants {
+            self.append_message(b"participant", identity);
+        }
+    }
+
     fn innerproduct_domain_sep(&mut self, n: u64) {
         self.append_message(b"dom-sep", b"ipp v1");
         self.append_u64(b"n", n);
     }
 
+    fn signature_domain_sep(&mut self) {
+        self.append_message(b"dom-sep", b"schnorr v1");
+    }
+
     fn r1cs_domain_sep(&mut self) {
         self.append_message(b"dom-sep", b"r1cs v1");
     }
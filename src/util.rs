@@ -69,6 +69,48 @@ pub fn exp_iter(x: Scalar) -> ScalarExp {
     ScalarExp { x, next_exp_x }
 }
 
+/// An iterator adapter for an iterator which must yield exactly `len`
+/// items.
+///
+/// The adapter reports `len` as its exact size hint, which the
+/// multiscalar multiplications rely on to match scalars with points,
+/// and asserts in debug builds that the inner iterator yields neither
+/// more nor fewer items, so that a dimension bug panics instead of
+/// silently misaligning the two.
+pub struct AssertSizeHint<I> {
+    iter: I,
+    remaining: usize,
+}
+
+impl<I> AssertSizeHint<I> {
+    /// Wraps `iter`, which must yield exactly `len` items.
+    pub fn new(iter: I, len: usize) -> Self {
+        AssertSizeHint {
+            iter,
+            remaining: len,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for AssertSizeHint<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let item = self.iter.next();
+        if item.is_some() {
+            debug_assert!(self.remaining > 0, "iterator is longer than expected");
+            self.remaining = self.remaining.saturating_sub(1);
+        } else {
+            debug_assert_eq!(self.remaining, 0, "iterator is shorter than expected");
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
 pub fn add_vec(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
     if a.len() != b.len() {
         // throw some error
@@ -215,6 +257,67 @@ fn sum_of_powers_slow(x: &Scalar, n: usize) -> Scalar {
     exp_iter(*x).take(n).sum()
 }
 
+/// Returns whether the integer representative of `x` is less than \\(2\^n\\).
+pub fn scalar_fits_bits(x: &Scalar, n: usize) -> bool {
+    let bytes = x.as_bytes();
+    let (full, partial) = (n / 8, n % 8);
+    if full >= 32 {
+        return true;
+    }
+    bytes[full] >> partial == 0 && bytes[full + 1..].iter().all(|&b| b == 0)
+}
+
+/// Returns the bitsize a range proof for `n`-bit values is padded to,
+/// the smallest of 8, 16, 32, 64 or 128 that is at least `n`.
+///
+/// Returns `None` unless \\(1 \leq n \leq 128\\).
+pub fn padded_bitsize(n: usize) -> Option<usize> {
+    match n {
+        1..=128 => Some(n.next_power_of_two().max(8)),
+        _ => None,
+    }
+}
+
+/// Returns the largest padded bitsize of the given `bitsizes`, or
+/// `None` if the list is empty or one of them is invalid.
+pub fn max_padded_bitsize(bitsizes: &[usize]) -> Option<usize> {
+    let mut max = None;
+    for &n in bitsizes {
+        max = max.max(Some(padded_bitsize(n)?));
+    }
+    max
+}
+
+/// Returns \\(\lceil \lg x \rceil\\), the logarithm of
+/// `x.next_power_of_two()`, without overflowing for large `x`.
+pub const fn ceil_log2(x: usize) -> usize {
+    if x <= 1 {
+        0
+    } else {
+        (usize::BITS - (x - 1).leading_zeros()) as usize
+    }
+}
+
+/// Returns the first `padded_n` entries of the vector
+/// \\((1, 2, \ldots, 2\^{n-1}, 0, \ldots, 0)\\), so that padding bits
+/// do not contribute to the committed value.
+pub fn padded_powers_of_2(n: usize, padded_n: usize) -> impl Iterator<Item = Scalar> {
+    scaled_padded_powers_of_2(Scalar::ONE, n, padded_n)
+}
+
+/// Returns the entries of [`padded_powers_of_2`] multiplied by
+/// `scale`, computed by repeated doubling instead of multiplication.
+pub fn scaled_padded_powers_of_2(
+    scale: Scalar,
+    n: usize,
+    padded_n: usize,
+) -> impl Iterator<Item = Scalar> {
+    core::iter::successors(Some(scale), |x| Some(x + x))
+        .take(n)
+        .chain(core::iter::repeat(Scalar::ZERO))
+        .take(padded_n)
+}
+
 /// Given `data` with `len >= 32`, return the first 32 bytes.
 pub fn read32(data: &[u8]) -> [u8; 32] {
     let mut buf32 = [0u8; 32];
@@ -222,33 +325,52 @@ pub fn read32(data: &[u8]) -> [u8; 32] {
     buf32
 }
 
-pub(crate) struct AssertSizeHint<I: Iterator<Item = T>, T> {
-    iter: I,
-    size_hint: usize,
+/// Encodes `data` as a lowercase hexadecimal string.
+pub fn to_hex(data: &[u8]) -> alloc::string::String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    data.iter()
+        .flat_map(|b| [DIGITS[(b >> 4) as usize], DIGITS[(b & 0xf) as usize]])
+        .map(char::from)
+        .collect()
 }
 
-impl<I: Iterator<Item = T>, T> AssertSizeHint<I, T> {
-    pub fn new(iter: I, size_hint: usize) -> Self {
-        Self { iter, size_hint }
+/// Decodes a hexadecimal string of either case, returning `None` if
+/// it has an odd length or a character which is not a hex digit.
+pub fn from_hex(s: &str) -> Option<Vec<u8>> {
+    let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+    let s = s.as_bytes();
+    if s.len() % 2 != 0 {
+        return None;
     }
+    s.chunks(2)
+        .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect()
 }
 
-impl<I: Iterator<Item = T>, T> Iterator for AssertSizeHint<I, T> {
-    type Item = T;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zeroize::Zeroize;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next()
+    #[test]
+    fn hex_round_trips() {
+        let data = [0x00, 0x01, 0x7f, 0xab, 0xff];
+        assert_eq!(to_hex(&data), "00017fabff");
+        assert_eq!(from_hex("00017fabff"), Some(data.to_vec()));
+        assert_eq!(from_hex("00017FABFF"), Some(data.to_vec()));
+        assert_eq!(from_hex(""), Some(Vec::new()));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+        assert_eq!(from_hex("+1"), None);
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.size_hint, Some(self.size_hint))
+    #[test]
+    fn ceil_log2_matches_next_power_of_two() {
+        for x in 0..=1025usize {
+            assert_eq!(1 << ceil_log2(x), x.next_power_of_two());
+        }
+        assert_eq!(ceil_log2(usize::MAX), usize::BITS as usize);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use zeroize::Zeroize;
-    use super::*;
 
     #[test]
     fn exp_2_is_powers_of_2() {
@@ -260,6 +382,18 @@ mod tests {
         assert_eq!(exp_2[3], Scalar::from(8u64));
     }
 
+    #[test]
+    fn scaled_padded_powers_of_2_matches_products() {
+        let scale = Scalar::from(12345u64);
+        let scaled: Vec<_> = scaled_padded_powers_of_2(scale, 5, 8).collect();
+        let expected: Vec<_> = exp_iter(Scalar::from(2u64))
+            .take(5)
+            .map(|exp_2| exp_2 * scale)
+            .chain(core::iter::repeat(Scalar::ZERO).take(3))
+            .collect();
+        assert_eq!(scaled, expected);
+    }
+
     #[test]
     fn test_inner_product() {
         let a = vec![
@@ -0,0 +1,237 @@
+//! JavaScript bindings for creating and verifying range proofs,
+//! available with the `wasm` feature.
+//!
+//! Proofs are passed as their [`RangeProof::to_bytes`] encoding,
+//! commitments and blinding factors as the concatenation of their
+//! 32-byte encodings, and values as `BigInt`s.  The generators are
+//! the default [`PedersenGens`] and [`BulletproofGens`] of the
+//! smallest capacity fitting the proof.
+//!
+//! A Merlin transcript label must be a static string, so the
+//! transcript is created with the label `bulletproofs-wasm`, and the
+//! `label` passed from JavaScript is appended to it.  Proofs created
+//! with these bindings therefore only verify with these bindings, or
+//! with a transcript prepared the same way.
+
+#![allow(non_snake_case)]
+
+use core::convert::TryFrom;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use js_sys::{Array, BigInt, Uint8Array};
+use merlin::Transcript;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+use crate::util;
+
+/// A value converted from a JavaScript `BigInt`.
+struct Value(u64);
+
+impl TryFrom<&BigInt> for Value {
+    type Error = JsValue;
+
+    fn try_from(value: &BigInt) -> Result<Self, Self::Error> {
+        let digits: String = value.to_string(10)?.into();
+        digits
+            .parse()
+            .map(Value)
+            .map_err(|_| JsValue::from_str("Value is not an unsigned 64-bit integer."))
+    }
+}
+
+/// A list of scalars converted from the concatenation of their
+/// canonical 32-byte encodings.
+struct Scalars(Vec<Scalar>);
+
+impl TryFrom<&Uint8Array> for Scalars {
+    type Error = JsValue;
+
+    fn try_from(bytes: &Uint8Array) -> Result<Self, Self::Error> {
+        let bytes = bytes.to_vec();
+        if bytes.len() % 32 != 0 {
+            return Err(Error(ProofError::FormatError).into());
+        }
+        bytes
+            .chunks(32)
+            .map(|chunk| Option::from(Scalar::from_canonical_bytes(util::read32(chunk))))
+            .collect::<Option<Vec<_>>>()
+            .map(Scalars)
+            .ok_or_else(|| Error(ProofError::FormatError).into())
+    }
+}
+
+/// A list of commitments converted from or to the concatenation of
+/// their 32-byte encodings.
+struct CommitmentBytes(Vec<CompressedRistretto>);
+
+impl TryFrom<&Uint8Array> for CommitmentBytes {
+    type Error = JsValue;
+
+    fn try_from(bytes: &Uint8Array) -> Result<Self, Self::Error> {
+        let bytes = bytes.to_vec();
+        if bytes.len() % 32 != 0 {
+            return Err(Error(ProofError::FormatError).into());
+        }
+        Ok(CommitmentBytes(
+            bytes
+                .chunks(32)
+                .map(|chunk| CompressedRistretto(util::read32(chunk)))
+                .collect(),
+        ))
+    }
+}
+
+impl From<CommitmentBytes> for Uint8Array {
+    fn from(commitments: CommitmentBytes) -> Self {
+        let bytes: Vec<u8> = commitments
+            .0
+            .iter()
+            .flat_map(|V| V.as_bytes().iter().copied())
+            .collect();
+        Uint8Array::from(&bytes[..])
+    }
+}
+
+/// A proof error, surfaced to JavaScript as its message.
+struct Error(ProofError);
+
+impl From<Error> for JsValue {
+    fn from(e: Error) -> Self {
+        JsValue::from_str(&e.0.to_string())
+    }
+}
+
+/// Creates the transcript of the bindings, bound to `label`.
+fn transcript(label: &Uint8Array) -> Transcript {
+    let mut transcript = Transcript::new(b"bulletproofs-wasm");
+    transcript.append_message(b"label", &label.to_vec());
+    transcript
+}
+
+thread_local! {
+    /// The generators created so far, by capacities, so that they are
+    /// only derived on the first call for each proof size.
+    static GENERATORS: RefCell<BTreeMap<(usize, usize), Rc<BulletproofGens>>> =
+        RefCell::new(BTreeMap::new());
+}
+
+/// Returns generators for `m` values of bitsize `n`.
+fn generators(n: usize, m: usize) -> Result<Rc<BulletproofGens>, JsValue> {
+    let gens_capacity = util::padded_bitsize(n).ok_or(Error(ProofError::InvalidBitsize))?;
+    let party_capacity = m
+        .checked_next_power_of_two()
+        .ok_or(Error(ProofError::InvalidAggregation))?;
+    let capacities = (gens_capacity, party_capacity);
+
+    GENERATORS.with(|cache| -> Result<_, JsValue> {
+        let cached = cache.borrow().get(&capacities).cloned();
+        if let Some(bp_gens) = cached {
+            return Ok(bp_gens);
+        }
+        let bp_gens = BulletproofGens::try_new(gens_capacity, party_capacity)
+            .map_err(|_| Error(ProofError::InvalidGeneratorsLength))?;
+        let bp_gens = Rc::new(bp_gens);
+        cache.borrow_mut().insert(capacities, bp_gens.clone());
+        Ok(bp_gens)
+    })
+}
+
+/// Creates a range proof that `value` fits in `n` bits, returning an
+/// array of the proof and the 32-byte commitment to `value` with the
+/// 32-byte scalar `blinding`.
+#[wasm_bindgen(js_name = proveSingle)]
+pub fn prove_single(
+    label: &Uint8Array,
+    value: &BigInt,
+    blinding: &Uint8Array,
+    n: usize,
+) -> Result<Array, JsValue> {
+    prove_multiple(label, &Array::of1(value), blinding, n)
+}
+
+/// Creates an aggregated range proof that each of the `values` fits
+/// in `n` bits, returning an array of the proof and the concatenated
+/// commitments to the values with the concatenated 32-byte scalars
+/// `blindings`.
+#[wasm_bindgen(js_name = proveMultiple)]
+pub fn prove_multiple(
+    label: &Uint8Array,
+    values: &Array,
+    blindings: &Uint8Array,
+    n: usize,
+) -> Result<Array, JsValue> {
+    let values = values
+        .iter()
+        .map(|value| {
+            let value: BigInt = value
+                .dyn_into()
+                .map_err(|_| JsValue::from_str("Values must be BigInts."))?;
+            Value::try_from(&value).map(|value| value.0)
+        })
+        .collect::<Result<Vec<u64>, JsValue>>()?;
+    let Scalars(blindings) = Scalars::try_from(blindings)?;
+
+    let bp_gens = generators(n, values.len())?;
+    let (proof, commitments) = RangeProof::prove_multiple(
+        &bp_gens,
+        &PedersenGens::default(),
+        &mut transcript(label),
+        &values,
+        &blindings,
+        n,
+    )
+    .map_err(Error)?;
+
+    Ok(Array::of2(
+        &Uint8Array::from(&proof.to_bytes()[..]),
+        &Uint8Array::from(CommitmentBytes(commitments)),
+    ))
+}
+
+/// Verifies a range proof that the value committed to in the 32-byte
+/// `commitment` fits in `n` bits.
+#[wasm_bindgen(js_name = verifySingle)]
+pub fn verify_single(
+    label: &Uint8Array,
+    proof: &Uint8Array,
+    commitment: &Uint8Array,
+    n: usize,
+) -> Result<(), JsValue> {
+    let CommitmentBytes(commitments) = CommitmentBytes::try_from(commitment)?;
+    if commitments.len() != 1 {
+        return Err(Error(ProofError::FormatError).into());
+    }
+    verify_multiple(label, proof, commitment, n)
+}
+
+/// Verifies an aggregated range proof that the values committed to in
+/// the concatenated `commitments` fit in `n` bits.
+#[wasm_bindgen(js_name = verifyMultiple)]
+pub fn verify_multiple(
+    label: &Uint8Array,
+    proof: &Uint8Array,
+    commitments: &Uint8Array,
+    n: usize,
+) -> Result<(), JsValue> {
+    let proof = RangeProof::from_bytes(&proof.to_vec()).map_err(Error)?;
+    let CommitmentBytes(commitments) = CommitmentBytes::try_from(commitments)?;
+
+    let bp_gens = generators(n, commitments.len())?;
+    proof
+        .verify_multiple(
+            &bp_gens,
+            &PedersenGens::default(),
+            &mut transcript(label),
+            &commitments,
+            n,
+        )
+        .map_err(|e| Error(e).into())
+}
@@ -0,0 +1,224 @@
+// Counts the heap allocations and measures the peak heap usage of
+// verification, so that regressions in the verifier's memory layout
+// are caught.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use rand_core::SeedableRng;
+
+use rand_chacha::ChaChaRng;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+
+/// Forwards to the system allocator, counting the allocations made by
+/// the current thread while counting is enabled, and tracking the bytes
+/// it holds while measuring is enabled.
+struct CountingAllocator;
+
+thread_local! {
+    // `None` while counting is disabled.  The cell needs no
+    // destructor, so accessing it never allocates.
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+    // The current and peak bytes allocated since measuring was
+    // enabled, or `None` while it is disabled.
+    static MEMORY: Cell<Option<(isize, isize)>> = const { Cell::new(None) };
+}
+
+fn record_allocation(bytes: isize) {
+    let _ = ALLOCATIONS.try_with(|count| {
+        if let Some(n) = count.get() {
+            count.set(Some(n + 1));
+        }
+    });
+    record_bytes(bytes);
+}
+
+fn record_bytes(bytes: isize) {
+    let _ = MEMORY.try_with(|memory| {
+        if let Some((current, peak)) = memory.get() {
+            let current = current + bytes;
+            memory.set(Some((current, peak.max(current))));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_allocation(layout.size() as isize);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // The old and new buffers may both be live while copying.
+        record_allocation(new_size as isize);
+        record_bytes(-(layout.size() as isize));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record_bytes(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `f`, returning its result and the number of allocations it
+/// made on the current thread.
+fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    ALLOCATIONS.with(|count| count.set(Some(0)));
+    let result = f();
+    let count = ALLOCATIONS.with(|count| count.replace(None)).unwrap();
+    (result, count)
+}
+
+/// Runs `f`, returning its result and the peak number of bytes it held
+/// allocated on the current thread.
+fn peak_memory<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    MEMORY.with(|memory| memory.set(Some((0, 0))));
+    let result = f();
+    let (_, peak) = MEMORY.with(|memory| memory.replace(None)).unwrap();
+    (result, peak as usize)
+}
+
+/// The maximum number of allocations for verifying a single `(64, 1)`
+/// proof.  The verifier allocates a fixed number of buffers per proof,
+/// so exceeding this indicates per-element allocations.
+const MAX_ALLOCATIONS_64_1: usize = 64;
+
+fn prove_64_1(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    rng: &mut ChaChaRng,
+) -> (RangeProof, CompressedRistretto) {
+    let mut transcript = Transcript::new(b"AllocationTest");
+    RangeProof::prove_single_with_rng(
+        bp_gens,
+        pc_gens,
+        &mut transcript,
+        1037578891,
+        &Scalar::random(rng),
+        64,
+        rng,
+    )
+    .unwrap()
+}
+
+#[test]
+fn verify_allocations() {
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(64, 1);
+    let mut rng = ChaChaRng::from_seed([3u8; 32]);
+
+    let (proof, commitment) = prove_64_1(&bp_gens, &pc_gens, &mut rng);
+    let mut transcript = Transcript::new(b"AllocationTest");
+    let (result, single) = count_allocations(|| {
+        proof.verify_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[commitment],
+            64,
+            &mut rng,
+        )
+    });
+    assert!(result.is_ok());
+    assert!(
+        single <= MAX_ALLOCATIONS_64_1,
+        "verify_multiple made {} allocations, expected at most {}",
+        single,
+        MAX_ALLOCATIONS_64_1
+    );
+
+    // Batching must not allocate more per proof than verifying alone.
+    let proofs: Vec<_> = (0..16)
+        .map(|_| prove_64_1(&bp_gens, &pc_gens, &mut rng))
+        .collect();
+    let mut transcripts = vec![Transcript::new(b"AllocationTest"); proofs.len()];
+    let (result, batch) = count_allocations(|| {
+        RangeProof::verify_batch_with_rng(
+            proofs
+                .iter()
+                .zip(&mut transcripts)
+                .map(|((proof, commitment), transcript)| {
+                    proof.verification_view(transcript, std::slice::from_ref(commitment), 64)
+                }),
+            &bp_gens,
+            &pc_gens,
+            &mut rng,
+        )
+    });
+    assert!(result.is_ok());
+    assert!(
+        batch <= proofs.len() * single,
+        "verify_batch made {} allocations for {} proofs, expected at most {}",
+        batch,
+        proofs.len(),
+        proofs.len() * single
+    );
+}
+
+/// Proves `m` values of 64 bits and returns the peak memory of
+/// verifying the proof.
+fn verify_peak_memory(m: usize, rng: &mut ChaChaRng) -> usize {
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(64, m);
+    let values: Vec<u64> = (0..m as u64).collect();
+    let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(rng)).collect();
+
+    let mut transcript = Transcript::new(b"PeakMemoryTest");
+    let (proof, commitments) = RangeProof::prove_multiple_with_rng(
+        &bp_gens,
+        &pc_gens,
+        &mut transcript,
+        &values,
+        &blindings,
+        64,
+        rng,
+    )
+    .unwrap();
+
+    let mut transcript = Transcript::new(b"PeakMemoryTest");
+    let (result, peak) = peak_memory(|| {
+        proof.verify_multiple_with_rng(&bp_gens, &pc_gens, &mut transcript, &commitments, 64, rng)
+    });
+    assert!(result.is_ok());
+    peak
+}
+
+// The proof itself only has 2 lg(nm) + 9 elements, so the verifier's
+// memory is dominated by the scalars of the nm generators and the
+// multiscalar multiplication over them, and must grow linearly in nm.
+#[test]
+fn verify_peak_memory_is_linear() {
+    let mut rng = ChaChaRng::from_seed([4u8; 32]);
+
+    let small = verify_peak_memory(4, &mut rng);
+    let large = verify_peak_memory(32, &mut rng);
+    // 8 times as many generators, with some slack for the buffers of
+    // the multiscalar multiplication, whose capacity grows by doubling.
+    assert!(
+        large <= 10 * small,
+        "verifying 32 values peaked at {} bytes, more than 10 times the {} bytes for 4 values",
+        large,
+        small
+    );
+    // At most 1 KiB for each of the 2 * 64 * 32 generators.
+    assert!(
+        large <= 1024 * 2 * 64 * 32,
+        "verifying 32 values peaked at {} bytes",
+        large
+    );
+}
@@ -13,6 +13,8 @@ use hex;
 use serde_json::json;
 
 // Tests that proofs generated with v1.0.0 continue to verify in later versions.
+// They predate the protocol version in the transcript, so they are version 0
+// proofs.
 #[test]
 fn deserialize_and_verify() {
     // proofs[i][j] has bitsize n = 8 << i, aggregation size m = 1 << j
@@ -91,9 +93,15 @@ fn deserialize_and_verify() {
                 .expect("Rangeproof deserialization failed");
             let mut transcript = Transcript::new(b"Deserialize-And-Verify Test");
             assert_eq!(
-                proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &vc[0..m], n,),
+                proof.verify_multiple_v0(&bp_gens, &pc_gens, &mut transcript, &vc[0..m], n),
                 Ok(())
             );
+
+            // The current verification path rejects them.
+            let mut transcript = Transcript::new(b"Deserialize-And-Verify Test");
+            assert!(proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &vc[0..m], n)
+                .is_err());
         }
     }
 }
@@ -119,7 +127,7 @@ fn generate_test_vectors() {
     for n in &[8, 16, 32, 64] {
         for m in &[1, 2, 4, 8] {
             let mut transcript = Transcript::new(b"Deserialize-And-Verify Test");
-            let (proof, value_commitments) = RangeProof::prove_multiple(
+            let (proof, value_commitments) = RangeProof::prove_multiple_v0(
                 &bp_gens,
                 &pc_gens,
                 &mut transcript,
@@ -151,4 +159,45 @@ fn test_serde() {
     let deserialized: RangeProof = serde_json::from_value(serialized).unwrap();
 
     assert_eq!(proof.to_bytes(), deserialized.to_bytes());
-}
\ No newline at end of file
+}
+
+#[test]
+fn gens_for_dimensions_verify_all_proofs() {
+    let dims = [(8, 1), (64, 2), (32, 4), (16, 8)];
+
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::for_dimensions(&dims);
+    assert_eq!(bp_gens.gens_capacity, 64);
+    assert_eq!(bp_gens.party_capacity, 8);
+
+    let mut rng = ChaChaRng::from_seed([42u8; 32]);
+    for &(n, m) in dims.iter() {
+        let values: Vec<u64> = (0..m as u64).collect();
+        let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"ForDimensionsTest");
+        let (proof, commitments) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            n,
+            &mut rng,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"ForDimensionsTest");
+        assert_eq!(
+            proof.verify_multiple_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &commitments,
+                n,
+                &mut rng
+            ),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Round trips through the JavaScript bindings, run with
+//! `wasm-pack test --node -- --features wasm`.
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Array, BigInt, Uint8Array};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+use bulletproofs::wasm::{prove_multiple, prove_single, verify_multiple, verify_single};
+
+fn blinding(byte: u8) -> Vec<u8> {
+    // A canonical scalar: the top byte is small.
+    let mut bytes = vec![byte; 32];
+    bytes[31] = 0;
+    bytes
+}
+
+fn split(result: Array) -> (Uint8Array, Uint8Array) {
+    (
+        result.get(0).dyn_into().unwrap(),
+        result.get(1).dyn_into().unwrap(),
+    )
+}
+
+#[wasm_bindgen_test]
+fn single_round_trip() {
+    let label = Uint8Array::from(&b"WasmTest"[..]);
+    let (proof, commitment) = split(
+        prove_single(
+            &label,
+            &BigInt::from(u64::MAX),
+            &Uint8Array::from(&blinding(7)[..]),
+            64,
+        )
+        .unwrap(),
+    );
+    assert_eq!(commitment.length(), 32);
+    assert!(verify_single(&label, &proof, &commitment, 64).is_ok());
+
+    // A different label, or bitsize, fails.
+    let other = Uint8Array::from(&b"OtherTest"[..]);
+    assert!(verify_single(&other, &proof, &commitment, 64).is_err());
+    assert!(verify_single(&label, &proof, &commitment, 32).is_err());
+
+    // Values out of range, malformed values and blindings are rejected.
+    assert!(prove_single(
+        &label,
+        &BigInt::from(1u64 << 40),
+        &Uint8Array::from(&blinding(7)[..]),
+        32
+    )
+    .is_err());
+    assert!(prove_single(
+        &label,
+        &BigInt::from(5u64),
+        &Uint8Array::from(&[0xffu8; 32][..]),
+        32
+    )
+    .is_err());
+    assert!(prove_single(
+        &label,
+        &BigInt::from(-1i64),
+        &Uint8Array::from(&blinding(7)[..]),
+        32
+    )
+    .is_err());
+}
+
+#[wasm_bindgen_test]
+fn multiple_round_trip() {
+    let label = Uint8Array::from(&b"WasmTest"[..]);
+    let values = Array::of3(
+        &BigInt::from(1u64),
+        &BigInt::from(2u64),
+        &BigInt::from(3u64),
+    );
+    let blindings: Vec<u8> = [blinding(1), blinding(2), blinding(3)].concat();
+    let (proof, commitments) =
+        split(prove_multiple(&label, &values, &Uint8Array::from(&blindings[..]), 32).unwrap());
+    assert_eq!(commitments.length(), 3 * 32);
+    assert!(verify_multiple(&label, &proof, &commitments, 32).is_ok());
+
+    // Dropping a commitment fails, and the error is a message string.
+    let error = verify_multiple(&label, &proof, &commitments.subarray(0, 64), 32).unwrap_err();
+    assert!(error.as_string().is_some());
+}
@@ -0,0 +1,86 @@
+// Checks that the secret vectors of the parties' intermediate states
+// are wiped before their memory is returned to the allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use rand_core::SeedableRng;
+
+use rand_chacha::ChaChaRng;
+
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use bulletproofs::range_proof_mpc::{dealer::Dealer, party::Party};
+use bulletproofs::{BulletproofGens, PedersenGens};
+
+/// Forwards to the system allocator, counting the buffers freed by the
+/// current thread while checking is enabled, and how many of them still
+/// held non-zero bytes.
+struct CheckingAllocator;
+
+thread_local! {
+    // `None` while checking is disabled.  The cell needs no destructor,
+    // so accessing it never allocates.
+    static FREES: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+unsafe impl GlobalAlloc for CheckingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = FREES.try_with(|frees| {
+            if let Some((freed, dirty)) = frees.get() {
+                let bytes = std::slice::from_raw_parts(ptr, layout.size());
+                let is_dirty = bytes.iter().any(|&b| b != 0);
+                frees.set(Some((freed + 1, dirty + is_dirty as usize)));
+            }
+        });
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CheckingAllocator = CheckingAllocator;
+
+/// Drops `value`, returning the number of buffers freed on the current
+/// thread and how many of them were not zeroed.
+fn check_frees<T>(value: T) -> (usize, usize) {
+    FREES.with(|frees| frees.set(Some((0, 0))));
+    drop(value);
+    FREES.with(|frees| frees.replace(None)).unwrap()
+}
+
+#[test]
+fn allocator_detects_unzeroed_buffers() {
+    let secrets = vec![Scalar::from(7u64); 4];
+    assert_eq!(check_frees(secrets), (1, 1));
+}
+
+#[test]
+fn party_states_wipe_their_vectors() {
+    let (n, m) = (32, 2);
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(n, m);
+    let mut rng = ChaChaRng::from_seed([5u8; 32]);
+
+    let mut transcript = Transcript::new(b"ZeroizeTest");
+    let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+    let party0 = Party::new(&bp_gens, &pc_gens, 7, Scalar::random(&mut rng), n).unwrap();
+    let party1 = Party::new(&bp_gens, &pc_gens, 9, Scalar::random(&mut rng), n).unwrap();
+    let (party0, bit_com0) = party0.assign_position_with_rng(0, &mut rng).unwrap();
+    let (party1, bit_com1) = party1.assign_position_with_rng(1, &mut rng).unwrap();
+    let (_, bit_challenge) = dealer
+        .receive_bit_commitments(vec![bit_com0, bit_com1])
+        .unwrap();
+
+    // The bit challenge state holds the blinding vectors s_L and s_R.
+    assert_eq!(check_frees(party1), (2, 0));
+
+    // The polynomial challenge state holds the vector polynomials l and r.
+    let (party0, _) = party0.apply_challenge_with_rng(&bit_challenge, &mut rng);
+    assert_eq!(check_frees(party0), (4, 0));
+}